@@ -1,4 +1,3 @@
-use core::time;
 use std::{
     collections::{HashMap, VecDeque},
     env,
@@ -6,13 +5,13 @@ use std::{
 };
 
 use gouda::{
-    camera::{Camera, OrthographicCamera},
-    ecs::{Entity, GameSceneId, Mutation, Mutations, ECS},
+    camera::OrthographicCamera,
+    ecs::{commands::Commands, Entity, GameSceneId, Mutation, Mutations, ECS},
     input::{GameInput, SpecialKeys},
     rendering::{sprites::{ColorBoxComponent, SpriteSheetComponent}, Scene},
-    transform::{self, TransformComponent},
+    transform::TransformComponent,
     window::WindowProps,
-    GameLogic, GameScene, Gouda, QuitEvent, RenderLayer,
+    GameLogic, GameScene, Gouda, RenderLayer,
 };
 
 pub const START_MENU_SCENE: GameSceneId = 0;
@@ -58,13 +57,7 @@ impl Bird {
         let sprite = SpriteSheetComponent::new(ecs, "./assets/bitmap/cheese.png".to_owned(), 1, 2);
         let animation = Animation { frame_duration_ms: 200., countdown: 0. };
         ecs.build_entity()
-            .add_component(collider)
-            .add_component(velocity)
-            .add_component(gravity)
-            .add_component(transform)
-            .add_component(sprite)
-            .add_component(animation)
-            .add_component(Bird {});
+            .add_bundle((collider, velocity, gravity, transform, sprite, animation, Bird {}));
     }
 }
 
@@ -83,10 +76,7 @@ impl Floor {
             height: 3.,
         };
         ecs.build_entity()
-            .add_component(collider)
-            .add_component(transform)
-            .add_component(color)
-            .add_component(Floor {});
+            .add_bundle((collider, transform, color, Floor {}));
     }
 }
 
@@ -107,24 +97,11 @@ impl Mutation for ApplyGravityMutation {
     }
 }
 
-struct ApplyMovementMutation {
-    entity: Entity,
-    dy: f32,
-    dx: f32,
-}
-
-impl Mutation for ApplyMovementMutation {
-    fn apply(&self, ecs: &mut ECS) {
-        let transform = ecs.write::<TransformComponent>(&self.entity).unwrap();
-        transform.change_pos(self.dx, self.dy);
-    }
-}
-
 pub fn gravity_system(ecs: &ECS, dt: f32) -> Mutations {
     let mut mutations: Mutations = Vec::new();
 
     for (_, gravity, ent) in ecs.read2::<Velocity, HasGravity>() {
-        if let None = ecs.read::<DetectedCollision>(&ent) {
+        if ecs.read::<DetectedCollision>(&ent).is_none() {
             mutations.push(Box::new(ApplyGravityMutation {
                 entity: ent,
                 dy: gravity.gravity * dt,
@@ -135,15 +112,14 @@ pub fn gravity_system(ecs: &ECS, dt: f32) -> Mutations {
 }
 
 pub fn apply_velocity_system(ecs: &ECS, dt: f32) -> Mutations {
-    let mut mutations: Mutations = Vec::new();
+    let mut commands = Commands::new();
     for (vel, ent) in ecs.read1::<Velocity>() {
-        mutations.push(Box::new(ApplyMovementMutation {
-            entity: ent,
-            dy: vel.dy * dt,
-            dx: vel.dx * dt,
-        }));
+        let (dx, dy) = (vel.dx * dt, vel.dy * dt);
+        commands.update(ent, move |transform: &mut TransformComponent| {
+            transform.change_pos(dx, dy)
+        });
     }
-    return mutations;
+    return commands.into();
 }
 
 fn collides(
@@ -205,24 +181,12 @@ pub fn collision_detection_system(ecs: &ECS, _dt: f32) -> Mutations {
     return mutations;
 }
 
-struct CollisionCleanupMutation {
-    e: Entity,
-}
-
-impl Mutation for CollisionCleanupMutation {
-    fn apply(&self, ecs: &mut ECS) {
-        ecs.remove_component::<DetectedCollision>(&self.e);
-    }
-}
-
 pub fn collision_cleanup_system(ecs: &ECS, _dt: f32) -> Mutations {
-    let collisions = ecs.read1::<DetectedCollision>();
-    let mut mutations: Mutations = Vec::new();
-    for collision in collisions {
-        mutations.push(Box::new(CollisionCleanupMutation { e: collision.1 }));
+    let mut commands = Commands::new();
+    for (_, entity) in ecs.read1::<DetectedCollision>() {
+        commands.remove::<DetectedCollision>(entity);
     }
-
-    return mutations;
+    return commands.into();
 }
 
 struct JumpMutation {
@@ -297,10 +261,7 @@ impl Pipe {
         let color = ColorBoxComponent::new([0.5, 0.5, 0.5]);
         let velocity = Velocity { dx: -3., dy: 0. };
         ecs.build_entity()
-            .add_component(velocity)
-            .add_component(Self::generate_transform())
-            .add_component(color)
-            .add_component(Pipe {})
+            .add_bundle((velocity, Self::generate_transform(), color, Pipe {}))
             .entity()
     }
 
@@ -402,23 +363,23 @@ pub fn animation_system(ecs: &ECS, dt:  f32) -> Mutations {
 pub struct GameOverScene {}
 
 impl GameScene for GameOverScene {
-    fn on_scene_start(&self, ecs: &mut ECS) {
+    fn on_scene_start(&self, _ecs: &mut ECS) {
         todo!()
     }
 
-    fn on_scene_stop(&self, ecs: &mut ECS) {
+    fn on_scene_stop(&self, _ecs: &mut ECS) {
         todo!()
     }
 
-    fn render_scene(&self, ecs: &ECS, scene: &Scene) {
+    fn render_scene(&self, _ecs: &ECS, _scene: &Scene) {
         todo!()
     }
 
-    fn next_scene(&self, ecs: &ECS) -> Option<GameSceneId> {
+    fn next_scene(&self, _ecs: &ECS) -> Option<GameSceneId> {
         todo!()
     }
 
-    fn active_layers(&self, ecs: &ECS) -> Vec<RenderLayer> {
+    fn active_layers(&self, _ecs: &ECS) -> Vec<RenderLayer> {
         todo!()
     }
 }
@@ -449,7 +410,7 @@ impl GameScene for MainGameScene {
         //
     }
 
-    fn on_scene_stop(&self, ecs: &mut ECS) {}
+    fn on_scene_stop(&self, _ecs: &mut ECS) {}
 
     fn render_scene(&self, ecs: &ECS, scene: &Scene) {
         // Draw background
@@ -457,15 +418,15 @@ impl GameScene for MainGameScene {
         // Draw bird
         // Draw pipes
         for (location, color_box, _) in ecs.read2::<TransformComponent, ColorBoxComponent>() {
-            color_box.draw(&scene, location);
+            color_box.draw(scene, location);
         }
 
         for (location, spritesheet, _) in ecs.read2::<TransformComponent, SpriteSheetComponent>() {
-            spritesheet.draw(&scene, location);
+            spritesheet.draw(scene, location);
         }
     }
 
-    fn next_scene(&self, ecs: &ECS) -> Option<u32> {
+    fn next_scene(&self, _ecs: &ECS) -> Option<u32> {
         return None;
     }
 
@@ -491,9 +452,7 @@ impl GameLogic for Game {
         }
     }
 
-    fn register_events(&self, ecs: &mut ECS) {}
-
-    fn migrate_events(&self, ecs: &mut ECS) {}
+    fn register_events(&self, _ecs: &mut ECS) {}
 
     fn game_scenes(&self) -> HashMap<GameSceneId, Box<dyn GameScene>> {
         let mut res: HashMap<GameSceneId, Box<dyn GameScene>> = HashMap::new();
@@ -505,7 +464,7 @@ impl GameLogic for Game {
         return START_MENU_SCENE;
     }
 
-    fn setup(&mut self, ecs: &mut ECS) {}
+    fn setup(&mut self, _ecs: &mut ECS) {}
 }
 
 fn main() {
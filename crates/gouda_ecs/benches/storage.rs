@@ -0,0 +1,147 @@
+//! Compares query performance against the old storage, where every component type kept a
+//! `GenIndexArray` slot for every entity and joins scanned the whole index range.
+//!
+//! Run with `cargo bench -p gouda_ecs`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use gouda_ecs::genindex::{GenIndexAllocator, GenIndexArray};
+use gouda_ecs::{Entity, ECS};
+
+#[derive(Debug)]
+struct Position(f32);
+
+#[derive(Debug)]
+struct Velocity(f32);
+
+#[derive(Debug)]
+struct Health(f32);
+
+const SIZES: [usize; 2] = [10_000, 100_000];
+
+/// How the world looked before the storage redesign.
+struct LegacyWorld {
+    positions: GenIndexArray<Position>,
+    velocities: GenIndexArray<Velocity>,
+    healths: GenIndexArray<Health>,
+}
+
+impl LegacyWorld {
+    fn read2(&self) -> Vec<(&Position, &Velocity, Entity)> {
+        let len = self.positions.0.len().min(self.velocities.0.len());
+        let mut res = Vec::new();
+        for i in 0..len {
+            if let (Some(Some(p)), Some(Some(v))) =
+                (self.positions.0.get(i), self.velocities.0.get(i))
+            {
+                if p.generation == v.generation {
+                    let e = Entity {
+                        index: i,
+                        generation: p.generation,
+                    };
+                    res.push((&p.value, &v.value, e));
+                }
+            }
+        }
+        return res;
+    }
+
+    fn get3(&self) -> Vec<Entity> {
+        let len = self
+            .positions
+            .0
+            .len()
+            .min(self.velocities.0.len())
+            .min(self.healths.0.len());
+        let mut res = Vec::new();
+        for i in 0..len {
+            if let (Some(Some(p)), Some(Some(v)), Some(Some(h))) = (
+                self.positions.0.get(i),
+                self.velocities.0.get(i),
+                self.healths.0.get(i),
+            ) {
+                if p.generation == v.generation && v.generation == h.generation {
+                    res.push(Entity {
+                        index: i,
+                        generation: p.generation,
+                    });
+                }
+            }
+        }
+        return res;
+    }
+}
+
+/// Every entity has a position, every other one a velocity and every tenth one health.
+fn populate(count: usize) -> (ECS, LegacyWorld) {
+    let mut ecs = ECS::new();
+    let mut allocator = GenIndexAllocator::new();
+    let mut legacy = LegacyWorld {
+        positions: GenIndexArray::new(),
+        velocities: GenIndexArray::new(),
+        healths: GenIndexArray::new(),
+    };
+    for i in 0..count {
+        let entity = ecs.new_entity();
+        let legacy_entity = allocator.allocate();
+        ecs.add_component(&entity, Position(i as f32));
+        legacy.positions.set(legacy_entity, Position(i as f32));
+        if i % 2 == 0 {
+            ecs.add_component(&entity, Velocity(1.));
+            legacy.velocities.set(legacy_entity, Velocity(1.));
+        }
+        if i % 10 == 0 {
+            ecs.add_component(&entity, Health(100.));
+            legacy.healths.set(legacy_entity, Health(100.));
+        }
+    }
+    return (ecs, legacy);
+}
+
+fn bench_read2(c: &mut Criterion) {
+    let mut group = c.benchmark_group("read2");
+    for size in SIZES {
+        let (ecs, legacy) = populate(size);
+        group.bench_with_input(BenchmarkId::new("legacy", size), &size, |b, _| {
+            b.iter(|| black_box(legacy.read2().len()))
+        });
+        group.bench_with_input(BenchmarkId::new("read2", size), &size, |b, _| {
+            b.iter(|| black_box(ecs.read2::<Position, Velocity>().len()))
+        });
+        group.bench_with_input(BenchmarkId::new("query", size), &size, |b, _| {
+            b.iter(|| {
+                let mut sum = 0.;
+                for (p, v) in ecs.query::<(&Position, &Velocity)>() {
+                    sum += p.0 * v.0;
+                }
+                black_box(sum)
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_get3(c: &mut Criterion) {
+    let mut group = c.benchmark_group("get3");
+    for size in SIZES {
+        let (ecs, legacy) = populate(size);
+        group.bench_with_input(BenchmarkId::new("legacy", size), &size, |b, _| {
+            b.iter(|| black_box(legacy.get3().len()))
+        });
+        group.bench_with_input(BenchmarkId::new("get3", size), &size, |b, _| {
+            b.iter(|| black_box(ecs.get3::<Position, Velocity, Health>().len()))
+        });
+        group.bench_with_input(BenchmarkId::new("query", size), &size, |b, _| {
+            b.iter(|| {
+                let mut total = 0.;
+                for (_, _, _, health) in ecs.query::<(Entity, &Position, &Velocity, &Health)>() {
+                    total += health.0;
+                }
+                black_box(total)
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_read2, bench_get3);
+criterion_main!(benches);
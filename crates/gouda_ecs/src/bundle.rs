@@ -0,0 +1,38 @@
+use crate::{Entity, ECS};
+use std::fmt::Debug;
+
+/// A group of components added to an entity together. Implemented for tuples of up to 12
+/// components, and can be implemented for structs by adding each field.
+pub trait Bundle: 'static {
+    fn add_to(self, ecs: &mut ECS, entity: &Entity);
+}
+
+macro_rules! impl_bundle_tuple {
+    ( $($c:ident),* ) => {
+        impl<$($c: 'static + Debug),*> Bundle for ($($c,)*) {
+            fn add_to(self, ecs: &mut ECS, entity: &Entity) {
+                let ($($c,)*) = self;
+                $(
+                    ecs.add_component(entity, $c);
+                )*
+            }
+        }
+    };
+}
+
+impl Bundle for () {
+    fn add_to(self, _ecs: &mut ECS, _entity: &Entity) {}
+}
+
+impl_bundle_tuple!(c1);
+impl_bundle_tuple!(c1, c2);
+impl_bundle_tuple!(c1, c2, c3);
+impl_bundle_tuple!(c1, c2, c3, c4);
+impl_bundle_tuple!(c1, c2, c3, c4, c5);
+impl_bundle_tuple!(c1, c2, c3, c4, c5, c6);
+impl_bundle_tuple!(c1, c2, c3, c4, c5, c6, c7);
+impl_bundle_tuple!(c1, c2, c3, c4, c5, c6, c7, c8);
+impl_bundle_tuple!(c1, c2, c3, c4, c5, c6, c7, c8, c9);
+impl_bundle_tuple!(c1, c2, c3, c4, c5, c6, c7, c8, c9, c10);
+impl_bundle_tuple!(c1, c2, c3, c4, c5, c6, c7, c8, c9, c10, c11);
+impl_bundle_tuple!(c1, c2, c3, c4, c5, c6, c7, c8, c9, c10, c11, c12);
@@ -0,0 +1,230 @@
+use crate::bundle::Bundle;
+use crate::parallel::SendMutations;
+use crate::{Entity, Mutation, Mutations, ECS};
+use std::cell::RefCell;
+use std::fmt::Debug;
+
+/// An entity spawned by an earlier command in the same `Commands` buffer. It only means something
+/// to that buffer.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct ReservedEntity(usize);
+
+/// The entity a command applies to: either one that already exists or one spawned earlier in the
+/// same buffer.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum CommandTarget {
+    Existing(Entity),
+    Reserved(ReservedEntity),
+}
+
+impl From<Entity> for CommandTarget {
+    fn from(entity: Entity) -> Self {
+        CommandTarget::Existing(entity)
+    }
+}
+
+impl From<&Entity> for CommandTarget {
+    fn from(entity: &Entity) -> Self {
+        CommandTarget::Existing(*entity)
+    }
+}
+
+impl From<ReservedEntity> for CommandTarget {
+    fn from(entity: ReservedEntity) -> Self {
+        CommandTarget::Reserved(entity)
+    }
+}
+
+/// The entities spawned so far while a `Commands` buffer is being applied.
+pub struct Spawned(Vec<Entity>);
+
+impl Spawned {
+    pub fn get(&self, entity: ReservedEntity) -> Entity {
+        self.0[entity.0]
+    }
+
+    pub fn resolve(&self, target: CommandTarget) -> Entity {
+        match target {
+            CommandTarget::Existing(entity) => entity,
+            CommandTarget::Reserved(entity) => self.get(entity),
+        }
+    }
+}
+
+type Command = Box<dyn FnOnce(&mut ECS, &mut Spawned) + Send>;
+
+/// A list of changes to the world that a system can return instead of hand-written mutations.
+/// Commands are applied in the order they were added. Anything moved into the buffer has to be
+/// `Send` so parallel systems can return it too. Convert it with `into()` to return it.
+#[derive(Default)]
+pub struct Commands {
+    commands: RefCell<Vec<Command>>,
+    reserved: usize,
+}
+
+impl Commands {
+    pub fn new() -> Commands {
+        Commands::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.borrow().is_empty()
+    }
+
+    fn push(&mut self, command: impl FnOnce(&mut ECS, &mut Spawned) + Send + 'static) {
+        self.commands.get_mut().push(Box::new(command));
+    }
+
+    /// Spawns an entity with every component in `bundle`. The returned handle can be passed to
+    /// later commands in this buffer.
+    pub fn spawn<B: Bundle + Send>(&mut self, bundle: B) -> ReservedEntity {
+        let reserved = ReservedEntity(self.reserved);
+        self.reserved += 1;
+        self.push(move |ecs, spawned| {
+            let entity = ecs.new_entity();
+            bundle.add_to(ecs, &entity);
+            spawned.0.push(entity);
+        });
+        return reserved;
+    }
+
+    pub fn despawn(&mut self, entity: impl Into<CommandTarget>) {
+        let target = entity.into();
+        self.push(move |ecs, spawned| ecs.delete_entity(&spawned.resolve(target)));
+    }
+
+    /// Adds or replaces a component.
+    pub fn insert<T: 'static + Debug + Send>(
+        &mut self,
+        entity: impl Into<CommandTarget>,
+        component: T,
+    ) {
+        let target = entity.into();
+        self.push(move |ecs, spawned| ecs.add_component(&spawned.resolve(target), component));
+    }
+
+    pub fn remove<T: 'static + Debug>(&mut self, entity: impl Into<CommandTarget>) {
+        let target = entity.into();
+        self.push(move |ecs, spawned| ecs.remove_component::<T>(&spawned.resolve(target)));
+    }
+
+    /// Changes a component in place. Does nothing if the entity doesn't have one.
+    pub fn update<T: 'static, F: FnOnce(&mut T) + Send + 'static>(
+        &mut self,
+        entity: impl Into<CommandTarget>,
+        f: F,
+    ) {
+        let target = entity.into();
+        self.push(move |ecs, spawned| {
+            if let Some(component) = ecs.write::<T>(&spawned.resolve(target)) {
+                f(component);
+            }
+        });
+    }
+
+    /// Adds or replaces a resource.
+    pub fn set_res<T: 'static + Send>(&mut self, value: T) {
+        self.push(move |ecs, _| ecs.add_res(value));
+    }
+
+    pub fn push_event<T: 'static + Send>(&mut self, event: T) {
+        self.push(move |ecs, _| ecs.push_event(event));
+    }
+
+    /// Runs arbitrary code against the world, for anything the other commands don't cover.
+    pub fn add<F: FnOnce(&mut ECS, &Spawned) + Send + 'static>(&mut self, f: F) {
+        self.push(move |ecs, spawned| f(ecs, spawned));
+    }
+}
+
+impl Mutation for Commands {
+    fn apply(&self, ecs: &mut ECS) {
+        let mut spawned = Spawned(Vec::with_capacity(self.reserved));
+        for command in self.commands.take() {
+            command(ecs, &mut spawned);
+        }
+    }
+}
+
+impl From<Commands> for Mutations {
+    fn from(commands: Commands) -> Self {
+        if commands.is_empty() {
+            return vec![];
+        }
+        return vec![Box::new(commands)];
+    }
+}
+
+impl From<Commands> for SendMutations {
+    fn from(commands: Commands) -> Self {
+        if commands.is_empty() {
+            return vec![];
+        }
+        return vec![Box::new(commands)];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Name(&'static str);
+
+    #[derive(Debug, PartialEq)]
+    struct Target(Entity);
+
+    struct Score(u32);
+
+    struct Scored;
+
+    #[test]
+    fn test_spawned_entities_can_be_referenced_later() {
+        let mut ecs = ECS::new();
+        let mut commands = Commands::new();
+        let turret = commands.spawn((Name("turret"),));
+        let arrow = commands.spawn((Name("arrow"),));
+        commands.add(move |ecs, spawned| {
+            let turret = spawned.get(turret);
+            ecs.add_component(&spawned.get(arrow), Target(turret));
+        });
+        commands.remove::<Name>(arrow);
+
+        let mutations: Mutations = commands.into();
+        for mutation in mutations {
+            mutation.apply(&mut ecs);
+        }
+
+        let (target, arrow) = ecs.read1::<Target>()[0];
+        assert!(ecs.read::<Name>(&arrow).is_none());
+        assert_eq!(ecs.read::<Name>(&target.0), Some(&Name("turret")));
+    }
+
+    #[test]
+    fn test_commands_edit_existing_entities_and_resources() {
+        let mut ecs = ECS::new();
+        ecs.register_event_type::<Scored>();
+        ecs.add_res(Score(0));
+        let keep = ecs.build_entity().add_component(Name("keep")).entity();
+        let gone = ecs.build_entity().add_component(Name("gone")).entity();
+
+        let mut commands = Commands::new();
+        commands.update::<Name, _>(keep, |name| name.0 = "kept");
+        commands.despawn(gone);
+        commands.set_res(Score(3));
+        commands.push_event(Scored);
+        commands.apply(&mut ecs);
+        ecs.update_events();
+
+        assert_eq!(ecs.read::<Name>(&keep), Some(&Name("kept")));
+        assert_eq!(ecs.get1::<Name>(), vec![keep]);
+        assert_eq!(ecs.read_res::<Score>().0, 3);
+        assert_eq!(ecs.events::<Scored>().len(), 1);
+    }
+
+    #[test]
+    fn test_empty_buffer_adds_no_mutations() {
+        let mutations: Mutations = Commands::new().into();
+        assert!(mutations.is_empty());
+    }
+}
@@ -0,0 +1,193 @@
+use crate::parallel::SystemAccess;
+use crate::Entity;
+use std::any::{type_name, TypeId};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// What to do when two mutations applied in the same stage write the same component of the same
+/// entity. Writes are adding, removing or `ECS::write`-ing a component; deleting an entity counts
+/// as removing each of its components.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum WriteConflicts {
+    Ignore,
+    Log,
+    Panic,
+}
+
+struct Writer {
+    mutation: usize,
+    system: String,
+}
+
+/// Remembers which mutation last wrote each entity/component pair during the current stage. In
+/// debug builds it also checks that mutations from parallel systems only write declared types,
+/// since batching trusts those declarations.
+pub(crate) struct WriteTracker {
+    pub(crate) mode: WriteConflicts,
+    writers: HashMap<(Entity, TypeId), Writer>,
+    mutations: usize,
+    current: Option<String>,
+    declared: Option<(String, Arc<SystemAccess>)>,
+}
+
+impl WriteTracker {
+    pub(crate) fn new() -> WriteTracker {
+        WriteTracker {
+            mode: WriteConflicts::Ignore,
+            writers: HashMap::new(),
+            mutations: 0,
+            current: None,
+            declared: None,
+        }
+    }
+
+    pub(crate) fn start_stage(&mut self) {
+        self.writers.clear();
+    }
+
+    /// Attributes every write until `end_mutation` to a new mutation returned by `system`.
+    /// `declared` is the access of a parallel system, which its writes are checked against.
+    pub(crate) fn begin_mutation(&mut self, system: &str, declared: Option<&Arc<SystemAccess>>) {
+        if cfg!(debug_assertions) {
+            self.declared = declared.map(|access| (system.to_string(), access.clone()));
+        }
+        if self.mode != WriteConflicts::Ignore {
+            self.mutations += 1;
+            self.current = Some(system.to_string());
+        }
+    }
+
+    pub(crate) fn end_mutation(&mut self) {
+        self.current = None;
+        self.declared = None;
+    }
+
+    /// Panics if the mutation being applied comes from a parallel system that didn't declare it
+    /// writes `T`. Only checked in debug builds.
+    pub(crate) fn check_declared<T: 'static>(&self) {
+        if let Some((system, access)) = &self.declared {
+            if !access.writes(TypeId::of::<T>()) {
+                panic!(
+                    "Parallel system {} writes {} without declaring it",
+                    system,
+                    type_name::<T>()
+                );
+            }
+        }
+    }
+
+    pub(crate) fn record<T: 'static>(&mut self, entity: Entity) {
+        self.check_declared::<T>();
+        let system = match &self.current {
+            Some(system) => system,
+            None => return,
+        };
+        let writer = Writer {
+            mutation: self.mutations,
+            system: system.clone(),
+        };
+        if let Some(previous) = self.writers.insert((entity, TypeId::of::<T>()), writer) {
+            if previous.mutation != self.mutations {
+                let message = format!(
+                    "Mutations from {} and {} both wrote {} on entity {:?} in the same stage",
+                    previous.system,
+                    system,
+                    type_name::<T>(),
+                    entity
+                );
+                match self.mode {
+                    WriteConflicts::Ignore => {}
+                    WriteConflicts::Log => eprintln!("{}", message),
+                    WriteConflicts::Panic => panic!("{}", message),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WriteConflicts;
+    use crate::schedule::Stage;
+    use crate::{Entity, Mutation, Mutations, ECS};
+
+    #[derive(Debug)]
+    struct Score(i32);
+
+    struct SetScore(Entity, i32);
+
+    impl Mutation for SetScore {
+        fn apply(&self, ecs: &mut ECS) {
+            ecs.write::<Score>(&self.0).unwrap().0 = self.1;
+            ecs.write::<Score>(&self.0).unwrap().0 += 1;
+        }
+    }
+
+    fn scoring_system(value: i32) -> Box<crate::System> {
+        Box::new(move |ecs: &ECS, _dt: f32| -> Mutations {
+            let mut mutations: Mutations = vec![];
+            for (_, entity) in ecs.read1::<Score>() {
+                mutations.push(Box::new(SetScore(entity, value)));
+            }
+            mutations
+        })
+    }
+
+    #[test]
+    #[should_panic(expected = "Mutations from first and second both wrote")]
+    fn test_same_stage_writes_panic() {
+        let mut ecs = ECS::new();
+        ecs.build_entity().add_component(Score(0));
+        ecs.set_write_conflicts(WriteConflicts::Panic);
+        ecs.register_system("first", scoring_system(1));
+        ecs.register_system("second", scoring_system(2));
+        ecs.run_systems(0.);
+    }
+
+    #[test]
+    fn test_writes_in_different_stages_are_allowed() {
+        let mut ecs = ECS::new();
+        ecs.build_entity().add_component(Score(0));
+        ecs.set_write_conflicts(WriteConflicts::Panic);
+        ecs.register_system("first", scoring_system(1));
+        ecs.register_system("second", scoring_system(2))
+            .stage(Stage::PostUpdate);
+        ecs.run_systems(0.);
+        ecs.run_systems(0.);
+
+        assert_eq!(ecs.read1::<Score>()[0].0 .0, 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "both wrote")]
+    fn test_deleting_written_entity_conflicts() {
+        struct Delete(Entity);
+
+        impl Mutation for Delete {
+            fn apply(&self, ecs: &mut ECS) {
+                ecs.delete_entity(&self.0);
+            }
+        }
+
+        let mut ecs = ECS::new();
+        let entity = ecs.build_entity().add_component(Score(0)).entity();
+        ecs.set_write_conflicts(WriteConflicts::Panic);
+        ecs.register_system("score", scoring_system(1));
+        ecs.register_system(
+            "cleanup",
+            Box::new(move |_ecs: &ECS, _dt: f32| -> Mutations { vec![Box::new(Delete(entity))] }),
+        );
+        ecs.run_systems(0.);
+    }
+
+    #[test]
+    fn test_conflicts_are_ignored_by_default() {
+        let mut ecs = ECS::new();
+        ecs.build_entity().add_component(Score(0));
+        ecs.register_system("first", scoring_system(1));
+        ecs.register_system("second", scoring_system(2));
+        ecs.run_systems(0.);
+
+        assert_eq!(ecs.read1::<Score>()[0].0 .0, 3);
+    }
+}
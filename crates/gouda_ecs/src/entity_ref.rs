@@ -0,0 +1,129 @@
+use crate::{Entity, ECS};
+use std::fmt;
+use std::fmt::Debug;
+
+/// A readable name for an entity, used by `EntityRef::debug_name`.
+#[derive(Debug, Clone)]
+pub struct DebugName(pub String);
+
+/// Read-only access to a single entity, for inspecting it while debugging.
+#[derive(Clone, Copy)]
+pub struct EntityRef<'a> {
+    ecs: &'a ECS,
+    entity: Entity,
+}
+
+impl<'a> EntityRef<'a> {
+    pub fn id(&self) -> Entity {
+        return self.entity;
+    }
+
+    /// False once the entity has been deleted, even if its slot has been reused.
+    pub fn is_alive(&self) -> bool {
+        return self.ecs.is_alive(&self.entity);
+    }
+
+    pub fn get<T: 'static + Debug>(&self) -> Option<&'a T> {
+        return self.ecs.read::<T>(&self.entity);
+    }
+
+    /// The type names of every component the entity has.
+    pub fn component_types(&self) -> Vec<&'static str> {
+        return self
+            .ecs
+            .component_types
+            .iter()
+            .filter(|component_type| (component_type.has)(self.ecs, &self.entity))
+            .map(|component_type| component_type.name)
+            .collect();
+    }
+
+    /// The entity's `DebugName` if it has one, otherwise its index and generation.
+    pub fn debug_name(&self) -> String {
+        return match self.get::<DebugName>() {
+            Some(name) => name.0.clone(),
+            None => format!("{}v{}", self.entity.index, self.entity.generation),
+        };
+    }
+}
+
+impl Debug for EntityRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = if self.is_alive() { "" } else { " (dead)" };
+        write!(
+            f,
+            "{}{} {:?}",
+            self.debug_name(),
+            state,
+            self.component_types()
+        )
+    }
+}
+
+impl ECS {
+    pub fn is_alive(&self, entity: &Entity) -> bool {
+        return self.entity_allocator.is_live(*entity);
+    }
+
+    pub fn entity(&self, entity: &Entity) -> EntityRef<'_> {
+        return EntityRef {
+            ecs: self,
+            entity: *entity,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DebugName;
+    use crate::ECS;
+
+    #[derive(Debug)]
+    struct Target(u32);
+
+    #[test]
+    fn test_stale_handles_read_nothing() {
+        let mut ecs = ECS::new();
+        let stale = ecs.build_entity().add_component(Target(1)).entity();
+        ecs.delete_entity(&stale);
+        let reused = ecs.build_entity().add_component(Target(2)).entity();
+
+        assert_eq!(stale.index, reused.index);
+        assert!(ecs.read::<Target>(&stale).is_none());
+        assert!(ecs.write::<Target>(&stale).is_none());
+        assert!(!ecs.entity(&stale).is_alive());
+        assert_eq!(ecs.read::<Target>(&reused).unwrap().0, 2);
+    }
+
+    #[test]
+    fn test_entity_ref_describes_entity() {
+        let mut ecs = ECS::new();
+        let unnamed = ecs.build_entity().add_component(Target(1)).entity();
+        let named = ecs
+            .build_entity()
+            .add_component(DebugName("arrow".to_string()))
+            .add_component(Target(2))
+            .entity();
+
+        let arrow = ecs.entity(&named);
+        assert!(arrow.is_alive());
+        assert_eq!(arrow.debug_name(), "arrow");
+        assert_eq!(arrow.get::<Target>().unwrap().0, 2);
+        let mut types = arrow.component_types();
+        types.sort();
+        assert_eq!(
+            types,
+            vec![
+                "gouda_ecs::entity_ref::DebugName",
+                "gouda_ecs::entity_ref::tests::Target"
+            ]
+        );
+        assert_eq!(
+            format!("{:?}", ecs.entity(&unnamed)),
+            format!(
+                "{}v1 [\"gouda_ecs::entity_ref::tests::Target\"]",
+                unnamed.index
+            )
+        );
+    }
+}
@@ -0,0 +1,271 @@
+use std::mem;
+
+/// Storage for one event type. Events pushed since the last `ECS::update_events` are current;
+/// updating makes them the previous events and moves the old previous ones into `retained`,
+/// where they stay until the oldest reader has run past them or they get too old. Each event
+/// remembers the change tick it was pushed at, which readers use as a cursor, and retained events
+/// remember how many updates had happened when they were pushed.
+#[derive(Clone)]
+pub(crate) struct EventChannel<T> {
+    retained: Vec<T>,
+    retained_ticks: Vec<u64>,
+    retained_updates: Vec<u64>,
+    previous: Vec<T>,
+    previous_ticks: Vec<u64>,
+    previous_update: u64,
+    current: Vec<T>,
+    current_ticks: Vec<u64>,
+    updates: u64,
+}
+
+impl<T> EventChannel<T> {
+    pub(crate) fn new() -> EventChannel<T> {
+        EventChannel {
+            retained: Vec::new(),
+            retained_ticks: Vec::new(),
+            retained_updates: Vec::new(),
+            previous: Vec::new(),
+            previous_ticks: Vec::new(),
+            previous_update: 0,
+            current: Vec::new(),
+            current_ticks: Vec::new(),
+            updates: 0,
+        }
+    }
+
+    pub(crate) fn push(&mut self, event: T, tick: u64) {
+        self.current.push(event);
+        self.current_ticks.push(tick);
+    }
+
+    /// Drops the retained events pushed at or before `oldest_reader`, or all of them if there
+    /// are no readers, along with any pushed `max_age` or more updates ago.
+    pub(crate) fn update(&mut self, oldest_reader: Option<u64>, max_age: u64) {
+        self.retained.append(&mut self.previous);
+        self.retained_ticks.append(&mut self.previous_ticks);
+        self.retained_updates
+            .resize(self.retained.len(), self.previous_update);
+        self.previous_update = self.updates;
+        self.updates += 1;
+
+        let read = match oldest_reader {
+            Some(tick) => self.retained_ticks.partition_point(|t| *t <= tick),
+            None => self.retained_ticks.len(),
+        };
+        let updates = self.updates;
+        let expired = self
+            .retained_updates
+            .partition_point(|pushed| updates - pushed >= max_age);
+        let dropped = read.max(expired);
+        self.retained.drain(..dropped);
+        self.retained_ticks.drain(..dropped);
+        self.retained_updates.drain(..dropped);
+        self.previous = mem::take(&mut self.current);
+        self.previous_ticks = mem::take(&mut self.current_ticks);
+    }
+
+    pub(crate) fn previous(&self) -> &Vec<T> {
+        return &self.previous;
+    }
+
+    /// Every event still stored that was pushed after `tick`, oldest first.
+    pub(crate) fn since(&self, tick: u64) -> impl Iterator<Item = &T> + '_ {
+        let retained = self.retained_ticks.partition_point(|t| *t <= tick);
+        let previous = self.previous_ticks.partition_point(|t| *t <= tick);
+        let current = self.current_ticks.partition_point(|t| *t <= tick);
+        return self.retained[retained..]
+            .iter()
+            .chain(self.previous[previous..].iter())
+            .chain(self.current[current..].iter());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EventChannel;
+    use crate::{Mutation, Mutations, ECS};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Hit(u32);
+
+    struct Push(u32);
+
+    impl Mutation for Push {
+        fn apply(&self, ecs: &mut ECS) {
+            ecs.push_event(Hit(self.0));
+        }
+    }
+
+    #[test]
+    fn test_events_are_delivered_in_order() {
+        let mut ecs = ECS::new();
+        ecs.register_event_type::<Hit>();
+        for i in 0..3 {
+            ecs.push_event(Hit(i));
+        }
+        assert!(ecs.events::<Hit>().is_empty());
+
+        ecs.update_events();
+        assert_eq!(ecs.events::<Hit>(), &vec![Hit(0), Hit(1), Hit(2)]);
+
+        ecs.update_events();
+        assert!(ecs.events::<Hit>().is_empty());
+    }
+
+    #[test]
+    fn test_each_system_reads_each_event_once() {
+        let mut ecs = ECS::new();
+        ecs.register_event_type::<Hit>();
+        ecs.add_res(0u32);
+        let seen = Rc::new(RefCell::new(vec![]));
+        let seen_in_system = seen.clone();
+        ecs.register_system(
+            "producer",
+            Box::new(|ecs: &ECS, _dt: f32| -> Mutations {
+                let frame = *ecs.read_res::<u32>();
+                vec![Box::new(Push(frame))]
+            }),
+        );
+        ecs.register_system(
+            "every_other_frame",
+            Box::new(move |ecs: &ECS, _dt: f32| -> Mutations {
+                let hits: Vec<u32> = ecs.read_events::<Hit>().map(|hit| hit.0).collect();
+                seen_in_system.borrow_mut().push(hits);
+                vec![]
+            }),
+        )
+        .run_if(|ecs| *ecs.read_res::<u32>() % 2 == 1);
+
+        for frame in 0..6 {
+            *ecs.write_res::<u32>() = frame;
+            ecs.update_events();
+            ecs.run_systems(0.);
+        }
+
+        assert_eq!(*seen.borrow(), vec![vec![0, 1], vec![2, 3], vec![4, 5]]);
+    }
+
+    #[test]
+    fn test_skipped_systems_keep_their_events() {
+        let mut ecs = ECS::new();
+        ecs.register_event_type::<Hit>();
+        ecs.add_res(0u32);
+        let seen = Rc::new(RefCell::new(vec![]));
+        let seen_in_system = seen.clone();
+        ecs.register_system(
+            "producer",
+            Box::new(|ecs: &ECS, _dt: f32| -> Mutations {
+                let frame = *ecs.read_res::<u32>();
+                vec![Box::new(Push(frame))]
+            }),
+        );
+        ecs.register_system(
+            "every_fourth_frame",
+            Box::new(move |ecs: &ECS, _dt: f32| -> Mutations {
+                let hits: Vec<u32> = ecs.read_events::<Hit>().map(|hit| hit.0).collect();
+                seen_in_system.borrow_mut().push(hits);
+                vec![]
+            }),
+        )
+        .run_if(|ecs| *ecs.read_res::<u32>() % 4 == 0);
+
+        for frame in 0..9 {
+            *ecs.write_res::<u32>() = frame;
+            ecs.update_events();
+            ecs.run_systems(0.);
+        }
+
+        assert_eq!(
+            *seen.borrow(),
+            vec![vec![0], vec![1, 2, 3, 4], vec![5, 6, 7, 8]]
+        );
+    }
+
+    #[test]
+    fn test_events_every_reader_has_seen_are_dropped() {
+        let mut ecs = ECS::new();
+        ecs.register_event_type::<Hit>();
+        ecs.register_system(
+            "reader",
+            Box::new(|ecs: &ECS, _dt: f32| -> Mutations {
+                ecs.read_events::<Hit>().count();
+                vec![]
+            }),
+        );
+        for i in 0..10 {
+            ecs.push_event(Hit(i));
+            ecs.update_events();
+            ecs.run_systems(0.);
+        }
+
+        let channel = ecs.events.get::<EventChannel<Hit>>().unwrap();
+        assert!(channel.retained.is_empty());
+    }
+
+    fn push_every_frame(ecs: &mut ECS) {
+        ecs.register_system(
+            "producer",
+            Box::new(|ecs: &ECS, _dt: f32| -> Mutations {
+                let frame = *ecs.read_res::<u32>();
+                vec![Box::new(Push(frame))]
+            }),
+        );
+    }
+
+    fn run_frames(ecs: &mut ECS, frames: u32) {
+        for frame in 0..frames {
+            *ecs.write_res::<u32>() = frame;
+            ecs.update_events();
+            ecs.run_systems(0.);
+        }
+    }
+
+    #[test]
+    fn test_systems_that_never_run_dont_keep_events() {
+        let mut ecs = ECS::new();
+        ecs.register_event_type::<Hit>();
+        ecs.add_res(0u32);
+        push_every_frame(&mut ecs);
+        ecs.register_system(
+            "debug_overlay",
+            Box::new(|ecs: &ECS, _dt: f32| -> Mutations {
+                ecs.read_events::<Hit>().count();
+                vec![]
+            }),
+        )
+        .run_if(|_| false);
+
+        run_frames(&mut ecs, 10);
+
+        let channel = ecs.events.get::<EventChannel<Hit>>().unwrap();
+        assert!(channel.retained.is_empty());
+    }
+
+    #[test]
+    fn test_paused_readers_keep_a_bounded_number_of_events() {
+        let mut ecs = ECS::new();
+        ecs.register_event_type::<Hit>();
+        ecs.add_res(0u32);
+        ecs.set_event_retention(5);
+        push_every_frame(&mut ecs);
+        let seen = Rc::new(RefCell::new(vec![]));
+        let seen_in_system = seen.clone();
+        ecs.register_system(
+            "menu",
+            Box::new(move |ecs: &ECS, _dt: f32| -> Mutations {
+                let hits: Vec<u32> = ecs.read_events::<Hit>().map(|hit| hit.0).collect();
+                seen_in_system.borrow_mut().push(hits);
+                vec![]
+            }),
+        )
+        .run_if(|ecs| *ecs.read_res::<u32>() == 0 || *ecs.read_res::<u32>() == 99);
+
+        run_frames(&mut ecs, 100);
+
+        let channel = ecs.events.get::<EventChannel<Hit>>().unwrap();
+        assert!(channel.retained.len() <= 5);
+        assert_eq!(*seen.borrow(), vec![vec![0], vec![95, 96, 97, 98, 99]]);
+    }
+}
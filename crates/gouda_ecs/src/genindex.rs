@@ -1,10 +1,21 @@
+use serde::{Deserialize, Serialize};
+
 // Generation Index
-#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash, Serialize, Deserialize)]
 pub struct GenIndex {
     pub index: usize,
     pub generation: u64,
 }
 
+impl GenIndex {
+    /// Never handed out by an allocator, since generations start at 1. Stands in for references to
+    /// something that doesn't exist.
+    pub const DEAD: GenIndex = GenIndex {
+        index: 0,
+        generation: 0,
+    };
+}
+
 #[derive(Eq, PartialEq, Clone)]
 pub enum GenIndexAllocationStatus {
     Free,
@@ -67,9 +78,25 @@ impl GenIndexAllocator {
         }
     }
 
+    /// False for indices this allocator never handed out.
     pub fn is_live(&self, index: GenIndex) -> bool {
-        let e = self.entries.get(index.index).unwrap();
-        e.generation == index.generation && e.status == GenIndexAllocationStatus::Live
+        return self.live_at(index.index) == Some(index);
+    }
+
+    /// The live index in the given slot, if there is one.
+    pub fn live_at(&self, index: usize) -> Option<GenIndex> {
+        match self.entries.get(index) {
+            Some(entry) if entry.status == GenIndexAllocationStatus::Live => Some(GenIndex {
+                index,
+                generation: entry.generation,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Every index that has ever been allocated is below this.
+    pub fn index_bound(&self) -> usize {
+        self.entries.len()
     }
 }
 
@@ -148,7 +175,7 @@ impl<T> Default for GenIndexArray<T> {
 
 #[cfg(test)]
 mod tests {
-    use super::{GenIndexAllocator, GenIndexArray};
+    use super::{GenIndex, GenIndexAllocator, GenIndexArray};
 
     #[test]
     fn test_allocate() {
@@ -166,6 +193,15 @@ mod tests {
         assert!(!allocator.is_live(index));
     }
 
+    #[test]
+    fn test_unknown_index_is_not_live() {
+        let allocator = GenIndexAllocator::new();
+        assert!(!allocator.is_live(GenIndex {
+            index: 3,
+            generation: 1
+        }));
+    }
+
     #[test]
     fn can_clear() {
         let mut allocator = GenIndexAllocator::new();
@@ -0,0 +1,165 @@
+use crate::{Entity, ECS};
+
+#[derive(Debug)]
+pub struct ChildOfComponent {
+    pub parent: Entity,
+}
+
+impl ChildOfComponent {
+    pub fn of(parent: Entity) -> Self {
+        Self { parent }
+    }
+}
+
+#[derive(Debug)]
+pub struct HasChildrenComponent {
+    pub children: Vec<Entity>,
+}
+
+impl HasChildrenComponent {
+    pub fn new(children: Vec<Entity>) -> Self {
+        Self { children }
+    }
+}
+
+impl ECS {
+    /// Makes `child` a child of `parent`, detaching it from any previous parent. Panics if
+    /// `parent` is `child` or one of its descendants.
+    pub fn set_parent(&mut self, child: &Entity, parent: &Entity) {
+        let mut ancestor = Some(*parent);
+        while let Some(entity) = ancestor {
+            if entity == *child {
+                panic!(
+                    "Cannot make {:?} a child of its own descendant {:?}",
+                    child, parent
+                );
+            }
+            ancestor = self.parent(&entity);
+        }
+
+        self.remove_parent(child);
+        self.add_component(child, ChildOfComponent::of(*parent));
+        match self.write::<HasChildrenComponent>(parent) {
+            Some(children) => children.children.push(*child),
+            None => self.add_component(parent, HasChildrenComponent::new(vec![*child])),
+        }
+    }
+
+    /// Detaches `child` from its parent, leaving it as a root.
+    pub fn remove_parent(&mut self, child: &Entity) {
+        let parent = match self.parent(child) {
+            Some(parent) => parent,
+            None => return,
+        };
+        self.remove_component::<ChildOfComponent>(child);
+        if let Some(children) = self.write::<HasChildrenComponent>(&parent) {
+            children.children.retain(|c| c != child);
+            if children.children.is_empty() {
+                self.remove_component::<HasChildrenComponent>(&parent);
+            }
+        }
+    }
+
+    pub fn parent(&self, child: &Entity) -> Option<Entity> {
+        return self
+            .read::<ChildOfComponent>(child)
+            .map(|child_of| child_of.parent);
+    }
+
+    pub fn children(&self, parent: &Entity) -> &[Entity] {
+        match self.read::<HasChildrenComponent>(parent) {
+            Some(children) => &children.children,
+            None => &[],
+        }
+    }
+
+    /// Deletes the entity along with all of its descendants.
+    pub fn despawn_recursive(&mut self, entity: &Entity) {
+        let mut to_delete = vec![*entity];
+        let mut i = 0;
+        while i < to_delete.len() {
+            to_delete.extend_from_slice(self.children(&to_delete[i]));
+            i += 1;
+        }
+        for entity in to_delete.iter().rev() {
+            self.delete_entity(entity);
+        }
+    }
+
+    /// Unlinks an entity that is about to be deleted: it leaves its parent's children and its own
+    /// children become roots.
+    pub(crate) fn detach_from_hierarchy(&mut self, entity: &Entity) {
+        self.remove_parent(entity);
+        let children = self.children(entity).to_vec();
+        for child in children {
+            self.remove_component::<ChildOfComponent>(&child);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ECS;
+
+    #[test]
+    fn test_set_parent_keeps_both_sides_in_sync() {
+        let mut ecs = ECS::new();
+        let first = ecs.new_entity();
+        let second = ecs.new_entity();
+        let child = ecs.new_entity();
+
+        ecs.set_parent(&child, &first);
+        assert_eq!(ecs.parent(&child), Some(first));
+        assert_eq!(ecs.children(&first), &[child]);
+
+        ecs.set_parent(&child, &second);
+        assert_eq!(ecs.parent(&child), Some(second));
+        assert!(ecs.children(&first).is_empty());
+        assert_eq!(ecs.children(&second), &[child]);
+
+        ecs.remove_parent(&child);
+        assert_eq!(ecs.parent(&child), None);
+        assert!(ecs.children(&second).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "own descendant")]
+    fn test_cycles_are_rejected() {
+        let mut ecs = ECS::new();
+        let root = ecs.new_entity();
+        let child = ecs.new_entity();
+        ecs.set_parent(&child, &root);
+        ecs.set_parent(&root, &child);
+    }
+
+    #[test]
+    fn test_despawn_recursive_deletes_descendants() {
+        let mut ecs = ECS::new();
+        let root = ecs.new_entity();
+        let child = ecs.new_entity();
+        let grandchild = ecs.new_entity();
+        let sibling = ecs.new_entity();
+        ecs.set_parent(&child, &root);
+        ecs.set_parent(&grandchild, &child);
+        ecs.set_parent(&sibling, &root);
+        let keep = ecs.new_entity();
+        ecs.set_parent(&root, &keep);
+
+        ecs.despawn_recursive(&root);
+
+        assert_eq!(ecs.query::<crate::Entity>().collect::<Vec<_>>(), vec![keep]);
+        assert!(ecs.children(&keep).is_empty());
+    }
+
+    #[test]
+    fn test_deleting_parent_orphans_children() {
+        let mut ecs = ECS::new();
+        let root = ecs.new_entity();
+        let child = ecs.new_entity();
+        ecs.set_parent(&child, &root);
+
+        ecs.delete_entity(&root);
+
+        assert_eq!(ecs.parent(&child), None);
+    }
+}
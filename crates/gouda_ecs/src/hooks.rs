@@ -0,0 +1,210 @@
+use crate::{Entity, ECS};
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::rc::Rc;
+
+/// Called with the entity whose component changed. Hooks run as part of the change, so they see
+/// the world exactly as it is at that moment and may edit it further.
+pub type ComponentHook = dyn Fn(&mut ECS, &Entity);
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub(crate) enum HookKind {
+    Add,
+    Replace,
+    Remove,
+}
+
+pub(crate) struct ComponentHooks<T> {
+    on_add: Vec<Rc<ComponentHook>>,
+    on_replace: Vec<Rc<ComponentHook>>,
+    on_remove: Vec<Rc<ComponentHook>>,
+    component: PhantomData<T>,
+}
+
+impl<T> ComponentHooks<T> {
+    fn new() -> ComponentHooks<T> {
+        ComponentHooks {
+            on_add: vec![],
+            on_replace: vec![],
+            on_remove: vec![],
+            component: PhantomData,
+        }
+    }
+
+    fn list(&mut self, kind: HookKind) -> &mut Vec<Rc<ComponentHook>> {
+        match kind {
+            HookKind::Add => &mut self.on_add,
+            HookKind::Replace => &mut self.on_replace,
+            HookKind::Remove => &mut self.on_remove,
+        }
+    }
+}
+
+/// Event pushed when an observed component is added to an entity that didn't have one.
+#[derive(Debug)]
+pub struct Added<T> {
+    pub entity: Entity,
+    component: PhantomData<T>,
+}
+
+/// Event pushed when an observed component is removed, including when its entity is deleted.
+#[derive(Debug)]
+pub struct Removed<T> {
+    pub entity: Entity,
+    component: PhantomData<T>,
+}
+
+impl ECS {
+    /// Runs `hook` after a `T` is added to an entity that didn't already have one.
+    pub fn on_add<T: 'static>(&mut self, hook: impl Fn(&mut ECS, &Entity) + 'static) {
+        self.add_hook::<T>(HookKind::Add, Rc::new(hook));
+    }
+
+    /// Runs `hook` before an entity's `T` is overwritten by `add_component`, while the old value
+    /// can still be read.
+    pub fn on_replace<T: 'static>(&mut self, hook: impl Fn(&mut ECS, &Entity) + 'static) {
+        self.add_hook::<T>(HookKind::Replace, Rc::new(hook));
+    }
+
+    /// Runs `hook` before an entity's `T` is removed, while it can still be read. Deleting an
+    /// entity removes each of its components. The entity is already dead by then, so components
+    /// added to it from the hook are dropped.
+    pub fn on_remove<T: 'static>(&mut self, hook: impl Fn(&mut ECS, &Entity) + 'static) {
+        self.add_hook::<T>(HookKind::Remove, Rc::new(hook));
+    }
+
+    /// Pushes `Added<T>` and `Removed<T>` events whenever a `T` is added or removed. The event
+    /// types are registered here.
+    pub fn observe<T: 'static + Debug>(&mut self) {
+        self.register_event_type::<Added<T>>();
+        self.register_event_type::<Removed<T>>();
+        self.on_add::<T>(|ecs, entity| {
+            ecs.push_event(Added::<T> {
+                entity: *entity,
+                component: PhantomData,
+            })
+        });
+        self.on_remove::<T>(|ecs, entity| {
+            ecs.push_event(Removed::<T> {
+                entity: *entity,
+                component: PhantomData,
+            })
+        });
+    }
+
+    fn add_hook<T: 'static>(&mut self, kind: HookKind, hook: Rc<ComponentHook>) {
+        if self.hooks.get::<ComponentHooks<T>>().is_none() {
+            self.hooks.insert(ComponentHooks::<T>::new());
+        }
+        let hooks = self.hooks.get_mut::<ComponentHooks<T>>().unwrap();
+        hooks.list(kind).push(hook);
+    }
+
+    pub(crate) fn run_hooks<T: 'static>(&mut self, kind: HookKind, entity: &Entity) {
+        let hooks = match self.hooks.get_mut::<ComponentHooks<T>>() {
+            Some(hooks) => hooks.list(kind).clone(),
+            None => return,
+        };
+        for hook in hooks {
+            hook(self, entity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Added, Removed};
+    use crate::{Entity, ECS};
+
+    #[derive(Debug)]
+    struct Health(u32);
+
+    #[derive(Debug)]
+    struct Log(Vec<String>);
+
+    fn log(ecs: &mut ECS, message: String) {
+        ecs.write_res::<Log>().0.push(message);
+    }
+
+    #[test]
+    fn test_hooks_run_on_add_replace_and_remove() {
+        let mut ecs = ECS::new();
+        ecs.add_res(Log(vec![]));
+        ecs.on_add::<Health>(|ecs, entity| {
+            let health = ecs.read::<Health>(entity).unwrap().0;
+            log(ecs, format!("add {}", health));
+        });
+        ecs.on_replace::<Health>(|ecs, entity| {
+            let health = ecs.read::<Health>(entity).unwrap().0;
+            log(ecs, format!("replace {}", health));
+        });
+        ecs.on_remove::<Health>(|ecs, entity| {
+            let health = ecs.read::<Health>(entity).unwrap().0;
+            log(ecs, format!("remove {}", health));
+        });
+
+        let entity = ecs.new_entity();
+        ecs.add_component(&entity, Health(1));
+        ecs.add_component(&entity, Health(2));
+        ecs.remove_component::<Health>(&entity);
+        ecs.remove_component::<Health>(&entity);
+        ecs.add_component(&entity, Health(3));
+        ecs.delete_entity(&entity);
+
+        assert_eq!(
+            ecs.read_res::<Log>().0,
+            vec!["add 1", "replace 1", "remove 2", "add 3", "remove 3"]
+        );
+    }
+
+    #[test]
+    fn test_hooks_can_keep_other_components_in_sync() {
+        #[derive(Debug)]
+        struct Members(Vec<Entity>);
+
+        #[derive(Debug)]
+        struct Member;
+
+        let mut ecs = ECS::new();
+        let group = ecs.build_entity().add_component(Members(vec![])).entity();
+        ecs.on_add::<Member>(move |ecs, entity| {
+            ecs.write::<Members>(&group).unwrap().0.push(*entity);
+        });
+        ecs.on_remove::<Member>(move |ecs, entity| {
+            ecs.write::<Members>(&group)
+                .unwrap()
+                .0
+                .retain(|member| member != entity);
+        });
+
+        let first = ecs.build_entity().add_component(Member).entity();
+        let second = ecs.build_entity().add_component(Member).entity();
+        ecs.delete_entity(&first);
+
+        assert_eq!(ecs.read::<Members>(&group).unwrap().0, vec![second]);
+    }
+
+    #[test]
+    fn test_observed_components_push_events() {
+        let mut ecs = ECS::new();
+        ecs.observe::<Health>();
+
+        let entity = ecs.build_entity().add_component(Health(5)).entity();
+        ecs.add_component(&entity, Health(6));
+        ecs.delete_entity(&entity);
+        ecs.update_events();
+
+        let added: Vec<Entity> = ecs
+            .events::<Added<Health>>()
+            .iter()
+            .map(|e| e.entity)
+            .collect();
+        let removed: Vec<Entity> = ecs
+            .events::<Removed<Health>>()
+            .iter()
+            .map(|e| e.entity)
+            .collect();
+        assert_eq!(added, vec![entity]);
+        assert_eq!(removed, vec![entity]);
+    }
+}
@@ -2,14 +2,46 @@
 #![allow(unused_parens)]
 
 use anymap::AnyMap;
+use std::any::{type_name, TypeId};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
 use std::fmt::Debug;
 
+pub mod bundle;
+pub mod commands;
+pub mod conflicts;
+pub mod entity_ref;
+mod events;
 pub mod genindex;
-
-use crate::genindex::{GenIndex, GenIndexAllocator, GenIndexArray};
+pub mod hierarchy;
+pub mod hooks;
+pub mod parallel;
+pub mod prefab;
+pub mod query;
+pub mod save;
+pub mod schedule;
+pub mod snapshot;
+pub mod storage;
+
+use crate::bundle::Bundle;
+use crate::conflicts::{WriteConflicts, WriteTracker};
+pub use crate::entity_ref::{DebugName, EntityRef};
+use crate::events::EventChannel;
+use crate::genindex::{GenIndex, GenIndexAllocator};
+pub use crate::hierarchy::{ChildOfComponent, HasChildrenComponent};
+use crate::hooks::HookKind;
+use crate::parallel::{ParallelSystem, RunningGuard, SharedWorld};
+use crate::prefab::Prefab;
+use crate::query::{Query, QueryData};
+use crate::save::SavedTypes;
+use crate::schedule::{MutationTiming, Schedule, Stage, SystemBuilder, SystemFn, SystemScope};
+use crate::snapshot::SnapshotTypes;
+use crate::storage::SparseSet;
+use rayon::prelude::*;
 
 pub type Entity = GenIndex;
-type EntityMap<T> = GenIndexArray<T>;
+type EntityMap<T> = SparseSet<T>;
 
 pub trait Mutation {
     fn apply(&self, ecs: &mut ECS);
@@ -19,170 +51,334 @@ pub type Mutations = Vec<Box<dyn Mutation>>;
 pub type System = dyn Fn(&ECS, f32) -> Mutations;
 pub type GameSceneId = u32;
 
-#[derive(Debug)]
-pub struct ChildOfComponent {
-    pub parent: Entity,
-}
-
-impl ChildOfComponent {
-    pub fn of(parent: Entity) -> Self {
-        Self { parent }
-    }
-}
-
-#[derive(Debug)]
-pub struct HasChildrenComponent {
-    pub children: Vec<Entity>,
-}
-
-impl HasChildrenComponent {
-    pub fn new(children: Vec<Entity>) -> Self {
-        Self { children }
-    }
+/// Type-erased access to one component type's storage.
+#[derive(Clone, Copy)]
+struct ComponentType {
+    name: &'static str,
+    has: fn(&ECS, &Entity) -> bool,
+    remove: fn(&mut ECS, &Entity),
 }
 
 pub struct ECS {
     entity_allocator: GenIndexAllocator,
     components: AnyMap,
     resources: AnyMap,
-    systems: Vec<Box<System>>,
-    queued_events: AnyMap,
-    processing_events: AnyMap,
-    cleanup_closures: Vec<fn(&mut ECS)>,
+    schedule: Schedule,
+    thread_pool: Option<rayon::ThreadPool>,
+    write_tracker: WriteTracker,
+    events: AnyMap,
+    event_updaters: Vec<fn(&mut ECS)>,
+    hooks: AnyMap,
+    saved_types: SavedTypes,
+    snapshot_types: SnapshotTypes,
+    prefabs: HashMap<String, Prefab>,
+    component_types: Vec<ComponentType>,
+    change_tick: u64,
+    last_run_tick: u64,
+    running_serial: Option<usize>,
+    event_retention: u64,
 }
 
 macro_rules! impl_read {
-        ( $fn_name:ident, [$($r:ident),*] ) => {
-            pub fn $fn_name<$($r: 'static),*>(&self) -> Vec<($(&$r),*, Entity)> {
-
-            let mut minlen = 100000;
-        $(
-            let $r = self.components.get::<EntityMap<$r>>();
-            if $r.is_none() {
-                return Vec::new();
-            }
-            minlen = std::cmp::min(minlen, $r.unwrap().0.len());
-        )*
-
-            let mut res = Vec::new();
-            let num_iter = minlen;
-            for i in 0..num_iter {
-                $(
-                    let $r = $r.unwrap().0.get(i);
-                )*
-                match ($($r),*) {
-                    ($(Some(Some($r))),*) => {
-                        let mut generation = 0;
-                        $(
-                            if generation == 0 {
-                                generation = $r.generation;
-                            }
-                            if generation != $r.generation {
-                                continue;
-                            }
-                        )*
-                        let e = Entity {index: i, generation };
-                        res.push(($(&$r.value),*, e));
-                    }
-                    _ => {}
-                }
-            }
+    ( $fn_name:ident, [$($r:ident),*] ) => {
+        pub fn $fn_name<$($r: 'static),*>(&self) -> Vec<($(&$r),*, Entity)> {
+            let query = self.query::<($(&$r),*, Entity)>();
+            let mut res = Vec::with_capacity(query.size_hint().1.unwrap_or(0));
+            res.extend(query);
             return res;
         }
     }
 }
 
 macro_rules! impl_get {
-        ( $fn_name:ident, [$($r:ident),*] ) => {
-            pub fn $fn_name<$($r: 'static),*>(&self) -> Vec<Entity> {
-
-            let mut minlen = 1000;
-        $(
-            let $r = self.components.get::<EntityMap<$r>>();
-            if $r.is_none() {
-                return Vec::new();
-            }
-            minlen = std::cmp::min(minlen, $r.unwrap().0.len());
-        )*
-
-            let mut res = Vec::new();
-            let num_iter = minlen;
-            for i in 0..num_iter {
+    ( $fn_name:ident, [$($r:ident),*] ) => {
+        pub fn $fn_name<$($r: 'static),*>(&self) -> Vec<Entity> {
+            return self.query::<($(&$r),*, Entity)>().map(|($($r),*, e)| {
                 $(
-                    let $r = $r.unwrap().0.get(i);
+                    let _ = $r;
                 )*
-                match ($($r),*) {
-                    ($(Some(Some($r))),*) => {
-                        let mut generation = 0;
-                        $(
-                            if generation == 0 {
-                                generation = $r.generation;
-                            }
-                            if generation != $r.generation {
-                                continue;
-                            }
-                        )*
-                        let e = Entity {index: i, generation };
-                        res.push(e);
-                    }
-                    _ => {}
-                }
-            }
-            return res;
+                e
+            }).collect();
         }
     }
 }
 
-struct MissingComponentError;
+/// Returned when an entity doesn't have the requested component.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct MissingComponentError {
+    pub entity: Entity,
+    pub component: &'static str,
+}
+
+impl fmt::Display for MissingComponentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Entity {:?} has no {} component",
+            self.entity, self.component
+        )
+    }
+}
+
+impl Error for MissingComponentError {}
+
+/// Returned when a resource was never added with `add_res`, or has been removed.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct MissingResourceError {
+    pub resource: &'static str,
+}
+
+impl fmt::Display for MissingResourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Resource {} has not been added", self.resource)
+    }
+}
+
+impl Error for MissingResourceError {}
 
-impl From<&str> for MissingComponentError {
-    fn from(_: &str) -> MissingComponentError {
-        MissingComponentError
+/// Returned when an event type was never registered with `register_event_type`.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct UnregisteredEventError {
+    pub event: &'static str,
+}
+
+impl fmt::Display for UnregisteredEventError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Event {} has not been registered", self.event)
     }
 }
 
+impl Error for UnregisteredEventError {}
+
 impl ECS {
+    /// Adds an unnamed scene system to the update stage.
     pub fn add_system(&mut self, system: Box<System>) {
-        self.systems.push(system);
+        self.schedule.add(None, SystemFn::Serial(system));
+    }
+
+    /// Adds a named scene system to the update stage. The returned builder can move it to another
+    /// stage, order it against other systems, give it a run condition or make it an engine system.
+    /// Panics if a system with this name is already registered.
+    pub fn register_system(&mut self, name: &str, system: Box<System>) -> SystemBuilder<'_> {
+        let index = self
+            .schedule
+            .add(Some(name.to_string()), SystemFn::Serial(system));
+        SystemBuilder { ecs: self, index }
+    }
+
+    /// Adds a named scene system that may run on a worker thread alongside other parallel systems
+    /// in its stage. Declare everything it reads and everything its mutations write on the
+    /// returned builder. Reading an undeclared type while running panics.
+    pub fn register_parallel_system(
+        &mut self,
+        name: &str,
+        system: Box<ParallelSystem>,
+    ) -> SystemBuilder<'_> {
+        let index = self
+            .schedule
+            .add(Some(name.to_string()), SystemFn::Parallel(system));
+        SystemBuilder { ecs: self, index }
+    }
+
+    /// Removes the named system. Returns false if there was no such system.
+    pub fn remove_system(&mut self, name: &str) -> bool {
+        self.schedule.remove(name)
     }
 
+    pub fn has_system(&self, name: &str) -> bool {
+        self.schedule.index_of(name).is_some()
+    }
+
+    /// Removes every scene system, leaving engine systems in place.
+    pub fn clear_scene_systems(&mut self) {
+        self.schedule
+            .retain(|entry| entry.scope == SystemScope::Engine);
+    }
+
+    /// Removes every system, including engine systems.
     pub fn clear_systems(&mut self) {
-        self.systems.clear();
+        self.schedule.retain(|_| false);
     }
 
     fn register_component_type<T: 'static + Debug>(&mut self) -> &mut EntityMap<T> {
         let e: EntityMap<T> = EntityMap::new();
         self.components.insert(e);
-        self.attach_cleanup(|ecs: &mut ECS| ecs.cleanup_components::<T>());
+        self.component_types.push(ComponentType {
+            name: type_name::<T>(),
+            has: |ecs: &ECS, entity: &Entity| ecs.has_component::<T>(entity),
+            remove: |ecs: &mut ECS, entity: &Entity| ecs.remove_component::<T>(entity),
+        });
         return self.components.get_mut::<EntityMap<T>>().unwrap();
     }
 
-    fn attach_cleanup(&mut self, cleanup_closure: fn(&mut ECS)) {
-        self.cleanup_closures.push(cleanup_closure);
+    /// Runs every system stage by stage. Systems whose run condition is false are skipped.
+    /// `Changed` queries inside a system see the changes made since that system last ran, and
+    /// outside of systems they see the changes made during the last call to this.
+    ///
+    /// By default each system's mutations are applied before the next system runs, and
+    /// neighbouring parallel systems run together on the thread pool unless one writes something
+    /// a later one reads. Their mutations are applied afterwards in schedule order, so the result
+    /// is the same as running them one at a time. In stages set to `MutationTiming::AtStageEnd`
+    /// every parallel system can run together, since none of them see each other's mutations.
+    pub fn run_systems(&mut self, dt: f32) {
+        let frame_start_tick = self.change_tick;
+        let order: Vec<(Stage, usize)> = self
+            .schedule
+            .order()
+            .into_iter()
+            .map(|i| (self.schedule.systems[i].stage, i))
+            .collect();
+        for stage in order.chunk_by(|a, b| a.0 == b.0) {
+            let systems: Vec<usize> = stage.iter().map(|(_, i)| *i).collect();
+            self.run_stage(&systems, dt);
+        }
+        self.last_run_tick = frame_start_tick;
+    }
+
+    fn run_stage(&mut self, stage: &[usize], dt: f32) {
+        let timing = self.schedule.timing(self.schedule.systems[stage[0]].stage);
+        let at_stage_end = timing == MutationTiming::AtStageEnd;
+        self.write_tracker.start_stage();
+        let mut pending: Vec<(usize, Mutations)> = vec![];
+        let mut batch: Vec<usize> = vec![];
+        for i in stage.iter().copied() {
+            let entry = &self.schedule.systems[i];
+            let joins_batch = match entry.system {
+                SystemFn::Parallel(_) => {
+                    at_stage_end
+                        || batch.is_empty()
+                        || (entry.run_if.is_none()
+                            && batch.iter().all(|b| {
+                                !self.schedule.systems[*b]
+                                    .access
+                                    .conflicts_with(&entry.access)
+                            }))
+                }
+                SystemFn::Serial(_) => at_stage_end,
+            };
+            if !joins_batch {
+                let results = self.run_batch(&batch, dt);
+                batch.clear();
+                self.apply_mutations(results);
+            }
+
+            let entry = &self.schedule.systems[i];
+            if let Some(condition) = &entry.run_if {
+                if !condition(self) {
+                    continue;
+                }
+            }
+            match &entry.system {
+                SystemFn::Parallel(_) => batch.push(i),
+                SystemFn::Serial(system) => {
+                    self.last_run_tick = entry.last_run_tick;
+                    self.running_serial = Some(i);
+                    let mutations = system(self, dt);
+                    self.running_serial = None;
+                    self.schedule.systems[i].last_run_tick = self.change_tick;
+                    if at_stage_end {
+                        pending.push((i, mutations));
+                    } else {
+                        self.apply_mutations(vec![(i, mutations)]);
+                    }
+                }
+            }
+        }
+
+        let results = self.run_batch(&batch, dt);
+        pending.extend(results);
+        // Serial systems ran as they were reached but parallel ones may have run at the end, so
+        // restore schedule order before applying
+        pending.sort_by_key(|(i, _)| stage.iter().position(|s| s == i));
+        self.apply_mutations(pending);
+    }
+
+    /// Runs parallel systems on a dedicated pool of `threads` worker threads instead of rayon's
+    /// global pool.
+    pub fn set_worker_threads(&mut self, threads: usize) {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        self.thread_pool = Some(pool);
+    }
+
+    /// Sets when the mutations returned by systems in `stage` are applied.
+    pub fn set_mutation_timing(&mut self, stage: Stage, timing: MutationTiming) {
+        self.schedule.set_timing(stage, timing);
+    }
+
+    /// Sets what happens when two mutations in the same stage write the same component of the
+    /// same entity. Checking costs a hash lookup per write, so this is off by default.
+    pub fn set_write_conflicts(&mut self, conflicts: WriteConflicts) {
+        self.write_tracker.mode = conflicts;
+    }
+
+    /// Sets how many calls to `update_events` an event is kept for at most, even if a system
+    /// that reads it hasn't run since it was pushed. Defaults to 60. Values below 2 are raised to
+    /// 2, so events can always be read on the frame after they were pushed.
+    pub fn set_event_retention(&mut self, updates: u64) {
+        self.event_retention = updates.max(2);
+    }
+
+    /// Runs a batch of parallel systems against the current world and returns their mutations
+    /// in batch order.
+    fn run_batch(&mut self, batch: &[usize], dt: f32) -> Vec<(usize, Mutations)> {
+        let world = SharedWorld(self);
+        let run = |i: &usize| {
+            let entry = &world.0.schedule.systems[*i];
+            let name = entry.name.as_deref().unwrap_or_default();
+            let _running = RunningGuard::enter(*i, name, &entry.access, entry.last_run_tick);
+            match &entry.system {
+                SystemFn::Parallel(system) => system(world.0, dt),
+                SystemFn::Serial(_) => unreachable!(),
+            }
+        };
+        let results: Vec<_> = match batch.len() {
+            0 => return vec![],
+            1 => vec![run(&batch[0])],
+            _ => {
+                let run_all = || batch.par_iter().map(run).collect();
+                match &self.thread_pool {
+                    Some(pool) => pool.install(run_all),
+                    None => run_all(),
+                }
+            }
+        };
+
+        let mut res = Vec::with_capacity(batch.len());
+        for (i, mutations) in batch.iter().zip(results) {
+            self.schedule.systems[*i].last_run_tick = self.change_tick;
+            let mutations: Mutations = mutations
+                .into_iter()
+                .map(|mutation| mutation as Box<dyn Mutation>)
+                .collect();
+            res.push((*i, mutations));
+        }
+        return res;
     }
 
-    pub fn run_systems(&mut self, dt: f32) {
-        let num_systems = self.systems.len();
-        for i in 0..num_systems {
-            let mutations = self.systems[i](self, dt);
+    fn apply_mutations(&mut self, results: Vec<(usize, Mutations)>) {
+        for (i, mutations) in results {
             for mutation in mutations {
+                let entry = &self.schedule.systems[i];
+                let system = entry.name.as_deref().unwrap_or("unnamed system");
+                let declared = match entry.system {
+                    SystemFn::Parallel(_) => Some(&entry.access),
+                    SystemFn::Serial(_) => None,
+                };
+                self.write_tracker.begin_mutation(system, declared);
                 mutation.apply(self);
+                self.write_tracker.end_mutation();
             }
         }
-        self.cleanup_all_components();
-    }
-
-    fn cleanup_all_components(&mut self) {
-        let closures = self.cleanup_closures.clone();
-        let iter = closures.iter();
-        iter.for_each(|closure| closure(self));
     }
 
     pub fn new_entity(&mut self) -> Entity {
         self.entity_allocator.allocate()
     }
 
-    pub fn build_entity(&mut self) -> EntityBuilder {
+    pub fn build_entity(&mut self) -> EntityBuilder<'_> {
         let e = self.entity_allocator.allocate();
         EntityBuilder {
             ecs: self,
@@ -190,111 +386,284 @@ impl ECS {
         }
     }
 
+    /// Frees the entity and drops all of its components, so storage only ever holds components
+    /// of live entities. Its children are left alive as roots; use `despawn_recursive` to delete
+    /// them too.
     pub fn delete_entity(&mut self, entity: &Entity) {
+        if !self.entity_allocator.is_live(*entity) {
+            return;
+        }
+        self.detach_from_hierarchy(entity);
         self.entity_allocator.deallocate(*entity);
+        let component_types = self.component_types.clone();
+        for component_type in component_types {
+            (component_type.remove)(self, entity);
+        }
     }
 
+    /// Adds or replaces a component. Components added to deleted entities are dropped.
     pub fn add_component<T: 'static + Debug>(&mut self, entity: &Entity, component: T) {
+        if !self.entity_allocator.is_live(*entity) {
+            return;
+        }
+        let replacing = self.has_component::<T>(entity);
+        if replacing {
+            self.run_hooks::<T>(HookKind::Replace, entity);
+        }
+        self.change_tick += 1;
+        let tick = self.change_tick;
         let mut comps = self.components.get_mut::<EntityMap<T>>();
         if (comps.is_none()) {
             comps = Some(self.register_component_type::<T>())
         }
         if let Some(comps) = comps {
-            comps.set(*entity, component);
+            comps.insert(*entity, component, tick);
+        }
+        self.write_tracker.record::<T>(*entity);
+        if !replacing {
+            self.run_hooks::<T>(HookKind::Add, entity);
         }
     }
 
     pub fn remove_component<T: 'static + Debug>(&mut self, entity: &Entity) {
+        if !self.has_component::<T>(entity) {
+            return;
+        }
+        self.run_hooks::<T>(HookKind::Remove, entity);
         let comps = self.components.get_mut::<EntityMap<T>>();
         if let Some(comps) = comps {
-            comps.clear(*entity);
+            if comps.remove(*entity).is_some() {
+                self.write_tracker.record::<T>(*entity);
+            }
         }
     }
 
-    pub fn cleanup_components<T: 'static + Debug>(&mut self) {
-        let mut comps_to_remove = vec![];
-        if let Some(comps) = self.components.get::<EntityMap<T>>() {
-            let l = comps.0.len();
-            for i in 0..l {
-                if let Some(e) = &comps.0[i] {
-                    let e = GenIndex {
-                        index: i,
-                        generation: e.generation,
-                    };
-                    if !self.entity_allocator.is_live(e) {
-                        comps_to_remove.push(e);
-                    }
-                }
-            }
-        }
-        for e in comps_to_remove {
-            self.remove_component::<T>(&e);
-        }
+    fn has_component<T: 'static>(&self, entity: &Entity) -> bool {
+        return self
+            .components
+            .get::<EntityMap<T>>()
+            .is_some_and(|comps| comps.get(*entity).is_some());
     }
 
+    /// Registering a type again keeps the events already pushed.
     pub fn register_event_type<T: 'static>(&mut self) {
-        self.queued_events.insert(Vec::<T>::new());
-        self.processing_events.insert(Vec::<T>::new());
+        if self.events.contains::<EventChannel<T>>() {
+            return;
+        }
+        self.events.insert(EventChannel::<T>::new());
+        self.event_updaters.push(|ecs: &mut ECS| {
+            let oldest_reader = ecs.oldest_event_reader::<T>();
+            let retention = ecs.event_retention;
+            ecs.events
+                .get_mut::<EventChannel<T>>()
+                .unwrap()
+                .update(oldest_reader, retention);
+        });
+    }
+
+    /// The earliest tick that a system which has read `T` events last ran at.
+    fn oldest_event_reader<T: 'static>(&self) -> Option<u64> {
+        return self
+            .schedule
+            .systems
+            .iter()
+            .filter(|entry| {
+                entry
+                    .event_reads
+                    .lock()
+                    .unwrap()
+                    .contains(&TypeId::of::<T>())
+            })
+            .map(|entry| entry.last_run_tick)
+            .min();
     }
 
-    pub fn migrate_events<T: 'static>(&mut self) {
-        if let Some(e) = self.processing_events.get_mut::<Vec<T>>() {
-            e.clear();
+    /// Remembers that the running system reads `T` events, so they're kept until it runs again.
+    fn mark_event_reader<T: 'static>(&self) {
+        if let Some(i) = parallel::running_index().or(self.running_serial) {
+            let mut event_reads = self.schedule.systems[i].event_reads.lock().unwrap();
+            event_reads.insert(TypeId::of::<T>());
         }
+    }
 
-        let v = self.queued_events.get_mut::<Vec<T>>();
-        if let Some(v) = v {
-            while let Some(element) = v.pop() {
-                self.processing_events
-                    .get_mut::<Vec<T>>()
-                    .unwrap()
-                    .push(element);
-            }
+    /// Moves the events pushed since the last update into `events` for every registered type.
+    /// Older events are dropped once every system that has read that event type has run since
+    /// they were pushed, or once they reach the age set by `set_event_retention`. The engine
+    /// calls this at the start of each frame.
+    pub fn update_events(&mut self) {
+        let updaters = self.event_updaters.clone();
+        for update in updaters {
+            update(self);
         }
     }
 
+    /// Panics if the event type hasn't been registered.
     pub fn push_event<T: 'static>(&mut self, event: T) {
-        self.queued_events.get_mut::<Vec<T>>().unwrap().push(event);
+        if let Err(err) = self.try_push_event(event) {
+            panic!("{}", err);
+        }
     }
 
+    pub fn try_push_event<T: 'static>(&mut self, event: T) -> Result<(), UnregisteredEventError> {
+        self.write_tracker.check_declared::<T>();
+        match self.events.get_mut::<EventChannel<T>>() {
+            Some(channel) => {
+                self.change_tick += 1;
+                channel.push(event, self.change_tick);
+                Ok(())
+            }
+            None => Err(UnregisteredEventError {
+                event: type_name::<T>(),
+            }),
+        }
+    }
+
+    /// The events pushed before the last `update_events`, in the order they were pushed.
+    /// Panics if the event type hasn't been registered.
     pub fn events<T: 'static>(&self) -> &Vec<T> {
-        self.processing_events.get::<Vec<T>>().unwrap()
+        match self.try_events() {
+            Ok(events) => events,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    pub fn try_events<T: 'static>(&self) -> Result<&Vec<T>, UnregisteredEventError> {
+        return self.event_channel::<T>().map(|channel| channel.previous());
+    }
+
+    /// The events pushed since the running system last ran, in the order they were pushed, so
+    /// each system sees each event once however often it runs. Outside of systems this is the
+    /// events pushed since the last call to `run_systems` started. Once a system has read an
+    /// event type, events of that type stay stored until it runs again, so a system skipped by
+    /// its run condition still sees them, up to the limit set by `set_event_retention`.
+    /// Panics if the event type hasn't been registered.
+    pub fn read_events<T: 'static>(&self) -> impl Iterator<Item = &T> + '_ {
+        match self.try_read_events() {
+            Ok(events) => events,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    pub fn try_read_events<T: 'static>(
+        &self,
+    ) -> Result<impl Iterator<Item = &T> + '_, UnregisteredEventError> {
+        let last_run_tick = self.last_run_tick();
+        self.mark_event_reader::<T>();
+        return self
+            .event_channel::<T>()
+            .map(|channel| channel.since(last_run_tick));
+    }
+
+    fn event_channel<T: 'static>(&self) -> Result<&EventChannel<T>, UnregisteredEventError> {
+        parallel::check_read::<T>();
+        return self
+            .events
+            .get::<EventChannel<T>>()
+            .ok_or(UnregisteredEventError {
+                event: type_name::<T>(),
+            });
     }
 
     pub fn add_res<T: 'static>(&mut self, value: T) {
+        self.write_tracker.check_declared::<T>();
         self.resources.insert(value);
     }
 
     pub fn remove_res<T: 'static>(&mut self) {
+        self.write_tracker.check_declared::<T>();
         self.resources.remove::<T>();
     }
 
+    /// Panics if the resource hasn't been added.
     pub fn read_res<T: 'static>(&self) -> &T {
-        self.resources.get::<T>().unwrap()
+        match self.try_read_res() {
+            Ok(res) => res,
+            Err(err) => panic!("{}", err),
+        }
     }
 
+    pub fn try_read_res<T: 'static>(&self) -> Result<&T, MissingResourceError> {
+        parallel::check_read::<T>();
+        return self.resources.get::<T>().ok_or(MissingResourceError {
+            resource: type_name::<T>(),
+        });
+    }
+
+    /// Panics if the resource hasn't been added.
     pub fn write_res<T: 'static>(&mut self) -> &mut T {
-        self.resources.get_mut::<T>().unwrap()
+        match self.try_write_res() {
+            Ok(res) => res,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    pub fn try_write_res<T: 'static>(&mut self) -> Result<&mut T, MissingResourceError> {
+        self.write_tracker.check_declared::<T>();
+        return self.resources.get_mut::<T>().ok_or(MissingResourceError {
+            resource: type_name::<T>(),
+        });
     }
 
+    /// Returns `None` for deleted entities, including stale handles to a reused slot.
     pub fn read<T: 'static + Debug>(&self, entity: &Entity) -> Option<&T> {
-        if let Some(map) = self.components.get::<EntityMap<T>>() {
-            if let Some(Some(i)) = map.0.get(entity.index) {
-                return Some(&i.value);
-            }
+        if let Some(map) = self.storage::<T>() {
+            return map.get(*entity);
         }
         None
     }
 
+    /// Like `read`, but the error names the entity and the missing component type.
+    pub fn try_read<T: 'static + Debug>(
+        &self,
+        entity: &Entity,
+    ) -> Result<&T, MissingComponentError> {
+        return self.read::<T>(entity).ok_or(MissingComponentError {
+            entity: *entity,
+            component: type_name::<T>(),
+        });
+    }
+
+    /// Like `write`, but the error names the entity and the missing component type.
+    pub fn try_write<T: 'static>(
+        &mut self,
+        entity: &Entity,
+    ) -> Result<&mut T, MissingComponentError> {
+        return self.write::<T>(entity).ok_or(MissingComponentError {
+            entity: *entity,
+            component: type_name::<T>(),
+        });
+    }
+
+    pub(crate) fn storage<T: 'static>(&self) -> Option<&EntityMap<T>> {
+        parallel::check_read::<T>();
+        return self.components.get::<EntityMap<T>>();
+    }
+
+    /// The tick `Changed` queries compare against: the last time the running system ran.
+    pub(crate) fn last_run_tick(&self) -> u64 {
+        return parallel::running_last_run_tick().unwrap_or(self.last_run_tick);
+    }
+
+    /// Mutable access to a component. This marks the component as changed for `Changed` queries.
+    /// Returns `None` for deleted entities, including stale handles to a reused slot.
     pub fn write<T: 'static>(&mut self, entity: &Entity) -> Option<&mut T> {
         if let Some(map) = self.components.get_mut::<EntityMap<T>>() {
-            if let Some(Some(val)) = map.0.get_mut(entity.index) {
-                return Some(&mut val.value);
+            self.change_tick += 1;
+            let value = map.write(*entity, self.change_tick);
+            if value.is_some() {
+                self.write_tracker.record::<T>(*entity);
             }
+            return value;
         }
         None
     }
 
+    /// Iterates over every live entity that has the requested components, without allocating.
+    /// For example `ecs.query::<(Entity, &Position, &Velocity)>()`.
+    pub fn query<Q: QueryData>(&self) -> Query<'_, Q> {
+        return Query::new(self);
+    }
+
     impl_read!(read1, [t1]);
     impl_read!(read2, [t1, t2]);
     impl_read!(read3, [t1, t2, t3]);
@@ -312,10 +681,20 @@ impl ECS {
             entity_allocator: GenIndexAllocator::new(),
             components: AnyMap::new(),
             resources: AnyMap::new(),
-            systems: Vec::new(),
-            queued_events: AnyMap::new(),
-            processing_events: AnyMap::new(),
-            cleanup_closures: Vec::new(),
+            schedule: Schedule::new(),
+            thread_pool: None,
+            write_tracker: WriteTracker::new(),
+            events: AnyMap::new(),
+            event_updaters: Vec::new(),
+            hooks: AnyMap::new(),
+            saved_types: SavedTypes::default(),
+            snapshot_types: SnapshotTypes::default(),
+            prefabs: HashMap::new(),
+            component_types: Vec::new(),
+            change_tick: 0,
+            last_run_tick: 0,
+            running_serial: None,
+            event_retention: 60,
         }
     }
 }
@@ -337,6 +716,11 @@ impl<'a> EntityBuilder<'a> {
         self
     }
 
+    pub fn add_bundle<B: Bundle>(self, bundle: B) -> EntityBuilder<'a> {
+        bundle.add_to(self.ecs, &self.entity);
+        self
+    }
+
     pub fn entity(&mut self) -> Entity {
         self.entity
     }
@@ -390,4 +774,69 @@ mod tests {
         let component = ecs.write::<TestComponent>(&entity);
         assert!(component.is_some());
     }
+
+    #[test]
+    fn deleting_entity_drops_its_components() {
+        let mut ecs = ECS::new();
+        let entity = ecs.new_entity();
+        ecs.add_component(&entity, TestComponent {});
+        ecs.delete_entity(&entity);
+
+        assert!(ecs.read::<TestComponent>(&entity).is_none());
+        assert!(ecs.get1::<TestComponent>().is_empty());
+
+        ecs.add_component(&entity, TestComponent {});
+        assert!(ecs.get1::<TestComponent>().is_empty());
+    }
+
+    #[test]
+    fn deleting_stale_entity_leaves_reused_slot_alone() {
+        let mut ecs = ECS::new();
+        let stale = ecs.new_entity();
+        ecs.delete_entity(&stale);
+        let reused = ecs.new_entity();
+        ecs.add_component(&reused, TestComponent {});
+
+        ecs.delete_entity(&stale);
+
+        assert_eq!(stale.index, reused.index);
+        assert_eq!(ecs.get1::<TestComponent>(), vec![reused]);
+    }
+
+    struct Gold(u32);
+
+    #[test]
+    fn missing_resource_and_event_errors_name_the_type() {
+        let mut ecs = ECS::new();
+
+        let err = ecs.try_read_res::<Gold>().err().unwrap();
+        assert!(err.to_string().contains("Gold"));
+        assert!(ecs.try_write_res::<Gold>().is_err());
+        assert!(ecs.try_push_event(Gold(1)).is_err());
+        assert!(ecs.try_events::<Gold>().is_err());
+
+        ecs.add_res(Gold(3));
+        ecs.register_event_type::<Gold>();
+        ecs.try_write_res::<Gold>().unwrap().0 += 1;
+        assert_eq!(ecs.try_read_res::<Gold>().unwrap().0, 4);
+        assert!(ecs.try_push_event(Gold(1)).is_ok());
+    }
+
+    #[test]
+    fn missing_component_error_names_entity_and_type() {
+        let mut ecs = ECS::new();
+        let entity = ecs.new_entity();
+
+        let err = ecs.try_read::<TestComponent>(&entity).err().unwrap();
+        assert_eq!(err.entity, entity);
+        assert!(err.to_string().contains("TestComponent"));
+        assert!(ecs.try_write::<TestComponent>(&entity).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "Gold has not been added")]
+    fn read_res_panic_names_the_type() {
+        let ecs = ECS::new();
+        ecs.read_res::<Gold>();
+    }
 }
@@ -0,0 +1,317 @@
+use crate::{Mutation, ECS};
+use std::any::{type_name, TypeId};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+pub type SendMutations = Vec<Box<dyn Mutation + Send>>;
+
+/// A system that can run on a worker thread. It may only read the component, resource and event
+/// types declared on its `SystemBuilder`, and anything it returns must be safe to send back to
+/// the main thread.
+pub type ParallelSystem = dyn Fn(&ECS, f32) -> SendMutations + Send + Sync;
+
+/// The types a parallel system reads while running, and the types its mutations write.
+#[derive(Default, Clone, Debug)]
+pub struct SystemAccess {
+    reads: HashMap<TypeId, &'static str>,
+    writes: HashMap<TypeId, &'static str>,
+}
+
+impl SystemAccess {
+    pub(crate) fn add_read<T: 'static>(&mut self) {
+        self.reads.insert(TypeId::of::<T>(), type_name::<T>());
+    }
+
+    pub(crate) fn add_write<T: 'static>(&mut self) {
+        self.writes.insert(TypeId::of::<T>(), type_name::<T>());
+    }
+
+    pub fn reads(&self, id: TypeId) -> bool {
+        self.reads.contains_key(&id)
+    }
+
+    pub fn writes(&self, id: TypeId) -> bool {
+        self.writes.contains_key(&id)
+    }
+
+    /// True if running a system with `later` access after this one's mutations have been applied
+    /// could give a different result than running both against the same world.
+    pub fn conflicts_with(&self, later: &SystemAccess) -> bool {
+        self.writes.keys().any(|id| later.reads(*id))
+    }
+}
+
+struct RunningSystem {
+    index: usize,
+    name: String,
+    access: Arc<SystemAccess>,
+    last_run_tick: u64,
+}
+
+thread_local! {
+    static RUNNING: RefCell<Option<RunningSystem>> = const { RefCell::new(None) };
+}
+
+/// Marks a parallel system as running on this thread until dropped, restoring whatever was
+/// running before.
+pub(crate) struct RunningGuard {
+    previous: Option<RunningSystem>,
+}
+
+impl RunningGuard {
+    pub(crate) fn enter(
+        index: usize,
+        name: &str,
+        access: &Arc<SystemAccess>,
+        last_run_tick: u64,
+    ) -> Self {
+        let running = RunningSystem {
+            index,
+            name: name.to_string(),
+            access: access.clone(),
+            last_run_tick,
+        };
+        let previous = RUNNING.with(|current| current.borrow_mut().replace(running));
+        return RunningGuard { previous };
+    }
+}
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        RUNNING.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+/// Panics if a parallel system is running on this thread and didn't declare that it reads `T`.
+/// Undeclared types may not be safe to share between threads.
+pub(crate) fn check_read<T: 'static>() {
+    RUNNING.with(|current| {
+        if let Some(running) = &*current.borrow() {
+            if !running.access.reads(TypeId::of::<T>()) {
+                panic!(
+                    "Parallel system {} reads {} without declaring it",
+                    running.name,
+                    type_name::<T>()
+                );
+            }
+        }
+    });
+}
+
+/// The tick `Changed` queries compare against for the parallel system running on this thread.
+pub(crate) fn running_last_run_tick() -> Option<u64> {
+    RUNNING.with(|current| {
+        current
+            .borrow()
+            .as_ref()
+            .map(|running| running.last_run_tick)
+    })
+}
+
+/// The schedule index of the parallel system running on this thread.
+pub(crate) fn running_index() -> Option<usize> {
+    RUNNING.with(|current| current.borrow().as_ref().map(|running| running.index))
+}
+
+/// Lets a batch of parallel systems share the world across threads.
+pub(crate) struct SharedWorld<'a>(pub(crate) &'a ECS);
+
+// Safety: while a batch runs the world is only borrowed immutably, and every read a parallel
+// system makes goes through `check_read`, which only allows types declared with
+// `SystemBuilder::reads`. That requires them to be `Send + Sync`.
+unsafe impl Sync for SharedWorld<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::schedule::Stage;
+    use crate::{Entity, Mutation, ECS};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{mpsc, Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    #[derive(Debug)]
+    struct Position(i32);
+
+    #[derive(Debug)]
+    struct Velocity(i32);
+
+    #[derive(Debug)]
+    struct Health;
+
+    struct SetPosition(Entity, i32);
+
+    impl Mutation for SetPosition {
+        fn apply(&self, ecs: &mut ECS) {
+            ecs.write::<Position>(&self.0).unwrap().0 = self.1;
+        }
+    }
+
+    struct Log(Mutex<Vec<&'static str>>);
+
+    impl Mutation for Log {
+        fn apply(&self, ecs: &mut ECS) {
+            ecs.write_res::<Vec<&'static str>>()
+                .extend(self.0.lock().unwrap().iter());
+        }
+    }
+
+    fn log(name: &'static str) -> Box<dyn Mutation + Send> {
+        Box::new(Log(Mutex::new(vec![name])))
+    }
+
+    #[test]
+    fn test_non_conflicting_systems_run_together() {
+        let mut ecs = ECS::new();
+        let (a_started, a_seen) = mpsc::channel();
+        let (b_started, b_seen) = mpsc::channel();
+        let met = Arc::new(AtomicUsize::new(0));
+        // Each system waits for the other to start, which only succeeds if they run at the same time
+        for (name, started, other) in [("a", a_started, b_seen), ("b", b_started, a_seen)] {
+            let started = Mutex::new(started);
+            let other = Mutex::new(other);
+            let met = met.clone();
+            ecs.register_parallel_system(
+                name,
+                Box::new(move |_ecs: &ECS, _dt: f32| {
+                    started.lock().unwrap().send(()).unwrap();
+                    let other = other.lock().unwrap();
+                    if other.recv_timeout(Duration::from_secs(5)).is_ok() {
+                        met.fetch_add(1, Ordering::SeqCst);
+                    }
+                    vec![]
+                }),
+            );
+        }
+
+        ecs.set_worker_threads(2);
+        ecs.run_systems(0.);
+
+        assert_eq!(met.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_mutations_apply_in_schedule_order() {
+        let mut ecs = ECS::new();
+        ecs.add_res::<Vec<&'static str>>(vec![]);
+        let (fast_done, fast_seen) = mpsc::channel();
+        let fast_done = Mutex::new(fast_done);
+        let fast_seen = Mutex::new(fast_seen);
+        // "slow" finishes after "fast", but its mutations still come first
+        ecs.register_parallel_system(
+            "slow",
+            Box::new(move |_ecs: &ECS, _dt: f32| {
+                let fast_seen = fast_seen.lock().unwrap();
+                let _ = fast_seen.recv_timeout(Duration::from_secs(5));
+                vec![log("slow")]
+            }),
+        )
+        .writes::<Vec<&'static str>>();
+        ecs.register_parallel_system(
+            "fast",
+            Box::new(move |_ecs: &ECS, _dt: f32| {
+                fast_done.lock().unwrap().send(()).unwrap();
+                vec![log("fast")]
+            }),
+        )
+        .writes::<Vec<&'static str>>();
+
+        ecs.set_worker_threads(2);
+        ecs.run_systems(0.);
+
+        assert_eq!(*ecs.read_res::<Vec<&'static str>>(), vec!["slow", "fast"]);
+    }
+
+    #[test]
+    fn test_reader_sees_conflicting_writes() {
+        let mut ecs = ECS::new();
+        let entity = ecs.build_entity().add_component(Position(0)).entity();
+        ecs.add_component(&entity, Velocity(3));
+        ecs.add_component(&entity, Health);
+        ecs.register_parallel_system(
+            "movement",
+            Box::new(|ecs: &ECS, _dt: f32| {
+                let mut mutations: Vec<Box<dyn Mutation + Send>> = vec![];
+                for (position, velocity, entity) in ecs.read2::<Position, Velocity>() {
+                    mutations.push(Box::new(SetPosition(entity, position.0 + velocity.0)));
+                }
+                mutations
+            }),
+        )
+        .reads::<Position>()
+        .reads::<Velocity>()
+        .writes::<Position>();
+        let seen = Arc::new(AtomicUsize::new(0));
+        let seen_in_system = seen.clone();
+        ecs.register_parallel_system(
+            "observer",
+            Box::new(move |ecs: &ECS, _dt: f32| {
+                let (position, _) = ecs.query::<(&Position, &Health)>().next().unwrap();
+                seen_in_system.store(position.0 as usize, Ordering::SeqCst);
+                vec![]
+            }),
+        )
+        .reads::<Position>()
+        .reads::<Health>();
+
+        ecs.run_systems(0.);
+
+        assert_eq!(seen.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "without declaring it")]
+    fn test_undeclared_read_panics() {
+        let mut ecs = ECS::new();
+        ecs.build_entity().add_component(Position(0));
+        ecs.register_parallel_system(
+            "sneaky",
+            Box::new(|ecs: &ECS, _dt: f32| {
+                let _ = ecs.read1::<Position>();
+                vec![]
+            }),
+        );
+        ecs.run_systems(0.);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Parallel system careless writes")]
+    fn test_undeclared_write_panics() {
+        let mut ecs = ECS::new();
+        let entity = ecs.build_entity().add_component(Position(0)).entity();
+        ecs.register_parallel_system(
+            "careless",
+            Box::new(move |_ecs: &ECS, _dt: f32| {
+                let mutations: Vec<Box<dyn Mutation + Send>> =
+                    vec![Box::new(SetPosition(entity, 1))];
+                mutations
+            }),
+        )
+        .writes::<Velocity>();
+        ecs.run_systems(0.);
+    }
+
+    #[test]
+    fn test_stages_are_not_merged() {
+        let mut ecs = ECS::new();
+        ecs.add_res::<Vec<&'static str>>(vec![]);
+        ecs.register_parallel_system("late", Box::new(|_ecs: &ECS, _dt: f32| vec![log("late")]))
+            .stage(Stage::PostUpdate)
+            .writes::<Vec<&'static str>>();
+        ecs.register_parallel_system(
+            "early",
+            Box::new(|_ecs: &ECS, _dt: f32| {
+                thread::sleep(Duration::from_millis(20));
+                vec![log("early")]
+            }),
+        )
+        .writes::<Vec<&'static str>>();
+
+        ecs.run_systems(0.);
+
+        assert_eq!(*ecs.read_res::<Vec<&'static str>>(), vec!["early", "late"]);
+    }
+}
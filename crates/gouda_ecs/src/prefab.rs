@@ -0,0 +1,153 @@
+use crate::bundle::Bundle;
+use crate::save::{EntityMapper, Insert, SaveError};
+use crate::{Entity, ECS};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+
+/// A template for an entity: its components, keyed by the names they were registered for saving
+/// under, and prefabs for any children to spawn with it.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Prefab {
+    #[serde(default)]
+    pub components: BTreeMap<String, Value>,
+    #[serde(default)]
+    pub children: Vec<Prefab>,
+}
+
+struct PreparedPrefab {
+    inserts: Vec<Insert>,
+    children: Vec<PreparedPrefab>,
+}
+
+impl ECS {
+    /// Adds or replaces the prefab called `name`.
+    pub fn add_prefab(&mut self, name: &str, prefab: Prefab) {
+        self.prefabs.insert(name.to_string(), prefab);
+    }
+
+    /// Reads a JSON object of prefabs by name and adds them all.
+    pub fn load_prefabs_from<R: io::Read>(&mut self, reader: R) -> Result<(), SaveError> {
+        let prefabs: HashMap<String, Prefab> = serde_json::from_reader(reader)?;
+        self.prefabs.extend(prefabs);
+        return Ok(());
+    }
+
+    pub fn spawn_prefab(&mut self, name: &str) -> Result<Entity, SaveError> {
+        return self.spawn_prefab_with(name, ());
+    }
+
+    /// Spawns the prefab called `name` and its children, then adds `overrides` to the root,
+    /// replacing any components of the same types. Entity references in the prefab's components
+    /// load as `Entity::DEAD`. Nothing is spawned if any component fails to load.
+    pub fn spawn_prefab_with<B: Bundle>(
+        &mut self,
+        name: &str,
+        overrides: B,
+    ) -> Result<Entity, SaveError> {
+        let prefab = self
+            .prefabs
+            .get(name)
+            .ok_or_else(|| SaveError::UnknownPrefab(name.to_string()))?;
+        let prepared = self.prepare_prefab(prefab)?;
+        let entity = self.spawn_prepared(prepared, None);
+        overrides.add_to(self, &entity);
+        return Ok(entity);
+    }
+
+    fn prepare_prefab(&self, prefab: &Prefab) -> Result<PreparedPrefab, SaveError> {
+        let mapper = EntityMapper::default();
+        let mut inserts = vec![];
+        for (name, value) in &prefab.components {
+            let component = self
+                .saved_types
+                .components
+                .get(name)
+                .ok_or_else(|| SaveError::UnknownComponent(name.clone()))?;
+            inserts.push((component.load)(value.clone(), &mapper)?);
+        }
+        let children = prefab
+            .children
+            .iter()
+            .map(|child| self.prepare_prefab(child))
+            .collect::<Result<_, _>>()?;
+        return Ok(PreparedPrefab { inserts, children });
+    }
+
+    fn spawn_prepared(&mut self, prepared: PreparedPrefab, parent: Option<Entity>) -> Entity {
+        let entity = self.new_entity();
+        for insert in prepared.inserts {
+            insert(self, &entity);
+        }
+        if let Some(parent) = parent {
+            self.set_parent(&entity, &parent);
+        }
+        for child in prepared.children {
+            self.spawn_prepared(child, Some(entity));
+        }
+        return entity;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::save::SaveError;
+    use crate::ECS;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Position(i32, i32);
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Name(String);
+
+    fn world() -> ECS {
+        let mut ecs = ECS::new();
+        ecs.register_saved_component::<Position>("position");
+        ecs.register_saved_component::<Name>("name");
+        let data = r#"{
+            "turret": {
+                "components": {"name": "turret", "position": [0, 0]},
+                "children": [
+                    {"components": {"name": "barrel"}},
+                    {"components": {"name": "base"}, "children": [{"components": {"name": "bolt"}}]}
+                ]
+            },
+            "broken": {"components": {"velocity": [1, 0]}}
+        }"#;
+        ecs.load_prefabs_from(data.as_bytes()).unwrap();
+        return ecs;
+    }
+
+    #[test]
+    fn test_prefab_spawns_nested_children_with_overrides() {
+        let mut ecs = world();
+
+        let turret = ecs.spawn_prefab_with("turret", (Position(3, 4),)).unwrap();
+
+        assert_eq!(ecs.read::<Position>(&turret), Some(&Position(3, 4)));
+        assert_eq!(ecs.read::<Name>(&turret), Some(&Name("turret".into())));
+        let children = ecs.children(&turret).to_vec();
+        let names: Vec<&str> = children
+            .iter()
+            .map(|child| ecs.read::<Name>(child).unwrap().0.as_str())
+            .collect();
+        assert_eq!(names, vec!["barrel", "base"]);
+        let bolt = ecs.children(&children[1])[0];
+        assert_eq!(ecs.read::<Name>(&bolt), Some(&Name("bolt".into())));
+        assert!(ecs.read::<Position>(&children[0]).is_none());
+    }
+
+    #[test]
+    fn test_bad_prefabs_spawn_nothing() {
+        let mut ecs = world();
+
+        let missing = ecs.spawn_prefab("tower").unwrap_err();
+        let broken = ecs.spawn_prefab("broken").unwrap_err();
+
+        assert!(matches!(missing, SaveError::UnknownPrefab(_)));
+        assert!(matches!(broken, SaveError::UnknownComponent(_)));
+        assert_eq!(ecs.query::<crate::Entity>().count(), 0);
+    }
+}
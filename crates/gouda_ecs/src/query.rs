@@ -0,0 +1,447 @@
+use crate::genindex::GenIndexAllocator;
+use crate::{Entity, EntityMap, ECS};
+use std::marker::PhantomData;
+
+/// Something that can be fetched per entity by `ECS::query`. Implemented for `&T` to borrow a
+/// component, for `Entity` to get the entity itself, for `Option<Q>` to make any term optional,
+/// for the `With`, `Without` and `Changed` filters, and for tuples of up to 12 of these. Tuples
+/// can be nested to query more.
+pub trait QueryData {
+    type Item<'a>;
+    type Fetch<'a>;
+
+    /// Looks up the storage this query reads from. Returns None if no entity can match.
+    fn init(ecs: &ECS) -> Option<Self::Fetch<'_>>;
+
+    /// Every entity that can match, if this term narrows the query down. The query iterates over
+    /// the shortest list offered by its terms, or every live entity if none offer one.
+    fn candidates<'a>(fetch: &Self::Fetch<'a>) -> Option<&'a [Entity]>;
+
+    fn fetch<'a>(fetch: &Self::Fetch<'a>, entity: Entity) -> Option<Self::Item<'a>>;
+}
+
+impl<T: 'static> QueryData for &T {
+    type Item<'a> = &'a T;
+    type Fetch<'a> = &'a EntityMap<T>;
+
+    fn init(ecs: &ECS) -> Option<Self::Fetch<'_>> {
+        return ecs.storage::<T>();
+    }
+
+    fn candidates<'a>(fetch: &Self::Fetch<'a>) -> Option<&'a [Entity]> {
+        return Some(fetch.entities());
+    }
+
+    fn fetch<'a>(fetch: &Self::Fetch<'a>, entity: Entity) -> Option<Self::Item<'a>> {
+        return fetch.get(entity);
+    }
+}
+
+impl QueryData for Entity {
+    type Item<'a> = Entity;
+    type Fetch<'a> = ();
+
+    fn init(_ecs: &ECS) -> Option<Self::Fetch<'_>> {
+        return Some(());
+    }
+
+    fn candidates<'a>(_fetch: &Self::Fetch<'a>) -> Option<&'a [Entity]> {
+        return None;
+    }
+
+    fn fetch<'a>(_fetch: &Self::Fetch<'a>, entity: Entity) -> Option<Self::Item<'a>> {
+        return Some(entity);
+    }
+}
+
+impl<Q: QueryData> QueryData for Option<Q> {
+    type Item<'a> = Option<Q::Item<'a>>;
+    type Fetch<'a> = Option<Q::Fetch<'a>>;
+
+    fn init(ecs: &ECS) -> Option<Self::Fetch<'_>> {
+        return Some(Q::init(ecs));
+    }
+
+    fn candidates<'a>(_fetch: &Self::Fetch<'a>) -> Option<&'a [Entity]> {
+        return None;
+    }
+
+    fn fetch<'a>(fetch: &Self::Fetch<'a>, entity: Entity) -> Option<Self::Item<'a>> {
+        return Some(fetch.as_ref().and_then(|fetch| Q::fetch(fetch, entity)));
+    }
+}
+
+/// Matches entities that have a `T`, without borrowing it.
+pub struct With<T>(PhantomData<T>);
+
+impl<T: 'static> QueryData for With<T> {
+    type Item<'a> = ();
+    type Fetch<'a> = &'a EntityMap<T>;
+
+    fn init(ecs: &ECS) -> Option<Self::Fetch<'_>> {
+        return ecs.storage::<T>();
+    }
+
+    fn candidates<'a>(fetch: &Self::Fetch<'a>) -> Option<&'a [Entity]> {
+        return Some(fetch.entities());
+    }
+
+    fn fetch<'a>(fetch: &Self::Fetch<'a>, entity: Entity) -> Option<Self::Item<'a>> {
+        return fetch.get(entity).map(|_| ());
+    }
+}
+
+/// Matches entities that do not have a `T`.
+pub struct Without<T>(PhantomData<T>);
+
+impl<T: 'static> QueryData for Without<T> {
+    type Item<'a> = ();
+    type Fetch<'a> = Option<&'a EntityMap<T>>;
+
+    fn init(ecs: &ECS) -> Option<Self::Fetch<'_>> {
+        return Some(ecs.storage::<T>());
+    }
+
+    fn candidates<'a>(_fetch: &Self::Fetch<'a>) -> Option<&'a [Entity]> {
+        return None;
+    }
+
+    fn fetch<'a>(fetch: &Self::Fetch<'a>, entity: Entity) -> Option<Self::Item<'a>> {
+        match fetch.and_then(|map| map.get(entity)) {
+            Some(_) => None,
+            None => Some(()),
+        }
+    }
+}
+
+/// Matches entities whose `T` was added or written through `ECS::write` since the running system
+/// last ran. Outside of systems this is anything changed during the last `ECS::run_systems`.
+pub struct Changed<T>(PhantomData<T>);
+
+impl<T: 'static> QueryData for Changed<T> {
+    type Item<'a> = ();
+    type Fetch<'a> = (&'a EntityMap<T>, u64);
+
+    fn init(ecs: &ECS) -> Option<Self::Fetch<'_>> {
+        let map = ecs.storage::<T>()?;
+        return Some((map, ecs.last_run_tick()));
+    }
+
+    fn candidates<'a>(fetch: &Self::Fetch<'a>) -> Option<&'a [Entity]> {
+        return Some(fetch.0.entities());
+    }
+
+    fn fetch<'a>(fetch: &Self::Fetch<'a>, entity: Entity) -> Option<Self::Item<'a>> {
+        let (map, last_run_tick) = fetch;
+        if map.changed_tick(entity)? > *last_run_tick {
+            return Some(());
+        }
+        return None;
+    }
+}
+
+macro_rules! impl_query_tuple {
+    ( $($q:ident),* ) => {
+        impl<$($q: QueryData),*> QueryData for ($($q,)*) {
+            type Item<'a> = ($($q::Item<'a>,)*);
+            type Fetch<'a> = ($($q::Fetch<'a>,)*);
+
+            fn init(ecs: &ECS) -> Option<Self::Fetch<'_>> {
+                return Some(($($q::init(ecs)?,)*));
+            }
+
+            fn candidates<'a>(fetch: &Self::Fetch<'a>) -> Option<&'a [Entity]> {
+                let ($($q,)*) = fetch;
+                let mut shortest: Option<&'a [Entity]> = None;
+                $(
+                    if let Some(candidates) = $q::candidates($q) {
+                        if shortest.map_or(true, |s| candidates.len() < s.len()) {
+                            shortest = Some(candidates);
+                        }
+                    }
+                )*
+                return shortest;
+            }
+
+            fn fetch<'a>(fetch: &Self::Fetch<'a>, entity: Entity) -> Option<Self::Item<'a>> {
+                let ($($q,)*) = fetch;
+                return Some(($($q::fetch($q, entity)?,)*));
+            }
+        }
+    };
+}
+
+impl_query_tuple!(q1);
+impl_query_tuple!(q1, q2);
+impl_query_tuple!(q1, q2, q3);
+impl_query_tuple!(q1, q2, q3, q4);
+impl_query_tuple!(q1, q2, q3, q4, q5);
+impl_query_tuple!(q1, q2, q3, q4, q5, q6);
+impl_query_tuple!(q1, q2, q3, q4, q5, q6, q7);
+impl_query_tuple!(q1, q2, q3, q4, q5, q6, q7, q8);
+impl_query_tuple!(q1, q2, q3, q4, q5, q6, q7, q8, q9);
+impl_query_tuple!(q1, q2, q3, q4, q5, q6, q7, q8, q9, q10);
+impl_query_tuple!(q1, q2, q3, q4, q5, q6, q7, q8, q9, q10, q11);
+impl_query_tuple!(q1, q2, q3, q4, q5, q6, q7, q8, q9, q10, q11, q12);
+
+/// A lazy iterator over every live entity that matches `Q`.
+pub struct Query<'a, Q: QueryData> {
+    fetch: Option<Q::Fetch<'a>>,
+    candidates: Option<&'a [Entity]>,
+    allocator: &'a GenIndexAllocator,
+    index: usize,
+    end: usize,
+    marker: PhantomData<Q>,
+}
+
+impl<'a, Q: QueryData> Query<'a, Q> {
+    pub(crate) fn new(ecs: &'a ECS) -> Self {
+        let fetch = Q::init(ecs);
+        let candidates = fetch.as_ref().and_then(|fetch| Q::candidates(fetch));
+        let end = match (&fetch, candidates) {
+            (None, _) => 0,
+            (Some(_), Some(candidates)) => candidates.len(),
+            (Some(_), None) => ecs.entity_allocator.index_bound(),
+        };
+        return Query {
+            fetch,
+            candidates,
+            allocator: &ecs.entity_allocator,
+            index: 0,
+            end,
+            marker: PhantomData,
+        };
+    }
+
+    fn entity_at(&self, index: usize) -> Option<Entity> {
+        match self.candidates {
+            // Component storage never holds dead entities, so these need no liveness check
+            Some(candidates) => Some(candidates[index]),
+            None => self.allocator.live_at(index),
+        }
+    }
+}
+
+impl<'a, Q: QueryData> Iterator for Query<'a, Q> {
+    type Item = Q::Item<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.index < self.end {
+            let index = self.index;
+            self.index += 1;
+            if let Some(entity) = self.entity_at(index) {
+                if let Some(item) = Q::fetch(self.fetch.as_ref()?, entity) {
+                    return Some(item);
+                }
+            }
+        }
+        return None;
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        return (0, Some(self.end - self.index));
+    }
+
+    // Walking the candidate slice directly lets collect and for_each compile to a tight loop
+    fn fold<B, F>(mut self, init: B, mut f: F) -> B
+    where
+        F: FnMut(B, Self::Item) -> B,
+    {
+        let mut acc = init;
+        let fetch = match self.fetch.take() {
+            Some(fetch) => fetch,
+            None => return acc,
+        };
+        match self.candidates {
+            Some(candidates) => {
+                for entity in &candidates[self.index..self.end] {
+                    if let Some(item) = Q::fetch(&fetch, *entity) {
+                        acc = f(acc, item);
+                    }
+                }
+            }
+            None => {
+                for index in self.index..self.end {
+                    if let Some(entity) = self.allocator.live_at(index) {
+                        if let Some(item) = Q::fetch(&fetch, entity) {
+                            acc = f(acc, item);
+                        }
+                    }
+                }
+            }
+        }
+        return acc;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Changed, With, Without};
+    use crate::{Entity, Mutation, Mutations, ECS};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[derive(Debug, PartialEq)]
+    struct Position(i32);
+
+    #[derive(Debug, PartialEq)]
+    struct Velocity(i32);
+
+    #[derive(Debug, PartialEq)]
+    struct Health(i32);
+
+    #[test]
+    fn test_query_matches_entities_with_all_components() {
+        let mut ecs = ECS::new();
+        let both = ecs.new_entity();
+        ecs.add_component(&both, Position(1));
+        ecs.add_component(&both, Velocity(2));
+        let position_only = ecs.new_entity();
+        ecs.add_component(&position_only, Position(3));
+
+        let results: Vec<(Entity, &Position, &Velocity)> =
+            ecs.query::<(Entity, &Position, &Velocity)>().collect();
+        assert_eq!(results, vec![(both, &Position(1), &Velocity(2))]);
+
+        let positions: Vec<&Position> = ecs.query::<&Position>().collect();
+        assert_eq!(positions, vec![&Position(1), &Position(3)]);
+    }
+
+    #[test]
+    fn test_query_skips_deleted_entities() {
+        let mut ecs = ECS::new();
+        let dead = ecs.new_entity();
+        ecs.add_component(&dead, Position(1));
+        let alive = ecs.new_entity();
+        ecs.add_component(&alive, Position(2));
+        ecs.delete_entity(&dead);
+
+        let results: Vec<Entity> = ecs.query::<(Entity, &Position)>().map(|(e, _)| e).collect();
+        assert_eq!(results, vec![alive]);
+    }
+
+    #[test]
+    fn test_query_with_missing_component_type_is_empty() {
+        let mut ecs = ECS::new();
+        let entity = ecs.new_entity();
+        ecs.add_component(&entity, Position(1));
+
+        assert_eq!(ecs.query::<(&Position, &Health)>().count(), 0);
+    }
+
+    #[test]
+    fn test_query_has_no_entity_cap() {
+        let mut ecs = ECS::new();
+        for i in 0..5000 {
+            let entity = ecs.new_entity();
+            ecs.add_component(&entity, Position(i));
+        }
+
+        assert_eq!(ecs.query::<&Position>().count(), 5000);
+        assert_eq!(ecs.get1::<Position>().len(), 5000);
+    }
+
+    #[test]
+    fn test_nested_tuples_extend_arity() {
+        let mut ecs = ECS::new();
+        let entity = ecs.new_entity();
+        ecs.add_component(&entity, Position(1));
+        ecs.add_component(&entity, Velocity(2));
+        ecs.add_component(&entity, Health(3));
+
+        let ((position, velocity), health) = ecs
+            .query::<((&Position, &Velocity), &Health)>()
+            .next()
+            .unwrap();
+        assert_eq!(position.0 + velocity.0 + health.0, 6);
+    }
+
+    #[test]
+    fn test_with_and_without_filters() {
+        let mut ecs = ECS::new();
+        let moving = ecs.new_entity();
+        ecs.add_component(&moving, Position(1));
+        ecs.add_component(&moving, Velocity(1));
+        let still = ecs.new_entity();
+        ecs.add_component(&still, Position(2));
+
+        let with: Vec<Entity> = ecs
+            .query::<(Entity, With<Velocity>)>()
+            .map(|(e, _)| e)
+            .collect();
+        assert_eq!(with, vec![moving]);
+
+        let without: Vec<Entity> = ecs
+            .query::<(Entity, &Position, Without<Velocity>)>()
+            .map(|(e, _, _)| e)
+            .collect();
+        assert_eq!(without, vec![still]);
+
+        let all_without_health = ecs.query::<(&Position, Without<Health>)>().count();
+        assert_eq!(all_without_health, 2);
+    }
+
+    #[test]
+    fn test_optional_components() {
+        let mut ecs = ECS::new();
+        let a = ecs.new_entity();
+        ecs.add_component(&a, Position(1));
+        ecs.add_component(&a, Velocity(5));
+        let b = ecs.new_entity();
+        ecs.add_component(&b, Position(2));
+
+        let results: Vec<(&Position, Option<&Velocity>)> =
+            ecs.query::<(&Position, Option<&Velocity>)>().collect();
+        assert_eq!(
+            results,
+            vec![(&Position(1), Some(&Velocity(5))), (&Position(2), None)]
+        );
+
+        let no_health: Vec<Option<&Health>> = ecs.query::<Option<&Health>>().collect();
+        assert_eq!(no_health, vec![None, None]);
+    }
+
+    struct Move(Entity);
+
+    impl Mutation for Move {
+        fn apply(&self, ecs: &mut ECS) {
+            ecs.write::<Position>(&self.0).unwrap().0 += 1;
+        }
+    }
+
+    #[test]
+    fn test_changed_tracks_writes_per_system() {
+        let mut ecs = ECS::new();
+        let a = ecs.new_entity();
+        ecs.add_component(&a, Position(0));
+        let b = ecs.new_entity();
+        ecs.add_component(&b, Position(0));
+
+        let seen = Rc::new(RefCell::new(vec![]));
+        let log = seen.clone();
+        ecs.add_system(Box::new(move |ecs: &ECS, _dt: f32| -> Mutations {
+            let changed: Vec<Entity> = ecs
+                .query::<(Entity, Changed<Position>)>()
+                .map(|(e, _)| e)
+                .collect();
+            log.borrow_mut().push(changed);
+            return vec![];
+        }));
+        ecs.add_system(Box::new(move |_ecs: &ECS, _dt: f32| -> Mutations {
+            return vec![Box::new(Move(b))];
+        }));
+
+        ecs.run_systems(0.);
+        ecs.run_systems(0.);
+        ecs.write::<Position>(&a);
+        ecs.run_systems(0.);
+
+        assert_eq!(*seen.borrow(), vec![vec![a, b], vec![b], vec![a, b]]);
+        // Outside of systems, changes from the last run are visible
+        let changed: Vec<Entity> = ecs
+            .query::<(Entity, Changed<Position>)>()
+            .map(|(e, _)| e)
+            .collect();
+        assert_eq!(changed, vec![b]);
+    }
+}
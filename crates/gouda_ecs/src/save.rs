@@ -0,0 +1,555 @@
+use crate::{Entity, ECS};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::fmt::Debug;
+use std::io;
+
+/// Implemented by saved components and resources that hold entities, so the references can be
+/// pointed at the entities spawned when the save is loaded.
+pub trait MapEntities {
+    fn map_entities(&mut self, mapper: &EntityMapper);
+}
+
+impl MapEntities for Entity {
+    fn map_entities(&mut self, mapper: &EntityMapper) {
+        *self = mapper.map(*self);
+    }
+}
+
+impl<T: MapEntities> MapEntities for Option<T> {
+    fn map_entities(&mut self, mapper: &EntityMapper) {
+        if let Some(value) = self {
+            value.map_entities(mapper);
+        }
+    }
+}
+
+impl<T: MapEntities> MapEntities for Vec<T> {
+    fn map_entities(&mut self, mapper: &EntityMapper) {
+        for value in self {
+            value.map_entities(mapper);
+        }
+    }
+}
+
+impl<T: MapEntities, const N: usize> MapEntities for [T; N] {
+    fn map_entities(&mut self, mapper: &EntityMapper) {
+        for value in self {
+            value.map_entities(mapper);
+        }
+    }
+}
+
+/// Maps the entity ids stored in a save to the entities spawned for them on load.
+#[derive(Debug, Default)]
+pub struct EntityMapper {
+    entities: HashMap<Entity, Entity>,
+}
+
+impl EntityMapper {
+    pub fn get(&self, saved: Entity) -> Option<Entity> {
+        return self.entities.get(&saved).copied();
+    }
+
+    /// Entities that weren't part of the save map to `Entity::DEAD`, so a reference to one can't
+    /// end up pointing at an unrelated live entity.
+    pub fn map(&self, saved: Entity) -> Entity {
+        return self.get(saved).unwrap_or(Entity::DEAD);
+    }
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    Format(serde_json::Error),
+    RonWrite(ron::Error),
+    RonRead(ron::error::SpannedError),
+    UnknownComponent(String),
+    UnknownResource(String),
+    UnknownPrefab(String),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(err) => write!(f, "Couldn't access save data: {}", err),
+            SaveError::Format(err) => write!(f, "Invalid save data: {}", err),
+            SaveError::RonWrite(err) => write!(f, "Couldn't write save data: {}", err),
+            SaveError::RonRead(err) => write!(f, "Invalid save data: {}", err),
+            SaveError::UnknownComponent(name) => {
+                write!(f, "Component {} has not been registered for saving", name)
+            }
+            SaveError::UnknownResource(name) => {
+                write!(f, "Resource {} has not been registered for saving", name)
+            }
+            SaveError::UnknownPrefab(name) => write!(f, "No prefab named {}", name),
+        }
+    }
+}
+
+impl Error for SaveError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SaveError::Io(err) => Some(err),
+            SaveError::Format(err) => Some(err),
+            SaveError::RonWrite(err) => Some(err),
+            SaveError::RonRead(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SaveError {
+    fn from(err: io::Error) -> Self {
+        SaveError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(err: serde_json::Error) -> Self {
+        if err.is_io() {
+            return SaveError::Io(err.into());
+        }
+        SaveError::Format(err)
+    }
+}
+
+// RON only keeps the message of I/O errors
+impl From<ron::Error> for SaveError {
+    fn from(err: ron::Error) -> Self {
+        match err {
+            ron::Error::Io(message) => SaveError::Io(io::Error::other(message)),
+            err => SaveError::RonWrite(err),
+        }
+    }
+}
+
+impl From<ron::error::SpannedError> for SaveError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        match err.code {
+            ron::Error::Io(message) => SaveError::Io(io::Error::other(message)),
+            _ => SaveError::RonRead(err),
+        }
+    }
+}
+
+/// A saved world. Components and resources are keyed by the names they were registered under.
+/// Being plain serde data, it can be written in other formats as well as JSON and RON.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SavedWorld {
+    pub entities: Vec<SavedEntity>,
+    pub resources: BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedEntity {
+    pub id: Entity,
+    pub components: BTreeMap<String, Value>,
+}
+
+pub(crate) type Insert = Box<dyn FnOnce(&mut ECS, &Entity)>;
+type InsertRes = Box<dyn FnOnce(&mut ECS)>;
+
+pub(crate) struct SavedComponent {
+    save: fn(&ECS, &Entity) -> Option<serde_json::Result<Value>>,
+    pub(crate) load: fn(Value, &EntityMapper) -> serde_json::Result<Insert>,
+}
+
+struct SavedResource {
+    save: fn(&ECS) -> Option<serde_json::Result<Value>>,
+    load: fn(Value, &EntityMapper) -> serde_json::Result<InsertRes>,
+}
+
+/// The components and resources that are included in saves, by name.
+#[derive(Default)]
+pub(crate) struct SavedTypes {
+    pub(crate) components: BTreeMap<String, SavedComponent>,
+    resources: BTreeMap<String, SavedResource>,
+}
+
+fn save_component<T: 'static + Serialize>(
+    ecs: &ECS,
+    entity: &Entity,
+) -> Option<serde_json::Result<Value>> {
+    let component = ecs.storage::<T>()?.get(*entity)?;
+    return Some(serde_json::to_value(component));
+}
+
+fn load_component<T: 'static + Debug + DeserializeOwned>(
+    value: Value,
+    _mapper: &EntityMapper,
+) -> serde_json::Result<Insert> {
+    let component: T = serde_json::from_value(value)?;
+    return Ok(Box::new(move |ecs, entity| {
+        ecs.add_component(entity, component)
+    }));
+}
+
+fn load_mapped_component<T: 'static + Debug + DeserializeOwned + MapEntities>(
+    value: Value,
+    mapper: &EntityMapper,
+) -> serde_json::Result<Insert> {
+    let mut component: T = serde_json::from_value(value)?;
+    component.map_entities(mapper);
+    return Ok(Box::new(move |ecs, entity| {
+        ecs.add_component(entity, component)
+    }));
+}
+
+fn save_resource<T: 'static + Serialize>(ecs: &ECS) -> Option<serde_json::Result<Value>> {
+    let resource = ecs.try_read_res::<T>().ok()?;
+    return Some(serde_json::to_value(resource));
+}
+
+fn load_resource<T: 'static + DeserializeOwned>(
+    value: Value,
+    _mapper: &EntityMapper,
+) -> serde_json::Result<InsertRes> {
+    let resource: T = serde_json::from_value(value)?;
+    return Ok(Box::new(move |ecs| ecs.add_res(resource)));
+}
+
+fn load_mapped_resource<T: 'static + DeserializeOwned + MapEntities>(
+    value: Value,
+    mapper: &EntityMapper,
+) -> serde_json::Result<InsertRes> {
+    let mut resource: T = serde_json::from_value(value)?;
+    resource.map_entities(mapper);
+    return Ok(Box::new(move |ecs| ecs.add_res(resource)));
+}
+
+impl ECS {
+    /// Includes `T` in saves under `name`. Panics if the name is already taken.
+    pub fn register_saved_component<T: 'static + Debug + Serialize + DeserializeOwned>(
+        &mut self,
+        name: &str,
+    ) {
+        self.add_saved_component(name, save_component::<T>, load_component::<T>);
+    }
+
+    /// Like `register_saved_component`, for components that refer to other entities.
+    pub fn register_saved_component_with_entities<T>(&mut self, name: &str)
+    where
+        T: 'static + Debug + Serialize + DeserializeOwned + MapEntities,
+    {
+        self.add_saved_component(name, save_component::<T>, load_mapped_component::<T>);
+    }
+
+    /// Includes the `T` resource in saves under `name`. Panics if the name is already taken.
+    pub fn register_saved_resource<T: 'static + Serialize + DeserializeOwned>(
+        &mut self,
+        name: &str,
+    ) {
+        self.add_saved_resource(name, save_resource::<T>, load_resource::<T>);
+    }
+
+    /// Like `register_saved_resource`, for resources that refer to entities.
+    pub fn register_saved_resource_with_entities<T>(&mut self, name: &str)
+    where
+        T: 'static + Serialize + DeserializeOwned + MapEntities,
+    {
+        self.add_saved_resource(name, save_resource::<T>, load_mapped_resource::<T>);
+    }
+
+    fn add_saved_component(
+        &mut self,
+        name: &str,
+        save: fn(&ECS, &Entity) -> Option<serde_json::Result<Value>>,
+        load: fn(Value, &EntityMapper) -> serde_json::Result<Insert>,
+    ) {
+        let components = &mut self.saved_types.components;
+        if components.contains_key(name) {
+            panic!("A saved component named {} is already registered", name);
+        }
+        components.insert(name.to_string(), SavedComponent { save, load });
+    }
+
+    fn add_saved_resource(
+        &mut self,
+        name: &str,
+        save: fn(&ECS) -> Option<serde_json::Result<Value>>,
+        load: fn(Value, &EntityMapper) -> serde_json::Result<InsertRes>,
+    ) {
+        let resources = &mut self.saved_types.resources;
+        if resources.contains_key(name) {
+            panic!("A saved resource named {} is already registered", name);
+        }
+        resources.insert(name.to_string(), SavedResource { save, load });
+    }
+
+    /// Saves every registered component of every live entity, and every registered resource.
+    /// Entities without any registered components are left out, references to them load as
+    /// `Entity::DEAD`.
+    pub fn save(&self) -> Result<SavedWorld, SaveError> {
+        let mut world = SavedWorld::default();
+        for entity in self.query::<Entity>() {
+            let mut components = BTreeMap::new();
+            for (name, component) in &self.saved_types.components {
+                if let Some(value) = (component.save)(self, &entity) {
+                    components.insert(name.clone(), value?);
+                }
+            }
+            if !components.is_empty() {
+                world.entities.push(SavedEntity {
+                    id: entity,
+                    components,
+                });
+            }
+        }
+        for (name, resource) in &self.saved_types.resources {
+            if let Some(value) = (resource.save)(self) {
+                world.resources.insert(name.clone(), value?);
+            }
+        }
+        return Ok(world);
+    }
+
+    /// Spawns a new entity for each saved one and adds its components, pointing any entity
+    /// references at the new entities. Saved resources replace existing ones. If the save can't
+    /// be loaded, no components or resources are added and the spawned entities are deleted
+    /// again, though the entity allocator has still handed out and freed their ids.
+    pub fn load(&mut self, world: SavedWorld) -> Result<EntityMapper, SaveError> {
+        let mut mapper = EntityMapper::default();
+        for saved in &world.entities {
+            let entity = self.new_entity();
+            mapper.entities.insert(saved.id, entity);
+        }
+        let inserts = match self.prepare_load(world, &mapper) {
+            Ok(inserts) => inserts,
+            Err(err) => {
+                for entity in mapper.entities.values() {
+                    self.delete_entity(entity);
+                }
+                return Err(err);
+            }
+        };
+        let (components, resources) = inserts;
+        for (entity, insert) in components {
+            insert(self, &entity);
+        }
+        for insert in resources {
+            insert(self);
+        }
+        return Ok(mapper);
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn prepare_load(
+        &self,
+        world: SavedWorld,
+        mapper: &EntityMapper,
+    ) -> Result<(Vec<(Entity, Insert)>, Vec<InsertRes>), SaveError> {
+        let mut components = vec![];
+        for saved in world.entities {
+            let entity = mapper.map(saved.id);
+            for (name, value) in saved.components {
+                let component = self
+                    .saved_types
+                    .components
+                    .get(&name)
+                    .ok_or(SaveError::UnknownComponent(name))?;
+                components.push((entity, (component.load)(value, mapper)?));
+            }
+        }
+        let mut resources = vec![];
+        for (name, value) in world.resources {
+            let resource = self
+                .saved_types
+                .resources
+                .get(&name)
+                .ok_or(SaveError::UnknownResource(name))?;
+            resources.push((resource.load)(value, mapper)?);
+        }
+        return Ok((components, resources));
+    }
+
+    /// Writes `save()` as JSON.
+    pub fn save_to<W: io::Write>(&self, writer: W) -> Result<(), SaveError> {
+        let world = self.save()?;
+        serde_json::to_writer_pretty(writer, &world)?;
+        return Ok(());
+    }
+
+    /// Reads JSON written by `save_to` and `load`s it.
+    pub fn load_from<R: io::Read>(&mut self, reader: R) -> Result<EntityMapper, SaveError> {
+        let world: SavedWorld = serde_json::from_reader(reader)?;
+        return self.load(world);
+    }
+
+    /// Writes `save()` as RON.
+    pub fn save_to_ron<W: io::Write>(&self, writer: W) -> Result<(), SaveError> {
+        let world = self.save()?;
+        ron::ser::to_writer_pretty(writer, &world, ron::ser::PrettyConfig::default())?;
+        return Ok(());
+    }
+
+    /// Reads RON written by `save_to_ron` and `load`s it.
+    pub fn load_from_ron<R: io::Read>(&mut self, reader: R) -> Result<EntityMapper, SaveError> {
+        let world: SavedWorld = ron::de::from_reader(reader)?;
+        return self.load(world);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MapEntities, SaveError};
+    use crate::{Entity, ECS};
+    use serde::{Deserialize, Serialize};
+    use std::io;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Position(i32, i32);
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Follow(Entity);
+
+    impl MapEntities for Follow {
+        fn map_entities(&mut self, mapper: &super::EntityMapper) {
+            self.0.map_entities(mapper);
+        }
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Score(u32);
+
+    #[derive(Debug)]
+    struct NotSaved;
+
+    fn registered() -> ECS {
+        let mut ecs = ECS::new();
+        ecs.register_saved_component::<Position>("position");
+        ecs.register_saved_component_with_entities::<Follow>("follow");
+        ecs.register_saved_resource::<Score>("score");
+        return ecs;
+    }
+
+    #[test]
+    fn test_round_trip_remaps_entity_references() {
+        let mut ecs = registered();
+        ecs.new_entity();
+        let leader = ecs.build_entity().add_component(Position(1, 2)).entity();
+        let follower = ecs
+            .build_entity()
+            .add_component(Position(3, 4))
+            .add_component(Follow(leader))
+            .add_component(NotSaved)
+            .entity();
+        ecs.add_res(Score(7));
+        let mut buffer = vec![];
+        ecs.save_to(&mut buffer).unwrap();
+
+        let mut loaded = registered();
+        let mapper = loaded.load_from(buffer.as_slice()).unwrap();
+
+        let new_leader = mapper.get(leader).unwrap();
+        let new_follower = mapper.get(follower).unwrap();
+        assert_ne!(new_leader, leader);
+        assert_eq!(loaded.read::<Position>(&new_leader), Some(&Position(1, 2)));
+        assert_eq!(loaded.read::<Follow>(&new_follower).unwrap().0, new_leader);
+        assert!(loaded.read::<NotSaved>(&new_follower).is_none());
+        assert_eq!(loaded.read_res::<Score>(), &Score(7));
+        assert_eq!(loaded.query::<Entity>().count(), 2);
+    }
+
+    #[test]
+    fn test_failed_load_adds_nothing() {
+        let mut ecs = registered();
+        let data = r#"{
+            "entities": [
+                {"id": {"index": 0, "generation": 1}, "components": {"position": [1, 2]}},
+                {"id": {"index": 1, "generation": 1}, "components": {"velocity": [0, 1]}}
+            ],
+            "resources": {}
+        }"#;
+
+        let err = ecs.load_from(data.as_bytes()).unwrap_err();
+
+        assert!(matches!(err, SaveError::UnknownComponent(name) if name == "velocity"));
+        assert_eq!(ecs.query::<Entity>().count(), 0);
+    }
+
+    #[test]
+    fn test_ron_round_trip() {
+        let mut ecs = registered();
+        let leader = ecs.build_entity().add_component(Position(1, 2)).entity();
+        let follower = ecs.build_entity().add_component(Follow(leader)).entity();
+        ecs.add_res(Score(3));
+        let mut buffer = vec![];
+        ecs.save_to_ron(&mut buffer).unwrap();
+
+        let mut loaded = registered();
+        let mapper = loaded.load_from_ron(buffer.as_slice()).unwrap();
+
+        let new_leader = mapper.get(leader).unwrap();
+        let new_follower = mapper.get(follower).unwrap();
+        assert_eq!(loaded.read::<Position>(&new_leader), Some(&Position(1, 2)));
+        assert_eq!(loaded.read::<Follow>(&new_follower).unwrap().0, new_leader);
+        assert_eq!(loaded.read_res::<Score>(), &Score(3));
+    }
+
+    #[test]
+    fn test_references_to_unsaved_entities_load_dead() {
+        let mut ecs = registered();
+        let unsaved = ecs.build_entity().add_component(NotSaved).entity();
+        let follower = ecs.build_entity().add_component(Follow(unsaved)).entity();
+        let mut buffer = vec![];
+        ecs.save_to(&mut buffer).unwrap();
+
+        // The unsaved entity's id is live in the loading world
+        let mut loaded = registered();
+        let existing = loaded.new_entity();
+        assert_eq!(existing, unsaved);
+        let mapper = loaded.load_from(buffer.as_slice()).unwrap();
+
+        let target = loaded
+            .read::<Follow>(&mapper.get(follower).unwrap())
+            .unwrap()
+            .0;
+        assert_eq!(target, Entity::DEAD);
+        assert!(loaded.query::<Entity>().all(|entity| entity != target));
+    }
+
+    struct BrokenWriter;
+
+    impl io::Write for BrokenWriter {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            return Err(io::Error::new(io::ErrorKind::BrokenPipe, "disk unplugged"));
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            return Ok(());
+        }
+    }
+
+    #[test]
+    fn test_write_failures_are_io_errors() {
+        let mut ecs = registered();
+        ecs.build_entity().add_component(Position(1, 2));
+
+        let err = ecs.save_to(BrokenWriter).unwrap_err();
+        assert!(matches!(err, SaveError::Io(err) if err.kind() == io::ErrorKind::BrokenPipe));
+        let err = ecs.save_to_ron(BrokenWriter).unwrap_err();
+        assert!(matches!(err, SaveError::Io(_)));
+    }
+
+    #[test]
+    #[should_panic(expected = "without declaring it")]
+    fn test_parallel_save_checks_declared_reads() {
+        let mut ecs = registered();
+        ecs.add_res(Score(3));
+        ecs.register_parallel_system(
+            "autosave",
+            Box::new(|ecs: &ECS, _dt: f32| {
+                ecs.save().unwrap();
+                vec![]
+            }),
+        )
+        .reads::<Position>()
+        .reads::<Follow>();
+        ecs.run_systems(0.);
+    }
+}
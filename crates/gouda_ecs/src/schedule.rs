@@ -0,0 +1,454 @@
+use crate::parallel::{ParallelSystem, SystemAccess};
+use crate::{System, ECS};
+use std::any::TypeId;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+/// The stages of a frame, run in declaration order. Every system belongs to exactly one stage.
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Copy, Clone, Hash)]
+pub enum Stage {
+    PreUpdate,
+    Update,
+    PostUpdate,
+    RenderPrep,
+}
+
+/// When the mutations returned by a stage's systems are applied.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum MutationTiming {
+    /// Right after the system that returned them, so later systems in the stage see them.
+    AfterEachSystem,
+    /// All together once every system in the stage has run, in schedule order. Every system in
+    /// the stage sees the world as it was when the stage started.
+    AtStageEnd,
+}
+
+/// Who owns a system. Scene systems are dropped when the active scene changes, engine systems
+/// live for the whole run.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum SystemScope {
+    Engine,
+    Scene,
+}
+
+pub type RunCondition = dyn Fn(&ECS) -> bool;
+
+pub(crate) enum SystemFn {
+    Serial(Box<System>),
+    Parallel(Box<ParallelSystem>),
+}
+
+pub(crate) struct SystemEntry {
+    pub(crate) name: Option<String>,
+    pub(crate) stage: Stage,
+    pub(crate) scope: SystemScope,
+    pub(crate) before: Vec<String>,
+    pub(crate) after: Vec<String>,
+    pub(crate) run_if: Option<Box<RunCondition>>,
+    pub(crate) system: SystemFn,
+    pub(crate) access: Arc<SystemAccess>,
+    pub(crate) last_run_tick: u64,
+    /// The event types this system has read, recorded as it reads them.
+    pub(crate) event_reads: Mutex<HashSet<TypeId>>,
+}
+
+/// All registered systems plus the order they run in. The order is rebuilt lazily whenever a
+/// system is added, removed or reconfigured.
+pub(crate) struct Schedule {
+    pub(crate) systems: Vec<SystemEntry>,
+    order: Option<Vec<usize>>,
+    timings: HashMap<Stage, MutationTiming>,
+}
+
+impl Schedule {
+    pub(crate) fn new() -> Schedule {
+        Schedule {
+            systems: Vec::new(),
+            order: None,
+            timings: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn add(&mut self, name: Option<String>, system: SystemFn) -> usize {
+        if let Some(name) = &name {
+            if self.index_of(name).is_some() {
+                panic!("A system named {} is already registered", name);
+            }
+        }
+        self.systems.push(SystemEntry {
+            name,
+            stage: Stage::Update,
+            scope: SystemScope::Scene,
+            before: Vec::new(),
+            after: Vec::new(),
+            run_if: None,
+            system,
+            access: Arc::new(SystemAccess::default()),
+            last_run_tick: 0,
+            event_reads: Mutex::new(HashSet::new()),
+        });
+        self.order = None;
+        return self.systems.len() - 1;
+    }
+
+    pub(crate) fn timing(&self, stage: Stage) -> MutationTiming {
+        return *self
+            .timings
+            .get(&stage)
+            .unwrap_or(&MutationTiming::AfterEachSystem);
+    }
+
+    pub(crate) fn set_timing(&mut self, stage: Stage, timing: MutationTiming) {
+        self.timings.insert(stage, timing);
+    }
+
+    pub(crate) fn entry_mut(&mut self, index: usize) -> &mut SystemEntry {
+        self.order = None;
+        return &mut self.systems[index];
+    }
+
+    pub(crate) fn index_of(&self, name: &str) -> Option<usize> {
+        self.systems
+            .iter()
+            .position(|entry| entry.name.as_deref() == Some(name))
+    }
+
+    pub(crate) fn remove(&mut self, name: &str) -> bool {
+        match self.index_of(name) {
+            Some(index) => {
+                self.systems.remove(index);
+                self.order = None;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn retain(&mut self, keep: impl Fn(&SystemEntry) -> bool) {
+        self.systems.retain(keep);
+        self.order = None;
+    }
+
+    /// The indices of every system in the order they should run.
+    pub(crate) fn order(&mut self) -> Vec<usize> {
+        if self.order.is_none() {
+            self.order = Some(self.build_order());
+        }
+        return self.order.clone().unwrap();
+    }
+
+    /// Sorts systems by stage, then orders each stage so every before/after constraint holds.
+    /// Systems with no constraint between them keep their registration order. Constraints that
+    /// name a system in a different stage, or one that isn't registered, are ignored.
+    fn build_order(&self) -> Vec<usize> {
+        let mut stages: Vec<Stage> = self.systems.iter().map(|entry| entry.stage).collect();
+        stages.sort();
+        stages.dedup();
+
+        let mut order = Vec::with_capacity(self.systems.len());
+        for stage in stages {
+            let members: Vec<usize> = (0..self.systems.len())
+                .filter(|i| self.systems[*i].stage == stage)
+                .collect();
+            order.extend(self.order_stage(&members));
+        }
+        return order;
+    }
+
+    fn order_stage(&self, members: &[usize]) -> Vec<usize> {
+        let position = |name: &str| {
+            members
+                .iter()
+                .position(|i| self.systems[*i].name.as_deref() == Some(name))
+        };
+
+        // runs_after[a] holds every member that must run before member a.
+        let mut runs_after: Vec<Vec<usize>> = vec![Vec::new(); members.len()];
+        for (a, index) in members.iter().enumerate() {
+            let entry = &self.systems[*index];
+            for name in &entry.after {
+                if let Some(b) = position(name) {
+                    runs_after[a].push(b);
+                }
+            }
+            for name in &entry.before {
+                if let Some(b) = position(name) {
+                    runs_after[b].push(a);
+                }
+            }
+        }
+
+        let mut placed = vec![false; members.len()];
+        let mut order = Vec::with_capacity(members.len());
+        while order.len() < members.len() {
+            let next = (0..members.len())
+                .find(|a| !placed[*a] && runs_after[*a].iter().all(|b| placed[*b]));
+            match next {
+                Some(a) => {
+                    placed[a] = true;
+                    order.push(members[a]);
+                }
+                None => {
+                    let cycle: Vec<&str> = (0..members.len())
+                        .filter(|a| !placed[*a])
+                        .map(|a| self.systems[members[a]].name.as_deref().unwrap_or("?"))
+                        .collect();
+                    panic!("System ordering constraints form a cycle: {:?}", cycle);
+                }
+            }
+        }
+        return order;
+    }
+}
+
+/// Configures a system registered with `ECS::register_system`. The system is already registered;
+/// each call adjusts it in place.
+pub struct SystemBuilder<'a> {
+    pub(crate) ecs: &'a mut ECS,
+    pub(crate) index: usize,
+}
+
+impl<'a> SystemBuilder<'a> {
+    pub fn stage(self, stage: Stage) -> SystemBuilder<'a> {
+        self.ecs.schedule.entry_mut(self.index).stage = stage;
+        self
+    }
+
+    /// Runs this system before the named one, if both are in the same stage.
+    pub fn before(self, name: &str) -> SystemBuilder<'a> {
+        self.ecs
+            .schedule
+            .entry_mut(self.index)
+            .before
+            .push(name.to_string());
+        self
+    }
+
+    /// Runs this system after the named one, if both are in the same stage.
+    pub fn after(self, name: &str) -> SystemBuilder<'a> {
+        self.ecs
+            .schedule
+            .entry_mut(self.index)
+            .after
+            .push(name.to_string());
+        self
+    }
+
+    /// Only runs the system on frames where `condition` holds.
+    pub fn run_if<F: Fn(&ECS) -> bool + 'static>(self, condition: F) -> SystemBuilder<'a> {
+        self.ecs.schedule.entry_mut(self.index).run_if = Some(Box::new(condition));
+        self
+    }
+
+    /// Declares that a parallel system reads the component, resource or event type `T`.
+    pub fn reads<T: Send + Sync + 'static>(self) -> SystemBuilder<'a> {
+        let entry = self.ecs.schedule.entry_mut(self.index);
+        Arc::make_mut(&mut entry.access).add_read::<T>();
+        self
+    }
+
+    /// Declares that a parallel system's mutations write, add or remove `T`. Systems that read `T`
+    /// won't run alongside it. Debug builds panic if a mutation writes a type that wasn't declared.
+    pub fn writes<T: 'static>(self) -> SystemBuilder<'a> {
+        let entry = self.ecs.schedule.entry_mut(self.index);
+        Arc::make_mut(&mut entry.access).add_write::<T>();
+        self
+    }
+
+    /// Keeps the system registered across scene changes.
+    pub fn engine(self) -> SystemBuilder<'a> {
+        self.ecs.schedule.entry_mut(self.index).scope = SystemScope::Engine;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Mutations;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    type Log = Rc<RefCell<Vec<&'static str>>>;
+
+    fn logging(log: &Log, name: &'static str) -> Box<System> {
+        let log = log.clone();
+        Box::new(move |_ecs: &ECS, _dt: f32| -> Mutations {
+            log.borrow_mut().push(name);
+            vec![]
+        })
+    }
+
+    #[test]
+    fn test_stages_run_in_order() {
+        let log = Log::default();
+        let mut ecs = ECS::new();
+        ecs.register_system("prep", logging(&log, "prep"))
+            .stage(Stage::RenderPrep);
+        ecs.register_system("post", logging(&log, "post"))
+            .stage(Stage::PostUpdate);
+        ecs.add_system(logging(&log, "update"));
+        ecs.register_system("pre", logging(&log, "pre"))
+            .stage(Stage::PreUpdate);
+
+        ecs.run_systems(0.);
+
+        assert_eq!(*log.borrow(), vec!["pre", "update", "post", "prep"]);
+    }
+
+    #[test]
+    fn test_before_and_after_constraints() {
+        let log = Log::default();
+        let mut ecs = ECS::new();
+        ecs.register_system("c", logging(&log, "c")).after("b");
+        ecs.register_system("b", logging(&log, "b"));
+        ecs.register_system("a", logging(&log, "a")).before("b");
+        ecs.register_system("d", logging(&log, "d"))
+            .after("missing");
+
+        ecs.run_systems(0.);
+
+        assert_eq!(*log.borrow(), vec!["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "cycle")]
+    fn test_cycle_panics() {
+        let mut ecs = ECS::new();
+        let log = Log::default();
+        ecs.register_system("a", logging(&log, "a")).after("b");
+        ecs.register_system("b", logging(&log, "b")).after("a");
+        ecs.run_systems(0.);
+    }
+
+    #[test]
+    fn test_run_condition_skips_system() {
+        struct Paused(bool);
+
+        let log = Log::default();
+        let mut ecs = ECS::new();
+        ecs.add_res(Paused(true));
+        ecs.register_system("physics", logging(&log, "physics"))
+            .run_if(|ecs| !ecs.read_res::<Paused>().0);
+
+        ecs.run_systems(0.);
+        ecs.write_res::<Paused>().0 = false;
+        ecs.run_systems(0.);
+
+        assert_eq!(*log.borrow(), vec!["physics"]);
+    }
+
+    #[test]
+    fn test_clearing_scene_systems_keeps_engine_systems() {
+        let log = Log::default();
+        let mut ecs = ECS::new();
+        ecs.register_system("input", logging(&log, "input"))
+            .engine();
+        ecs.register_system("menu", logging(&log, "menu"));
+        ecs.add_system(logging(&log, "anonymous"));
+
+        ecs.clear_scene_systems();
+        ecs.run_systems(0.);
+
+        assert_eq!(*log.borrow(), vec!["input"]);
+        assert!(ecs.has_system("input"));
+        assert!(!ecs.has_system("menu"));
+    }
+
+    #[test]
+    fn test_remove_system_by_name() {
+        let log = Log::default();
+        let mut ecs = ECS::new();
+        ecs.register_system("a", logging(&log, "a"));
+        ecs.register_system("b", logging(&log, "b"));
+
+        assert!(ecs.remove_system("a"));
+        assert!(!ecs.remove_system("a"));
+        ecs.run_systems(0.);
+
+        assert_eq!(*log.borrow(), vec!["b"]);
+    }
+
+    #[derive(Debug)]
+    struct Counter(i32);
+
+    struct Increment(crate::Entity);
+
+    impl crate::Mutation for Increment {
+        fn apply(&self, ecs: &mut ECS) {
+            ecs.write::<Counter>(&self.0).unwrap().0 += 1;
+        }
+    }
+
+    fn counter_test(timing: MutationTiming) -> Vec<i32> {
+        let seen = Rc::new(RefCell::new(vec![]));
+        let mut ecs = ECS::new();
+        ecs.build_entity().add_component(Counter(0));
+        ecs.set_mutation_timing(Stage::Update, timing);
+        for name in ["first", "second"] {
+            let seen = seen.clone();
+            ecs.register_system(
+                name,
+                Box::new(move |ecs: &ECS, _dt: f32| -> Mutations {
+                    let (counter, entity) = ecs.read1::<Counter>()[0];
+                    seen.borrow_mut().push(counter.0);
+                    vec![Box::new(Increment(entity))]
+                }),
+            );
+        }
+        ecs.run_systems(0.);
+        let (counter, _) = ecs.read1::<Counter>()[0];
+        seen.borrow_mut().push(counter.0);
+        return seen.take();
+    }
+
+    #[test]
+    fn test_mutations_apply_after_each_system_by_default() {
+        assert_eq!(counter_test(MutationTiming::AfterEachSystem), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_stage_end_timing_applies_mutations_at_barrier() {
+        assert_eq!(counter_test(MutationTiming::AtStageEnd), vec![0, 0, 2]);
+    }
+
+    #[test]
+    fn test_stage_end_timing_keeps_schedule_order() {
+        struct Push(&'static str);
+
+        impl crate::Mutation for Push {
+            fn apply(&self, ecs: &mut ECS) {
+                ecs.write_res::<Vec<&'static str>>().push(self.0);
+            }
+        }
+
+        let mut ecs = ECS::new();
+        ecs.add_res::<Vec<&'static str>>(vec![]);
+        ecs.set_mutation_timing(Stage::Update, MutationTiming::AtStageEnd);
+        ecs.register_system(
+            "a",
+            Box::new(|_ecs: &ECS, _dt: f32| -> Mutations { vec![Box::new(Push("a"))] }),
+        );
+        ecs.register_parallel_system(
+            "b",
+            Box::new(|_ecs: &ECS, _dt: f32| vec![Box::new(Push("b"))]),
+        )
+        .writes::<Vec<&'static str>>();
+        ecs.register_system(
+            "c",
+            Box::new(|_ecs: &ECS, _dt: f32| -> Mutations { vec![Box::new(Push("c"))] }),
+        );
+        ecs.register_parallel_system(
+            "d",
+            Box::new(|_ecs: &ECS, _dt: f32| vec![Box::new(Push("d"))]),
+        )
+        .writes::<Vec<&'static str>>();
+
+        ecs.run_systems(0.);
+
+        assert_eq!(
+            *ecs.read_res::<Vec<&'static str>>(),
+            vec!["a", "b", "c", "d"]
+        );
+    }
+}
@@ -0,0 +1,320 @@
+use crate::events::EventChannel;
+use crate::genindex::GenIndexAllocator;
+use crate::{Entity, EntityMap, ECS};
+use anymap::AnyMap;
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+/// A copy of the world taken by `ECS::snapshot`. It holds the entity allocator, change ticks and
+/// every component, resource and event type registered for snapshots.
+pub struct Snapshot {
+    allocator: GenIndexAllocator,
+    components: AnyMap,
+    resources: AnyMap,
+    events: AnyMap,
+    change_tick: u64,
+    last_run_tick: u64,
+    system_ticks: Vec<u64>,
+}
+
+struct SnapshotType {
+    take: fn(&ECS, &mut Snapshot),
+    restore: fn(&mut ECS, &Snapshot),
+}
+
+/// The component, resource and event types copied into snapshots.
+#[derive(Default)]
+pub(crate) struct SnapshotTypes {
+    types: HashMap<TypeId, SnapshotType>,
+}
+
+fn take_component<T: 'static + Clone>(ecs: &ECS, snapshot: &mut Snapshot) {
+    if let Some(storage) = ecs.storage::<T>() {
+        snapshot.components.insert(storage.clone());
+    }
+}
+
+fn restore_component<T: 'static + Clone>(ecs: &mut ECS, snapshot: &Snapshot) {
+    let storage = match snapshot.components.get::<EntityMap<T>>() {
+        Some(storage) => storage.clone(),
+        None if ecs.components.contains::<EntityMap<T>>() => EntityMap::new(),
+        None => return,
+    };
+    ecs.components.insert(storage);
+}
+
+fn take_resource<T: 'static + Clone>(ecs: &ECS, snapshot: &mut Snapshot) {
+    if let Ok(resource) = ecs.try_read_res::<T>() {
+        snapshot.resources.insert(resource.clone());
+    }
+}
+
+fn restore_resource<T: 'static + Clone>(ecs: &mut ECS, snapshot: &Snapshot) {
+    match snapshot.resources.get::<T>() {
+        Some(resource) => ecs.add_res(resource.clone()),
+        None => ecs.remove_res::<T>(),
+    }
+}
+
+fn take_events<T: 'static + Clone>(ecs: &ECS, snapshot: &mut Snapshot) {
+    if let Ok(channel) = ecs.event_channel::<T>() {
+        snapshot.events.insert(channel.clone());
+    }
+}
+
+fn restore_events<T: 'static + Clone>(ecs: &mut ECS, snapshot: &Snapshot) {
+    if let Some(channel) = snapshot.events.get::<EventChannel<T>>() {
+        ecs.events.insert(channel.clone());
+    }
+}
+
+impl ECS {
+    /// Includes every `T` component in snapshots.
+    pub fn register_snapshot_component<T: 'static + Debug + Clone>(&mut self) {
+        self.add_snapshot_type::<T>(take_component::<T>, restore_component::<T>);
+    }
+
+    /// Includes the `T` resource in snapshots.
+    pub fn register_snapshot_resource<T: 'static + Clone>(&mut self) {
+        self.add_snapshot_type::<T>(take_resource::<T>, restore_resource::<T>);
+    }
+
+    /// Includes every stored `T` event in snapshots.
+    pub fn register_snapshot_event<T: 'static + Clone>(&mut self) {
+        self.add_snapshot_type::<Vec<T>>(take_events::<T>, restore_events::<T>);
+    }
+
+    fn add_snapshot_type<T: 'static>(
+        &mut self,
+        take: fn(&ECS, &mut Snapshot),
+        restore: fn(&mut ECS, &Snapshot),
+    ) {
+        self.snapshot_types
+            .types
+            .insert(TypeId::of::<T>(), SnapshotType { take, restore });
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        let mut snapshot = Snapshot {
+            allocator: self.entity_allocator.clone(),
+            components: AnyMap::new(),
+            resources: AnyMap::new(),
+            events: AnyMap::new(),
+            change_tick: self.change_tick,
+            last_run_tick: self.last_run_tick,
+            system_ticks: self
+                .schedule
+                .systems
+                .iter()
+                .map(|system| system.last_run_tick)
+                .collect(),
+        };
+        for snapshot_type in self.snapshot_types.types.values() {
+            (snapshot_type.take)(self, &mut snapshot);
+        }
+        return snapshot;
+    }
+
+    /// Puts the world back the way it was when `snapshot` was taken. Entities spawned since are
+    /// deleted, so their remove hooks run, and deleted entities come back with the same ids.
+    /// Components of types that aren't registered for snapshots are not rolled back, and are lost
+    /// from entities that were deleted in between.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        let spawned: Vec<Entity> = self
+            .query::<Entity>()
+            .filter(|entity| snapshot.allocator.live_at(entity.index) != Some(*entity))
+            .collect();
+        for entity in spawned {
+            self.delete_entity(&entity);
+        }
+
+        self.entity_allocator = snapshot.allocator.clone();
+        self.change_tick = snapshot.change_tick;
+        self.last_run_tick = snapshot.last_run_tick;
+        if self.schedule.systems.len() == snapshot.system_ticks.len() {
+            for (system, tick) in self.schedule.systems.iter_mut().zip(&snapshot.system_ticks) {
+                system.last_run_tick = *tick;
+            }
+        }
+        let restores: Vec<fn(&mut ECS, &Snapshot)> = self
+            .snapshot_types
+            .types
+            .values()
+            .map(|snapshot_type| snapshot_type.restore)
+            .collect();
+        for restore in restores {
+            restore(self, snapshot);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Entity, Mutation, Mutations, ECS};
+    use gouda_input::{GameInput, LetterKeys};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Position(i32, i32);
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Moved(Entity);
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Spawns(u32);
+
+    struct Step(Entity, i32, i32);
+
+    impl Mutation for Step {
+        fn apply(&self, ecs: &mut ECS) {
+            let position = ecs.write::<Position>(&self.0).unwrap();
+            position.0 += self.1;
+            position.1 += self.2;
+            ecs.push_event(Moved(self.0));
+        }
+    }
+
+    struct Spawn;
+
+    impl Mutation for Spawn {
+        fn apply(&self, ecs: &mut ECS) {
+            ecs.build_entity().add_component(Position(0, 0));
+            ecs.write_res::<Spawns>().0 += 1;
+        }
+    }
+
+    struct Despawn(Entity);
+
+    impl Mutation for Despawn {
+        fn apply(&self, ecs: &mut ECS) {
+            ecs.delete_entity(&self.0);
+        }
+    }
+
+    fn input_system(ecs: &ECS, _dt: f32) -> Mutations {
+        let input = ecs.read_res::<GameInput>();
+        let mut mutations: Mutations = vec![];
+        let (dx, dy) = if input.keyboard.letter_down(LetterKeys::D) {
+            (1, 0)
+        } else {
+            (0, 1)
+        };
+        for (_, entity) in ecs.read1::<Position>() {
+            mutations.push(Box::new(Step(entity, dx, dy)));
+        }
+        if input.keyboard.letter_down(LetterKeys::S) {
+            mutations.push(Box::new(Spawn));
+        }
+        if input.keyboard.letter_down(LetterKeys::X) {
+            if let Some((_, entity)) = ecs.read1::<Position>().first() {
+                mutations.push(Box::new(Despawn(*entity)));
+            }
+        }
+        return mutations;
+    }
+
+    fn world() -> ECS {
+        let mut ecs = ECS::new();
+        ecs.register_event_type::<Moved>();
+        ecs.register_snapshot_component::<Position>();
+        ecs.register_snapshot_resource::<GameInput>();
+        ecs.register_snapshot_resource::<Spawns>();
+        ecs.register_snapshot_event::<Moved>();
+        ecs.add_res(GameInput::new());
+        ecs.add_res(Spawns(0));
+        ecs.build_entity().add_component(Position(0, 0));
+        ecs.register_system("input", Box::new(input_system));
+        return ecs;
+    }
+
+    fn press(keys: &[LetterKeys]) -> GameInput {
+        let mut input = GameInput::new();
+        for key in keys {
+            input.keyboard.letter_keys[*key].ended_down = true;
+        }
+        return input;
+    }
+
+    fn step(ecs: &mut ECS, input: &GameInput) {
+        *ecs.write_res::<GameInput>() = input.clone();
+        ecs.update_events();
+        ecs.run_systems(1.);
+    }
+
+    type State = (Vec<(Position, Entity)>, Vec<Moved>, u32, Entity);
+
+    fn state(ecs: &mut ECS) -> State {
+        let positions = ecs
+            .read1::<Position>()
+            .into_iter()
+            .map(|(position, entity)| (position.clone(), entity))
+            .collect();
+        let next = ecs.new_entity();
+        ecs.delete_entity(&next);
+        return (
+            positions,
+            ecs.events::<Moved>().clone(),
+            ecs.read_res::<Spawns>().0,
+            next,
+        );
+    }
+
+    #[test]
+    fn test_replaying_inputs_from_snapshot_reproduces_state() {
+        let inputs = [
+            press(&[LetterKeys::S]),
+            press(&[LetterKeys::D]),
+            press(&[LetterKeys::X, LetterKeys::S]),
+            press(&[LetterKeys::S, LetterKeys::D]),
+            press(&[]),
+        ];
+        let mut ecs = world();
+        step(&mut ecs, &inputs[0]);
+        let snapshot = ecs.snapshot();
+        let before = state(&mut ecs);
+
+        for input in &inputs[1..] {
+            step(&mut ecs, input);
+        }
+        let after = state(&mut ecs);
+
+        ecs.restore(&snapshot);
+        assert_eq!(state(&mut ecs), before);
+        for input in &inputs[1..] {
+            step(&mut ecs, input);
+        }
+        assert_eq!(state(&mut ecs), after);
+    }
+
+    #[test]
+    fn test_restore_drops_unregistered_components_of_spawned_entities() {
+        #[derive(Debug)]
+        struct Marker;
+
+        let mut ecs = ECS::new();
+        let snapshot = ecs.snapshot();
+        let spawned = ecs.build_entity().add_component(Marker).entity();
+
+        ecs.restore(&snapshot);
+        let reused = ecs.new_entity();
+
+        assert_eq!(reused, spawned);
+        assert!(ecs.read::<Marker>(&reused).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "without declaring it")]
+    fn test_parallel_snapshot_checks_declared_reads() {
+        let mut ecs = world();
+        ecs.register_parallel_system(
+            "rewind",
+            Box::new(|ecs: &ECS, _dt: f32| {
+                ecs.snapshot();
+                vec![]
+            }),
+        )
+        .reads::<Position>()
+        .reads::<GameInput>();
+        ecs.run_systems(0.);
+    }
+}
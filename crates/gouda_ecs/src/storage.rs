@@ -0,0 +1,182 @@
+use crate::genindex::GenIndex;
+
+const PAGE_SIZE: usize = 1024;
+const EMPTY: u32 = u32::MAX;
+
+/// Component storage for a single type. Values are packed densely so iterating touches only
+/// entities that actually have the component. The sparse side maps entity indices to dense slots
+/// and is allocated in pages, so large entity indices only cost memory where components exist.
+#[derive(Clone)]
+pub struct SparseSet<T> {
+    sparse: Vec<Option<Box<[u32; PAGE_SIZE]>>>,
+    entities: Vec<GenIndex>,
+    values: Vec<T>,
+    changed_ticks: Vec<u64>,
+}
+
+impl<T> SparseSet<T> {
+    pub fn new() -> SparseSet<T> {
+        return SparseSet {
+            sparse: Vec::new(),
+            entities: Vec::new(),
+            values: Vec::new(),
+            changed_ticks: Vec::new(),
+        };
+    }
+
+    fn slot(&self, index: usize) -> Option<usize> {
+        let page = self.sparse.get(index / PAGE_SIZE)?.as_ref()?;
+        let slot = page[index % PAGE_SIZE];
+        if slot == EMPTY {
+            return None;
+        }
+        return Some(slot as usize);
+    }
+
+    fn set_slot(&mut self, index: usize, slot: u32) {
+        let page = index / PAGE_SIZE;
+        if page >= self.sparse.len() {
+            self.sparse.resize_with(page + 1, || None);
+        }
+        let page = self.sparse[page].get_or_insert_with(|| Box::new([EMPTY; PAGE_SIZE]));
+        page[index % PAGE_SIZE] = slot;
+    }
+
+    /// The dense slot for this entity, if it has a value of the same generation.
+    fn live_slot(&self, entity: GenIndex) -> Option<usize> {
+        let slot = self.slot(entity.index)?;
+        if self.entities[slot].generation != entity.generation {
+            return None;
+        }
+        return Some(slot);
+    }
+
+    /// Stores a value for the entity, replacing anything already stored at its index.
+    pub fn insert(&mut self, entity: GenIndex, value: T, tick: u64) {
+        if let Some(slot) = self.slot(entity.index) {
+            self.entities[slot] = entity;
+            self.values[slot] = value;
+            self.changed_ticks[slot] = tick;
+            return;
+        }
+        self.set_slot(entity.index, self.entities.len() as u32);
+        self.entities.push(entity);
+        self.values.push(value);
+        self.changed_ticks.push(tick);
+    }
+
+    /// Removes whatever is stored at the entity's index, regardless of generation.
+    pub fn remove(&mut self, entity: GenIndex) -> Option<T> {
+        let slot = self.slot(entity.index)?;
+        self.set_slot(entity.index, EMPTY);
+        let last = self.entities.len() - 1;
+        if slot != last {
+            let moved = self.entities[last];
+            self.set_slot(moved.index, slot as u32);
+        }
+        self.entities.swap_remove(slot);
+        self.changed_ticks.swap_remove(slot);
+        return Some(self.values.swap_remove(slot));
+    }
+
+    pub fn get(&self, entity: GenIndex) -> Option<&T> {
+        return self.live_slot(entity).map(|slot| &self.values[slot]);
+    }
+
+    pub fn get_mut(&mut self, entity: GenIndex) -> Option<&mut T> {
+        return self
+            .live_slot(entity)
+            .map(move |slot| &mut self.values[slot]);
+    }
+
+    /// Looks up by index alone, ignoring the generation.
+    pub fn get_by_index(&self, index: usize) -> Option<&T> {
+        return self.slot(index).map(|slot| &self.values[slot]);
+    }
+
+    /// Like `get_mut`, but records the write at `tick`.
+    pub fn write(&mut self, entity: GenIndex, tick: u64) -> Option<&mut T> {
+        let slot = self.live_slot(entity)?;
+        self.changed_ticks[slot] = tick;
+        return Some(&mut self.values[slot]);
+    }
+
+    pub fn changed_tick(&self, entity: GenIndex) -> Option<u64> {
+        return self.live_slot(entity).map(|slot| self.changed_ticks[slot]);
+    }
+
+    /// The entities with a stored value, in dense order.
+    pub fn entities(&self) -> &[GenIndex] {
+        return &self.entities;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.entities.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.entities.is_empty();
+    }
+}
+
+impl<T> Default for SparseSet<T> {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SparseSet;
+    use crate::genindex::GenIndex;
+
+    fn entity(index: usize, generation: u64) -> GenIndex {
+        return GenIndex { index, generation };
+    }
+
+    #[test]
+    fn test_insert_and_get_checks_generation() {
+        let mut set = SparseSet::new();
+        set.insert(entity(3, 1), "a", 0);
+
+        assert_eq!(set.get(entity(3, 1)), Some(&"a"));
+        assert_eq!(set.get(entity(3, 2)), None);
+        assert_eq!(set.get(entity(4, 1)), None);
+        assert_eq!(set.get_by_index(3), Some(&"a"));
+    }
+
+    #[test]
+    fn test_remove_keeps_other_values_reachable() {
+        let mut set = SparseSet::new();
+        set.insert(entity(0, 1), 10, 0);
+        set.insert(entity(5000, 1), 20, 0);
+        set.insert(entity(7, 1), 30, 0);
+
+        assert_eq!(set.remove(entity(0, 1)), Some(10));
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.get(entity(5000, 1)), Some(&20));
+        assert_eq!(set.get(entity(7, 1)), Some(&30));
+        assert_eq!(set.get(entity(0, 1)), None);
+        assert_eq!(set.remove(entity(0, 1)), None);
+    }
+
+    #[test]
+    fn test_insert_replaces_value_at_index() {
+        let mut set = SparseSet::new();
+        set.insert(entity(2, 1), 1, 0);
+        set.insert(entity(2, 2), 2, 5);
+
+        assert_eq!(set.len(), 1);
+        assert_eq!(set.get(entity(2, 2)), Some(&2));
+        assert_eq!(set.changed_tick(entity(2, 2)), Some(5));
+        assert_eq!(set.entities(), &[entity(2, 2)]);
+    }
+
+    #[test]
+    fn test_pages_are_only_allocated_where_used() {
+        let mut set = SparseSet::new();
+        set.insert(entity(100_000, 1), 1, 0);
+
+        assert_eq!(set.sparse.iter().filter(|page| page.is_some()).count(), 1);
+    }
+}
@@ -14,6 +14,7 @@ use gouda::rendering::Scene;
 use gouda::layer::Layer;
 use gouda::imgui::FontSource;
 
+#[allow(dead_code)]
 pub struct EditorLayer {
     imgui: Option<Context>,
     imgui_platform: Option<GoudaImguiPlatform>,
@@ -42,7 +43,7 @@ impl Layer for EditorLayer {
                 io.mouse_down[0] = input.mouse.buttons[0].ended_down;
             }
 
-            let mut opened: bool = false;
+            let _opened: bool = false;
             let ui: &mut Ui = imgui.new_frame();
 
             if let Some(menu_bar) = ui.begin_main_menu_bar() {
@@ -55,11 +56,11 @@ impl Layer for EditorLayer {
         }
     }
 
-    fn render(&mut self, ecs: &mut ECS, scene: &mut Scene) {
+    fn render(&mut self, _ecs: &mut ECS, scene: &mut Scene) {
         if let Some(imgui) = self.imgui.as_mut() {
             let draw_data = imgui.render();
             if let Some(imgui_renderer) = self.imgui_renderer.as_ref() {
-                imgui_renderer.render(&scene, draw_data);
+                imgui_renderer.render(scene, draw_data);
             }
         }
     }
@@ -90,7 +91,7 @@ impl Layer for EditorLayer {
             }),
         }]);
 
-        self.imgui_renderer = Some(GoudaImguiRenderer::create(&renderer, &mut imgui));
+        self.imgui_renderer = Some(GoudaImguiRenderer::create(renderer, &mut imgui));
         self.imgui = Some(imgui);
     }
 }
@@ -1,7 +1,7 @@
 use std::{collections::HashMap, env};
 
 use editorlayer::EditorLayer;
-use gouda::{camera::{Camera, OrthographicCamera}, ecs::{GameSceneId, ECS}, rendering::Scene, transform::TransformComponent, window::WindowProps, GameLogic, GameScene, Gouda, QuitEvent, RenderLayer};
+use gouda::{camera::OrthographicCamera, ecs::{GameSceneId, ECS}, rendering::Scene, transform::TransformComponent, window::WindowProps, GameLogic, GameScene, Gouda, QuitEvent, RenderLayer};
 
 pub mod editorlayer;
 
@@ -16,13 +16,13 @@ impl GameScene for StartMenuScene {
             .add_component(TransformComponent::builder().build());
     }
 
-    fn on_scene_stop(&self, ecs: &mut ECS) {
+    fn on_scene_stop(&self, _ecs: &mut ECS) {
     }
 
-    fn render_scene(&self, ecs: &ECS, scene: &Scene) {
+    fn render_scene(&self, _ecs: &ECS, _scene: &Scene) {
     }
 
-    fn next_scene(&self, ecs: &ECS) -> Option<u32> {
+    fn next_scene(&self, _ecs: &ECS) -> Option<u32> {
         return None;
     }
 
@@ -52,10 +52,6 @@ impl GameLogic for Game {
         ecs.register_event_type::<QuitEvent>();
     }
 
-    fn migrate_events(&self, ecs: &mut ECS) {
-        ecs.migrate_events::<QuitEvent>();
-    }
-
     fn game_scenes(&self) -> HashMap<GameSceneId, Box<dyn GameScene>> {
         let mut res: HashMap<GameSceneId, Box<dyn GameScene>> = HashMap::new();
         res.insert(START_MENU_SCENE, Box::new(StartMenuScene {}));
@@ -66,7 +62,7 @@ impl GameLogic for Game {
         return START_MENU_SCENE;
     }
 
-    fn setup(&mut self, ecs: &mut ECS) {
+    fn setup(&mut self, _ecs: &mut ECS) {
     }
 }
 
@@ -0,0 +1,240 @@
+use std::collections::{HashMap, HashSet};
+
+use gouda_types::Color;
+
+use crate::Image;
+
+/// Where an image ended up inside an atlas, in pixels from the top left.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtlasRect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum AtlasError {
+    DuplicateName(String),
+}
+
+/// Many images combined into one, with the location of each looked up by name.
+pub struct Atlas {
+    pub image: Image,
+    rects: HashMap<String, AtlasRect>,
+}
+
+impl Atlas {
+    pub fn rect(&self, name: &str) -> Option<AtlasRect> {
+        return self.rects.get(name).copied();
+    }
+
+    /// The region of the atlas holding `name` as (x, y, width, height) in normalized texture
+    /// coordinates.
+    pub fn uv(&self, name: &str) -> Option<[f32; 4]> {
+        let (width, height) = (self.image.width as f32, self.image.height as f32);
+        return self.rect(name).map(|rect| {
+            [
+                rect.x as f32 / width,
+                rect.y as f32 / height,
+                rect.width as f32 / width,
+                rect.height as f32 / height,
+            ]
+        });
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        return self.rects.keys();
+    }
+}
+
+/// Packs images into an atlas using rows of images sorted tallest first. Every image is
+/// surrounded by `padding` pixels copied from its own edge, so filtering near the edge of one
+/// image never picks up its neighbours.
+pub struct AtlasBuilder {
+    padding: usize,
+    images: Vec<(String, Image)>,
+}
+
+impl AtlasBuilder {
+    pub fn new(padding: usize) -> AtlasBuilder {
+        return AtlasBuilder {
+            padding,
+            images: vec![],
+        };
+    }
+
+    pub fn add(mut self, name: &str, image: Image) -> AtlasBuilder {
+        self.images.push((name.to_string(), image));
+        return self;
+    }
+
+    pub fn build(self) -> Result<Atlas, AtlasError> {
+        let padding = self.padding;
+        let mut seen = HashSet::new();
+        for (name, _) in &self.images {
+            if !seen.insert(name) {
+                return Err(AtlasError::DuplicateName(name.clone()));
+            }
+        }
+
+        let padded = |image: &Image| (image.width + padding * 2, image.height + padding * 2);
+        let area: usize = self
+            .images
+            .iter()
+            .map(|(_, image)| padded(image).0 * padded(image).1)
+            .sum();
+        let widest = self
+            .images
+            .iter()
+            .map(|(_, image)| padded(image).0)
+            .max()
+            .unwrap_or(0);
+        let width = widest
+            .max((area as f64).sqrt().ceil() as usize)
+            .next_power_of_two();
+
+        let mut order: Vec<usize> = (0..self.images.len()).collect();
+        order.sort_by_key(|i| std::cmp::Reverse(self.images[*i].1.height));
+
+        let mut placements = vec![(0, 0); self.images.len()];
+        let (mut x, mut y, mut row_height) = (0, 0, 0);
+        for i in order {
+            let (w, h) = padded(&self.images[i].1);
+            if x + w > width {
+                x = 0;
+                y += row_height;
+                row_height = 0;
+            }
+            placements[i] = (x + padding, y + padding);
+            x += w;
+            row_height = row_height.max(h);
+        }
+        let height = (y + row_height).next_power_of_two();
+
+        let mut data = vec![Color::from_u8(0, 0, 0, 0); width * height];
+        let mut rects = HashMap::new();
+        for ((name, image), (left, top)) in self.images.into_iter().zip(placements) {
+            blit_extruded(&mut data, width, &image, left, top, padding);
+            rects.insert(
+                name,
+                AtlasRect {
+                    x: left,
+                    y: top,
+                    width: image.width,
+                    height: image.height,
+                },
+            );
+        }
+
+        return Ok(Atlas {
+            image: Image {
+                width,
+                height,
+                data,
+            },
+            rects,
+        });
+    }
+}
+
+/// Copies `image` to (left, top), filling `padding` pixels around it with the nearest edge pixel.
+fn blit_extruded(
+    data: &mut [Color],
+    atlas_width: usize,
+    image: &Image,
+    left: usize,
+    top: usize,
+    padding: usize,
+) {
+    if image.width == 0 || image.height == 0 {
+        return;
+    }
+    let padding = padding as isize;
+    for dy in -padding..image.height as isize + padding {
+        for dx in -padding..image.width as isize + padding {
+            let sx = dx.clamp(0, image.width as isize - 1) as usize;
+            let sy = dy.clamp(0, image.height as isize - 1) as usize;
+            let tx = (left as isize + dx) as usize;
+            let ty = (top as isize + dy) as usize;
+            data[ty * atlas_width + tx] = image.data[sy * image.width + sx];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(width: usize, height: usize, value: u8) -> Image {
+        return Image {
+            width,
+            height,
+            data: vec![Color::from_u8(value, value, value, 255); width * height],
+        };
+    }
+
+    fn pixel(image: &Image, x: usize, y: usize) -> u8 {
+        return (image.data[y * image.width + x].r * 255.).round() as u8;
+    }
+
+    fn overlaps(a: AtlasRect, b: AtlasRect, padding: usize) -> bool {
+        return a.x < b.x + b.width + padding
+            && b.x < a.x + a.width + padding
+            && a.y < b.y + b.height + padding
+            && b.y < a.y + a.height + padding;
+    }
+
+    #[test]
+    fn test_packs_without_overlap() {
+        let atlas = AtlasBuilder::new(1)
+            .add("grass", solid(16, 16, 10))
+            .add("dirt", solid(16, 16, 20))
+            .add("tree", solid(8, 24, 30))
+            .add("arrow", solid(4, 2, 40))
+            .build()
+            .unwrap();
+
+        let names = ["grass", "dirt", "tree", "arrow"];
+        let rects: Vec<AtlasRect> = names.iter().map(|n| atlas.rect(n).unwrap()).collect();
+        for (i, a) in rects.iter().enumerate() {
+            assert!(a.x + a.width < atlas.image.width);
+            assert!(a.y + a.height < atlas.image.height);
+            for b in &rects[i + 1..] {
+                assert!(!overlaps(*a, *b, 2));
+            }
+        }
+        assert!(atlas.image.width.is_power_of_two());
+        assert!(atlas.image.height.is_power_of_two());
+
+        let tree = atlas.rect("tree").unwrap();
+        assert_eq!((tree.width, tree.height), (8, 24));
+        assert_eq!(pixel(&atlas.image, tree.x, tree.y), 30);
+        assert_eq!(pixel(&atlas.image, tree.x + 7, tree.y + 23), 30);
+    }
+
+    #[test]
+    fn test_padding_copies_edges() {
+        let mut image = solid(2, 1, 50);
+        image.data[1] = Color::from_u8(60, 60, 60, 255);
+        let atlas = AtlasBuilder::new(2).add("strip", image).build().unwrap();
+
+        let rect = atlas.rect("strip").unwrap();
+        assert_eq!((rect.x, rect.y), (2, 2));
+        assert_eq!(pixel(&atlas.image, 0, 0), 50);
+        assert_eq!(pixel(&atlas.image, 5, 4), 60);
+        assert_eq!(atlas.uv("strip"), Some([0.25, 0.25, 0.25, 0.125]));
+    }
+
+    #[test]
+    fn test_rejects_duplicate_names() {
+        let result = AtlasBuilder::new(0)
+            .add("a", solid(1, 1, 0))
+            .add("a", solid(1, 1, 0))
+            .build();
+        assert_eq!(
+            result.err(),
+            Some(AtlasError::DuplicateName("a".to_string()))
+        );
+    }
+}
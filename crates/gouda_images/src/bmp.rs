@@ -6,7 +6,7 @@ use gouda_types::Color;
 use std::fs::File;
 use std::io::prelude::*;
 
-#[repr(packed)]
+#[repr(C, packed)]
 #[derive(Debug, Clone, Copy)]
 pub struct BitmapHeader {
     pub file_type: u16,
@@ -76,7 +76,7 @@ impl Bitmap {
                 let color = Color::from_u8(
                     c[offset + i as usize + 2],
                     c[offset + i as usize + 1],
-                    c[offset + i as usize + 0],
+                    c[offset + i as usize],
                     255,
                 );
                 res.push(color);
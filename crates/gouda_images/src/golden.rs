@@ -0,0 +1,181 @@
+use crate::png::PNG;
+use crate::Image;
+use gouda_types::Color;
+use std::env;
+use std::path::Path;
+
+/// Set this environment variable to rewrite golden images from the current output instead of
+/// comparing against them, e.g. `GOUDA_UPDATE_GOLDENS=1 cargo test`.
+pub const UPDATE_GOLDENS_VAR: &str = "GOUDA_UPDATE_GOLDENS";
+
+/// How two images differ. The diff image shows every pixel outside the tolerance in red over a
+/// faded copy of the expected image.
+pub struct ImageDiff {
+    pub mismatched_pixels: usize,
+    pub max_difference: u8,
+    pub diff: Image,
+}
+
+/// Compares two images channel by channel. Pixels whose channels all differ by at most
+/// `tolerance` (out of 255) are considered equal.
+pub fn compare_images(actual: &Image, expected: &Image, tolerance: u8) -> Result<(), ImageDiff> {
+    if actual.width != expected.width || actual.height != expected.height {
+        return Err(ImageDiff {
+            mismatched_pixels: actual.width * actual.height,
+            max_difference: 255,
+            diff: all_mismatched(expected.width, expected.height),
+        });
+    }
+
+    let actual_pixels = actual.raw_pixels();
+    let expected_pixels = expected.raw_pixels();
+
+    let mut mismatched_pixels = 0;
+    let mut max_difference = 0;
+    let mut diff = vec![];
+    for (a, e) in actual_pixels
+        .chunks_exact(4)
+        .zip(expected_pixels.chunks_exact(4))
+    {
+        let difference = a.iter().zip(e).map(|(a, e)| a.abs_diff(*e)).max().unwrap();
+        max_difference = max_difference.max(difference);
+        if difference > tolerance {
+            mismatched_pixels += 1;
+            diff.push(Color::from_u8(255, 0, 0, 255));
+        } else {
+            let luma = (e[0] as u32 * 3 + e[1] as u32 * 6 + e[2] as u32) / 10;
+            let faded = (luma / 3 + 170) as u8;
+            diff.push(Color::from_u8(faded, faded, faded, 255));
+        }
+    }
+
+    if mismatched_pixels == 0 {
+        return Ok(());
+    }
+    return Err(ImageDiff {
+        mismatched_pixels,
+        max_difference,
+        diff: Image {
+            width: actual.width,
+            height: actual.height,
+            data: diff,
+        },
+    });
+}
+
+fn all_mismatched(width: usize, height: usize) -> Image {
+    return Image {
+        width,
+        height,
+        data: vec![Color::from_u8(255, 0, 0, 255); width * height],
+    };
+}
+
+fn sibling_path(golden_path: &str, suffix: &str) -> String {
+    let path = Path::new(golden_path);
+    let stem = path.file_stem().unwrap().to_string_lossy();
+    return path
+        .with_file_name(format!("{}.{}.png", stem, suffix))
+        .to_string_lossy()
+        .to_string();
+}
+
+/// Panics unless `actual` matches the png at `golden_path` within `tolerance`. On a mismatch the
+/// captured frame and a diff image are written next to the golden as `<name>.actual.png` and
+/// `<name>.diff.png`.
+pub fn assert_matches_golden(actual: &Image, golden_path: &str, tolerance: u8) {
+    if env::var(UPDATE_GOLDENS_VAR).is_ok() {
+        PNG::from_image(actual).write_file(golden_path).unwrap();
+        return;
+    }
+
+    if !Path::new(golden_path).exists() {
+        panic!(
+            "Golden image {} does not exist, run with {}=1 to create it",
+            golden_path, UPDATE_GOLDENS_VAR
+        );
+    }
+    let expected = PNG::from_file(golden_path).unwrap().image();
+
+    if let Err(diff) = compare_images(actual, &expected, tolerance) {
+        let actual_path = sibling_path(golden_path, "actual");
+        let diff_path = sibling_path(golden_path, "diff");
+        PNG::from_image(actual).write_file(&actual_path).unwrap();
+        PNG::from_image(&diff.diff).write_file(&diff_path).unwrap();
+        panic!(
+            "Frame does not match {} ({}x{} vs {}x{}): {} pixels differ by more than {}, max difference {}. Wrote {} and {}",
+            golden_path,
+            actual.width,
+            actual.height,
+            expected.width,
+            expected.height,
+            diff.mismatched_pixels,
+            tolerance,
+            diff.max_difference,
+            actual_path,
+            diff_path
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(width: usize, height: usize, value: u8) -> Image {
+        return Image {
+            width,
+            height,
+            data: vec![Color::from_u8(value, value, value, 255); width * height],
+        };
+    }
+
+    #[test]
+    fn test_differences_within_tolerance_match() {
+        assert!(compare_images(&solid(4, 4, 100), &solid(4, 4, 102), 2).is_ok());
+    }
+
+    #[test]
+    fn test_diff_marks_mismatched_pixels() {
+        let expected = solid(2, 2, 100);
+        let mut actual = solid(2, 2, 100);
+        actual.data[3] = Color::from_u8(100, 140, 100, 255);
+
+        let diff = compare_images(&actual, &expected, 2).err().unwrap();
+        assert_eq!(diff.mismatched_pixels, 1);
+        assert_eq!(diff.max_difference, 40);
+        let pixels = diff.diff.raw_pixels();
+        assert_eq!(&pixels[12..16], &[255, 0, 0, 255]);
+        assert_ne!(&pixels[0..4], &[255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_size_mismatch_fails() {
+        assert!(compare_images(&solid(2, 2, 0), &solid(2, 3, 0), 255).is_err());
+    }
+
+    #[test]
+    fn test_failure_writes_actual_and_diff() {
+        if env::var(UPDATE_GOLDENS_VAR).is_ok() {
+            return;
+        }
+        let dir = env::temp_dir().join(format!("gouda_golden_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let golden = dir.join("frame.png").to_string_lossy().to_string();
+        PNG::from_image(&solid(3, 3, 10))
+            .write_file(&golden)
+            .unwrap();
+
+        let result = std::panic::catch_unwind(|| {
+            assert_matches_golden(&solid(3, 3, 200), &golden, 0);
+        });
+
+        assert!(result.is_err());
+        assert!(dir.join("frame.actual.png").exists());
+        let diff = PNG::from_file(dir.join("frame.diff.png").to_str().unwrap())
+            .unwrap()
+            .image();
+        assert_eq!(diff.raw_pixels()[0..4], [255, 0, 0, 255]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
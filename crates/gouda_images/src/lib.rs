@@ -1,6 +1,8 @@
 use gouda_types::Color;
 
+pub mod atlas;
 pub mod bmp;
+pub mod golden;
 pub mod png;
 pub mod spritesheet;
 pub mod utils;
@@ -15,10 +17,10 @@ impl Image {
     pub fn raw_pixels(&self) -> Vec<u8> {
         let mut raw = vec![];
         for color in &self.data {
-            raw.push((color.r * 255.) as u8);
-            raw.push((color.g * 255.) as u8);
-            raw.push((color.b * 255.) as u8);
-            raw.push((color.a * 255.) as u8);
+            raw.push((color.r * 255.).round() as u8);
+            raw.push((color.g * 255.).round() as u8);
+            raw.push((color.b * 255.).round() as u8);
+            raw.push((color.a * 255.).round() as u8);
         }
         return raw;
     }
@@ -5,6 +5,7 @@ use crate::Image;
 use gouda_types::Color;
 use std::fs::File;
 use std::io::prelude::*;
+use std::io::BufWriter;
 
 pub struct Chunk {
     length: u32,
@@ -60,6 +61,33 @@ impl PNG {
         }
     }
 
+    pub fn from_image(image: &Image) -> PNG {
+        return PNG {
+            header_chunk: PNGHeader {
+                width: image.width as u32,
+                height: image.height as u32,
+            },
+            data: image.raw_pixels(),
+        };
+    }
+
+    /// Writes the pixels out as an 8 bit RGBA png.
+    pub fn write_file(&self, path: &str) -> Result<(), String> {
+        let file = File::create(path).map_err(|e| e.to_string())?;
+        let mut encoder = png::Encoder::new(
+            BufWriter::new(file),
+            self.header_chunk.width,
+            self.header_chunk.height,
+        );
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+        writer
+            .write_image_data(&self.data)
+            .map_err(|e| e.to_string())?;
+        return Ok(());
+    }
+
     pub fn from_buffer(buf: &[u8]) -> Option<PNG> {
         let decoder = png::Decoder::new(buf);
         let mut reader = decoder.read_info().unwrap();
@@ -74,12 +102,13 @@ impl PNG {
         });
     }
 
+    /// Returns `None` if the file can't be opened or isn't a valid PNG.
     pub fn from_file(path: &str) -> Option<PNG> {
         if true {
-            let decoder = png::Decoder::new(File::open(path).unwrap());
-            let mut reader = decoder.read_info().unwrap();
+            let decoder = png::Decoder::new(File::open(path).ok()?);
+            let mut reader = decoder.read_info().ok()?;
             let mut buf = vec![0; reader.info().raw_bytes()];
-            reader.next_frame(&mut buf).unwrap();
+            reader.next_frame(&mut buf).ok()?;
             return Some(PNG {
                 header_chunk: PNGHeader {
                     width: reader.info().width,
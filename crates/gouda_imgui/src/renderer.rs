@@ -26,7 +26,7 @@ pub struct GoudaImguiRenderer {
 
 impl GoudaImguiRenderer {
     pub fn create(renderer: &Renderer, context: &mut Context) -> GoudaImguiRenderer {
-        let font_texture = GoudaImguiRenderer::upload_font_texture(renderer, &mut context.fonts());
+        let font_texture = GoudaImguiRenderer::upload_font_texture(renderer, context.fonts());
 
         context.set_renderer_name("gouda-imgui-renderer".to_string());
 
@@ -4,12 +4,23 @@ pub mod osx;
 #[cfg(target_os = "windows")]
 pub mod win32;
 
+#[cfg(target_os = "linux")]
+pub mod linux;
+
 #[derive(Default, Clone)]
 pub struct GameButtonState {
     pub half_transition_count: i32,
     pub ended_down: bool,
 }
 
+impl GameButtonState {
+    /// Folds a later capture of this button into this one, as if both had been captured at once
+    pub fn accumulate(&mut self, later: &GameButtonState) {
+        self.half_transition_count += later.half_transition_count;
+        self.ended_down = later.ended_down;
+    }
+}
+
 #[derive(Enum, Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum LetterKeys {
     A,
@@ -147,6 +158,23 @@ impl KeyboardInput {
         new_keyboard
     }
 
+    pub fn accumulate(&mut self, later: &KeyboardInput) {
+        for (key, state) in self.number_keys.iter_mut() {
+            state.accumulate(&later.number_keys[key]);
+        }
+        for (key, state) in self.letter_keys.iter_mut() {
+            state.accumulate(&later.letter_keys[key]);
+        }
+        for (key, state) in self.special_keys.iter_mut() {
+            state.accumulate(&later.special_keys[key]);
+        }
+
+        self.cmd_down = later.cmd_down;
+        self.alt_down = later.alt_down;
+        self.ctrl_down = later.ctrl_down;
+        self.shift_down = later.shift_down;
+    }
+
     pub fn key_pressed(&self, key: AnyKey) -> bool {
         match key {
             AnyKey::Letter(letter) => self.letter_pressed(letter),
@@ -237,6 +265,29 @@ impl GameControllerInput {
 
         new_controller
     }
+
+    pub fn accumulate(&mut self, later: &GameControllerInput) {
+        self.is_connected = later.is_connected;
+        self.is_analog = later.is_analog;
+        self.stick_average_x = later.stick_average_x;
+        self.stick_average_y = later.stick_average_y;
+
+        self.move_up.accumulate(&later.move_up);
+        self.move_down.accumulate(&later.move_down);
+        self.move_left.accumulate(&later.move_left);
+        self.move_right.accumulate(&later.move_right);
+
+        self.action_up.accumulate(&later.action_up);
+        self.action_down.accumulate(&later.action_down);
+        self.action_left.accumulate(&later.action_left);
+        self.action_right.accumulate(&later.action_right);
+
+        self.left_shoulder.accumulate(&later.left_shoulder);
+        self.right_shoulder.accumulate(&later.right_shoulder);
+
+        self.back.accumulate(&later.back);
+        self.start.accumulate(&later.start);
+    }
 }
 
 #[derive(Default, Clone)]
@@ -249,11 +300,12 @@ pub struct Mouse {
 
 impl Mouse {
     pub fn from(old_mouse: &Mouse) -> Mouse {
-        let mut new_mouse = Mouse::default();
-
-        new_mouse.x = old_mouse.x;
-        new_mouse.y = old_mouse.y;
-        new_mouse.z = old_mouse.z;
+        let mut new_mouse = Mouse {
+            x: old_mouse.x,
+            y: old_mouse.y,
+            z: old_mouse.z,
+            ..Mouse::default()
+        };
 
         new_mouse.buttons[0].ended_down = old_mouse.buttons[0].ended_down;
         new_mouse.buttons[1].ended_down = old_mouse.buttons[1].ended_down;
@@ -263,6 +315,15 @@ impl Mouse {
 
         new_mouse
     }
+
+    pub fn accumulate(&mut self, later: &Mouse) {
+        self.x = later.x;
+        self.y = later.y;
+        self.z = later.z;
+        for (button, later_button) in self.buttons.iter_mut().zip(later.buttons.iter()) {
+            button.accumulate(later_button);
+        }
+    }
 }
 
 #[derive(Default, Clone)]
@@ -292,4 +353,17 @@ impl GameInput {
             ],
         }
     }
+
+    /// Folds input captured after this one into it, keeping every button transition from both.
+    /// Used when a frame is too short to run a simulation step, so its presses aren't lost.
+    pub fn accumulate(&mut self, later: &GameInput) {
+        self.seconds_to_advance_over_update = later.seconds_to_advance_over_update;
+        self.mouse.accumulate(&later.mouse);
+        self.keyboard.accumulate(&later.keyboard);
+        for (controller, later_controller) in
+            self.controllers.iter_mut().zip(later.controllers.iter())
+        {
+            controller.accumulate(later_controller);
+        }
+    }
 }
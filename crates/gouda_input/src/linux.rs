@@ -0,0 +1,116 @@
+use crate::{GameButtonState, KeyboardInput, LetterKeys, NumberKeys, SpecialKeys};
+
+// X11 keysyms, see X11/keysymdef.h
+pub const XK_SPACE: u32 = 0x0020;
+pub const XK_0: u32 = 0x0030;
+pub const XK_9: u32 = 0x0039;
+pub const XK_UPPER_A: u32 = 0x0041;
+pub const XK_UPPER_Z: u32 = 0x005a;
+pub const XK_LOWER_A: u32 = 0x0061;
+pub const XK_LOWER_Z: u32 = 0x007a;
+pub const XK_RETURN: u32 = 0xff0d;
+pub const XK_LEFT: u32 = 0xff51;
+pub const XK_UP: u32 = 0xff52;
+pub const XK_RIGHT: u32 = 0xff53;
+pub const XK_DOWN: u32 = 0xff54;
+pub const XK_SHIFT_L: u32 = 0xffe1;
+pub const XK_SHIFT_R: u32 = 0xffe2;
+pub const XK_CONTROL_L: u32 = 0xffe3;
+pub const XK_CONTROL_R: u32 = 0xffe4;
+pub const XK_ALT_L: u32 = 0xffe9;
+pub const XK_ALT_R: u32 = 0xffea;
+pub const XK_SUPER_L: u32 = 0xffeb;
+pub const XK_SUPER_R: u32 = 0xffec;
+
+pub fn linux_process_keyboard_message(new_state: &mut GameButtonState, is_down: bool) {
+    if new_state.ended_down != is_down {
+        new_state.ended_down = is_down;
+        new_state.half_transition_count += 1;
+    }
+}
+
+/// Translates an unshifted X11 keysym into the keyboard state. The super key stands in for cmd.
+pub fn linux_process_key(keyboard: &mut KeyboardInput, keysym: u32, is_down: bool) {
+    match keysym {
+        XK_UP => linux_process_keyboard_message(
+            &mut keyboard.special_keys[SpecialKeys::UpArrow],
+            is_down,
+        ),
+        XK_DOWN => linux_process_keyboard_message(
+            &mut keyboard.special_keys[SpecialKeys::DownArrow],
+            is_down,
+        ),
+        XK_LEFT => linux_process_keyboard_message(
+            &mut keyboard.special_keys[SpecialKeys::LeftArrow],
+            is_down,
+        ),
+        XK_RIGHT => linux_process_keyboard_message(
+            &mut keyboard.special_keys[SpecialKeys::RightArrow],
+            is_down,
+        ),
+        XK_RETURN => {
+            linux_process_keyboard_message(&mut keyboard.special_keys[SpecialKeys::Enter], is_down)
+        }
+        XK_SPACE => {
+            linux_process_keyboard_message(&mut keyboard.special_keys[SpecialKeys::Space], is_down)
+        }
+        XK_SHIFT_L | XK_SHIFT_R => keyboard.shift_down = is_down,
+        XK_CONTROL_L | XK_CONTROL_R => keyboard.ctrl_down = is_down,
+        XK_ALT_L | XK_ALT_R => keyboard.alt_down = is_down,
+        XK_SUPER_L | XK_SUPER_R => keyboard.cmd_down = is_down,
+        XK_0..=XK_9 => linux_process_keyboard_message(
+            &mut keyboard.number_keys[NumberKeys::from_usize((keysym - XK_0) as usize)],
+            is_down,
+        ),
+        XK_LOWER_A..=XK_LOWER_Z | XK_UPPER_A..=XK_UPPER_Z => {
+            let index = if keysym >= XK_LOWER_A {
+                keysym - XK_LOWER_A
+            } else {
+                keysym - XK_UPPER_A
+            };
+            let key = LetterKeys::iterator().nth(index as usize).unwrap();
+            linux_process_keyboard_message(&mut keyboard.letter_keys[*key], is_down)
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_letters_ignore_case() {
+        let mut keyboard = KeyboardInput::default();
+        linux_process_key(&mut keyboard, 'q' as u32, true);
+        assert!(keyboard.letter_keys[LetterKeys::Q].ended_down);
+
+        linux_process_key(&mut keyboard, 'Q' as u32, false);
+        assert!(!keyboard.letter_keys[LetterKeys::Q].ended_down);
+        assert_eq!(keyboard.letter_keys[LetterKeys::Q].half_transition_count, 2);
+    }
+
+    #[test]
+    fn test_numbers_arrows_and_modifiers() {
+        let mut keyboard = KeyboardInput::default();
+        linux_process_key(&mut keyboard, '7' as u32, true);
+        linux_process_key(&mut keyboard, XK_LEFT, true);
+        linux_process_key(&mut keyboard, XK_SUPER_L, true);
+
+        assert!(keyboard.number_keys[NumberKeys::SEVEN].ended_down);
+        assert!(keyboard.special_keys[SpecialKeys::LeftArrow].ended_down);
+        assert!(keyboard.cmd_down);
+        assert!(!keyboard.shift_down);
+    }
+
+    #[test]
+    fn test_repeated_presses_are_not_transitions() {
+        let mut keyboard = KeyboardInput::default();
+        linux_process_key(&mut keyboard, XK_SPACE, true);
+        linux_process_key(&mut keyboard, XK_SPACE, true);
+        assert_eq!(
+            keyboard.special_keys[SpecialKeys::Space].half_transition_count,
+            1
+        );
+    }
+}
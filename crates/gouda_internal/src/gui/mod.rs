@@ -6,6 +6,7 @@ use crate::mouse_capture::{MouseCaptureArea, MouseCaptureLayer};
 use gouda_ecs::{Entity, ECS};
 use gouda_images::Image;
 use gouda_rendering::font::{TextConfig, TextDrawable};
+use gouda_rendering::texture_library::TextureHandle;
 use gouda_rendering::{Renderer, Scene, Texture};
 use gouda_types::{Bounds, Color};
 use std::rc::Rc;
@@ -19,7 +20,7 @@ pub struct ActiveGui {}
 
 #[derive(Debug)]
 pub struct GuiImage {
-    drawable: Texture,
+    drawable: TextureHandle,
     visible: bool,
     transform: Matrix4<f32>,
 }
@@ -43,12 +44,12 @@ impl GuiImage {
         let w = renderer.get_width() as f32;
         let h = renderer.get_height() as f32;
         let pos = [
-            (bounds.x as f32) / (w as f32 / 2.) - 1.0,
-            (bounds.y as f32) / (h as f32 / 2.) - 1.0,
+            bounds.x / (w / 2.) - 1.0,
+            bounds.y / (h / 2.) - 1.0,
             1.0,
         ];
-        let size = [bounds.w as f32 / w as f32, bounds.h as f32 / h as f32, 1.0];
-        let drawable = Texture::new(renderer, &image);
+        let size = [bounds.w / w, bounds.h / h, 1.0];
+        let drawable = TextureHandle::new(Texture::new(renderer, &image));
 
         let transform = Matrix4::from_translation(Vector3::new(pos[0], pos[1], pos[2]))
             * Matrix4::from_nonuniform_scale(size[0], size[1], size[2]);
@@ -108,8 +109,8 @@ impl GuiText {
                 constraints.calculate_bounds(Bounds { x: 0., y: 0., w, h })
             }
         };
-        let pos = [(bounds.x as f32) / 450. - 1., (bounds.y as f32) / 450. - 1.];
-        let size = [(bounds.w as f32) / 450., (bounds.h as f32) / 450.];
+        let pos = [bounds.x / 450. - 1., bounds.y / 450. - 1.];
+        let size = [bounds.w / 450., bounds.h / 450.];
         let drawable = TextDrawable::new(
             renderer,
             TextConfig {
@@ -137,8 +138,8 @@ impl GuiText {
 
     pub fn change_text(&mut self, renderer: &Renderer, text: String, font: &'static str) {
         let bounds = self.calculated_bounds;
-        let pos = [(bounds.x as f32) / 450. - 1., (bounds.y as f32) / 450. - 1.];
-        let size = [(bounds.w as f32) / 450., (bounds.h as f32) / 450.];
+        let pos = [bounds.x / 450. - 1., bounds.y / 450. - 1.];
+        let size = [bounds.w / 450., bounds.h / 450.];
         let drawable = TextDrawable::new(
             renderer,
             TextConfig {
@@ -278,7 +279,7 @@ impl GuiComponent {
         entity
     }
 
-    pub fn change_color(&mut self, renderer: &Renderer, color: Color) {
+    pub fn change_color(&mut self, _renderer: &Renderer, color: Color) {
         self.color = color;
         let drawable = GuiDrawable::new(
             self.corner_radius,
@@ -337,7 +338,7 @@ impl GuiComponent {
         }
 
         for child in &self.children {
-            ecs.read::<GuiComponent>(child).unwrap().render(&ecs, scene);
+            ecs.read::<GuiComponent>(child).unwrap().render(ecs, scene);
         }
     }
 }
@@ -362,13 +363,13 @@ impl GuiDrawable {
         let w = scene.renderer.get_width() as f32;
         let h = scene.renderer.get_height() as f32;
         let position = [
-            (self.bounds.x as f32) / (w as f32 / 2.) - 1.,
-            (self.bounds.y as f32) / (h as f32 / 2.) - 1.,
+            self.bounds.x / (w / 2.) - 1.,
+            self.bounds.y / (h / 2.) - 1.,
             0.0,
         ];
         let scale = [
-            self.bounds.w as f32 / w as f32,
-            self.bounds.h as f32 / h as f32,
+            self.bounds.w / w,
+            self.bounds.h / h,
             1.0,
         ];
         let radius = self.radius / w;
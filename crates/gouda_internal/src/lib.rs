@@ -1,23 +1,33 @@
-use cgmath::Transform;
+use gouda_ecs::schedule::Stage;
 use gouda_ecs::{GameSceneId, ECS};
 use gouda_input::{GameInput, LetterKeys};
 use gouda_layer::Layer;
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+use gouda_platform::HeadlessPlatformLayer;
 use gouda_platform::PlatformLayer;
 use gouda_rendering::font_library::FontLibrary;
 use gouda_rendering::shader_lib::ShaderLibrary;
 use gouda_rendering::shapes::ShapeLibrary;
 use gouda_rendering::{Renderer, Scene};
-use gouda_transform::TransformComponent;
+use gouda_transform::{transform_propagation_system, TransformComponent};
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub use gouda_window::headless::ScriptedFrame;
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+use gouda_window::GameWindowImpl;
 use gouda_window::{WindowEvent, WindowProps};
 use std::collections::HashMap;
 use std::rc::Rc;
-use std::time;
-use std::time::Instant;
+use std::thread;
+use std::time::{Duration, Instant};
 
 pub use gouda_images::{bmp, png};
-use gouda_rendering::camera::{Camera, OrthographicCamera, PerspectiveCamera};
+use gouda_rendering::camera::Camera;
 pub mod gui;
 pub mod mouse_capture;
+pub mod time;
+
+use crate::time::FixedTimestep;
+pub use crate::time::{Time, TimeSettings};
 
 pub type RenderLayer = String;
 pub type RenderOrder = u32;
@@ -35,7 +45,6 @@ pub trait GameScene {
 pub trait GameLogic {
     fn window_props(&self) -> WindowProps;
     fn register_events(&self, ecs: &mut ECS);
-    fn migrate_events(&self, ecs: &mut ECS);
     fn game_scenes(&self) -> HashMap<GameSceneId, Box<dyn GameScene>>;
     fn initial_game_scene(&self) -> GameSceneId;
     fn setup(&mut self, ecs: &mut ECS);
@@ -47,6 +56,9 @@ pub struct Gouda<T: GameLogic> {
     game_scenes: HashMap<GameSceneId, Box<dyn GameScene>>,
     active_scene: Option<GameSceneId>,
     layers: Vec<Box<dyn Layer>>,
+    timestep: FixedTimestep,
+    pending_input: Option<GameInput>,
+    pending_events: Vec<WindowEvent>,
     pub shader_lib: Option<ShaderLibrary>,
     pub shape_lib: Option<ShapeLibrary>,
     pub font_lib: Option<FontLibrary>,
@@ -60,15 +72,30 @@ impl<T: GameLogic> Gouda<T> {
             game_scenes: HashMap::new(),
             active_scene: None,
             layers: vec![],
+            timestep: FixedTimestep::default(),
+            pending_input: None,
+            pending_events: vec![],
             shader_lib: None,
             shape_lib: None,
             font_lib: None,
         }
     }
 
-    fn setup_engine(&mut self) {
+    fn setup_engine(&mut self, props: &WindowProps) {
         self.ecs.add_res(GameInput::new());
+        self.ecs.add_res(TimeSettings::from_props(props));
+        self.ecs.add_res(Time::default());
         self.ecs.add_res::<Vec<WindowEvent>>(vec![]);
+        self.ecs
+            .register_system(
+                "transform_propagation",
+                Box::new(transform_propagation_system),
+            )
+            .stage(Stage::RenderPrep)
+            .engine();
+        self.ecs.on_remove::<mouse_capture::MouseCaptureArea>(
+            mouse_capture::remove_from_capture_layers,
+        );
     }
 
     fn setup_game(&mut self) {
@@ -89,16 +116,13 @@ impl<T: GameLogic> Gouda<T> {
         self.ecs.register_event_type::<QuitEvent>();
     }
 
-    fn migrate_core_events(&mut self) {
-        self.ecs.migrate_events::<QuitEvent>();
-    }
 
     pub fn push_layer(&mut self, layer: Box<dyn Layer>) {
         self.layers.push(layer);
     }
 
-    fn get_active_scene(&self) -> &Box<dyn GameScene> {
-        self.game_scenes.get(&self.active_scene.unwrap()).unwrap()
+    fn get_active_scene(&self) -> &dyn GameScene {
+        self.game_scenes.get(&self.active_scene.unwrap()).unwrap().as_ref()
     }
 
     fn update(&mut self, dt: f32, game_input: GameInput, events: Vec<WindowEvent>) {
@@ -106,7 +130,7 @@ impl<T: GameLogic> Gouda<T> {
             let gscene = self.game_scenes.get(&self.active_scene.unwrap());
             if let Some(gscene) = gscene {
                 gscene.on_scene_stop(&mut self.ecs);
-                self.ecs.clear_systems();
+                self.ecs.clear_scene_systems();
             }
             self.active_scene = Some(scene);
             let gscene = self.game_scenes.get(&self.active_scene.unwrap());
@@ -125,81 +149,422 @@ impl<T: GameLogic> Gouda<T> {
         self.ecs.run_systems(dt);
     }
 
-    pub fn run(&mut self) {
-        self.setup_engine();
-
-        let props = self.game_logic.window_props();
-
-        let mut platform = PlatformLayer::new(props);
-
-        let mut now = Instant::now();
+    pub fn ecs(&self) -> &ECS {
+        return &self.ecs;
+    }
 
-        let renderer = platform.get_renderer();
+    fn start(&mut self, props: &WindowProps, renderer: &Rc<Renderer>) {
+        self.setup_engine(props);
         self.ecs.add_res(renderer.clone());
 
         let ecs = &self.ecs;
         self.layers.iter_mut().for_each(|layer| layer.setup(ecs));
 
         self.setup_game();
+    }
+
+    /// Runs a single frame of the game: as many fixed simulation steps as `frame_time` seconds
+    /// pay for, followed by a render. Input and window events from frames too short to run a step
+    /// are held until the next step. Returns false once the game has been asked to close.
+    fn run_frame(
+        &mut self,
+        frame_time: f32,
+        input: GameInput,
+        events: Vec<WindowEvent>,
+        renderer: &Rc<Renderer>,
+    ) -> bool {
+        for event in &events {
+            match event {
+                WindowEvent::CloseEvent => {
+                    return false;
+                }
+                WindowEvent::ResizeEvent {
+                    width: _,
+                    height: _,
+                } => {
+                    self.ecs.remove_res::<Rc<Renderer>>();
+                    // platform.get_mut_renderer().resize(*width, *height);
+                    self.ecs.add_res(renderer.clone());
+                }
+            };
+        }
+
+        let settings = *self.ecs.read_res::<TimeSettings>();
+        let steps = self.timestep.advance(frame_time, &settings);
+
+        let mut input = match self.pending_input.take() {
+            Some(mut pending) => {
+                pending.accumulate(&input);
+                pending
+            }
+            None => input,
+        };
+        let mut events = events;
+        if !self.pending_events.is_empty() {
+            let mut pending = std::mem::take(&mut self.pending_events);
+            pending.append(&mut events);
+            events = pending;
+        }
+        if steps == 0 {
+            self.pending_input = Some(input);
+            self.pending_events = events;
+        } else {
+            // Only the first step sees this frame's transitions and window events, later catch-up
+            // steps get the same held keys without the presses being counted again
+            for _ in 0..steps {
+                let mut step_input = GameInput::from(&input);
+                std::mem::swap(&mut step_input, &mut input);
+                step_input.seconds_to_advance_over_update = settings.fixed_step;
+                let step_events = std::mem::take(&mut events);
+                if !self.step(settings.fixed_step, step_input, step_events) {
+                    return false;
+                }
+            }
+        }
+
+        let time = self.ecs.write_res::<Time>();
+        time.alpha = self.timestep.alpha(&settings);
+        time.frame_count += 1;
+
+        self.render(renderer);
+        return true;
+    }
+
+    /// Advances the simulation by a single fixed step. Returns false if the game quit.
+    fn step(&mut self, dt: f32, input: GameInput, events: Vec<WindowEvent>) -> bool {
+        self.ecs.update_events();
+
+        let time = self.ecs.write_res::<Time>();
+        time.elapsed += dt;
+        time.delta = dt;
+
+        let ecs = &self.ecs;
+        self.layers
+            .iter_mut()
+            .for_each(|layer| layer.update(ecs, dt));
+
+        self.update(dt, input, events);
+        return self.ecs.events::<QuitEvent>().is_empty();
+    }
+
+    fn render(&mut self, renderer: &Rc<Renderer>) {
+        let game_scene = self.game_scenes.get(&self.active_scene.unwrap()).unwrap();
+        let (camera, transform) = match self.ecs.query::<(&Camera, &TransformComponent)>().next() {
+            Some(camera) => camera,
+            None => return,
+        };
+        if let Some(mut scene) = renderer.begin_scene() {
+            scene.bind_camera(camera, transform);
+            game_scene.render_scene(&self.ecs, &scene);
+            let ecs = &mut self.ecs;
+            self.layers
+                .iter_mut()
+                .for_each(|layer| layer.render(ecs, &mut scene));
+            scene.unbind_camera();
+            renderer.end_scene(scene);
+        }
+    }
 
+    /// Runs the game in a window until it closes or quits. The simulation advances in fixed steps
+    /// set by the `TimeSettings` resource, sleeping between frames until the next step is due.
+    pub fn run(&mut self) {
+        let props = self.game_logic.window_props();
+
+        let mut platform = PlatformLayer::new(props);
+
+        let renderer = platform.get_renderer().clone();
+        self.start(&self.game_logic.window_props(), &renderer);
+
+        let mut last = Instant::now();
         loop {
-            let next = Instant::now();
-            let delta = next - now;
-            let target_dur = time::Duration::from_millis(16);
-            if target_dur > delta {
-                continue;
+            let settings = *self.ecs.read_res::<TimeSettings>();
+            let wait = Duration::from_secs_f32(self.timestep.until_next_step(&settings));
+            let elapsed = last.elapsed();
+            if wait > elapsed {
+                thread::sleep(wait - elapsed);
             }
-            let dt = delta.as_millis() as f32 / 1000.;
-            now = next;
 
-            self.migrate_core_events();
-            self.game_logic.migrate_events(&mut self.ecs);
-            let window = platform.get_window();
+            let now = Instant::now();
+            let frame_time = (now - last).as_secs_f32();
+            last = now;
 
+            let window = platform.get_window();
             let input = window.capture_input();
             let events = window.capture_events();
-            for event in &events {
-                match event {
-                    WindowEvent::CloseEvent => {
-                        return;
-                    }
-                    WindowEvent::ResizeEvent {
-                        width: _,
-                        height: _,
-                    } => {
-                        self.ecs.remove_res::<Rc<Renderer>>();
-                        // platform.get_mut_renderer().resize(*width, *height);
-                        let renderer = platform.get_renderer();
-                        self.ecs.add_res(renderer.clone());
-                    }
-                };
+            if !self.run_frame(frame_time, input, events, &renderer) {
+                return;
             }
+        }
+    }
 
-            let ecs = &self.ecs;
-            self.layers
-                .iter_mut()
-                .for_each(|layer| layer.update(ecs, dt));
+    /// Runs the game without a window for at most `frame_count` frames, as fast as possible. Each
+    /// frame takes its input and window events from the next entry in `frames`, carrying the last
+    /// input over once they run out, and runs exactly one fixed simulation step unless the entry
+    /// sets its own frame time. Returns the number of frames that ran to completion, which is
+    /// less than `frame_count` if the game closed or quit.
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    pub fn run_headless(&mut self, frames: Vec<ScriptedFrame>, frame_count: usize) -> usize {
+        let props = self.game_logic.window_props();
 
-            self.update(dt, input.clone(), events);
-            if self.ecs.events::<QuitEvent>().len() > 0 {
-                return;
+        let frame_times: Vec<Option<f32>> = frames.iter().map(|frame| frame.frame_time).collect();
+        let mut platform = HeadlessPlatformLayer::scripted(props, frames);
+
+        let renderer = platform.get_renderer().clone();
+        self.start(&self.game_logic.window_props(), &renderer);
+
+        for frame in 0..frame_count {
+            let fixed_step = self.ecs.read_res::<TimeSettings>().fixed_step;
+            let dt = frame_times.get(frame).copied().flatten().unwrap_or(fixed_step);
+            let window = platform.get_window();
+            let input = window.capture_input();
+            let events = window.capture_events();
+            if !self.run_frame(dt, input, events, &renderer) {
+                return frame;
             }
+        }
+        return frame_count;
+    }
+}
+
+#[cfg(all(test, not(any(target_os = "macos", target_os = "windows"))))]
+mod tests {
+    use super::*;
+    use gouda_ecs::{Mutation, Mutations};
+    use gouda_input::SpecialKeys;
+    use gouda_rendering::camera::OrthographicCamera;
+    use std::cell::Cell;
+
+    const MENU_SCENE: GameSceneId = 0;
+    const GAME_SCENE: GameSceneId = 1;
+
+    struct FrameLog {
+        frames: u32,
+        scene_frames: Vec<GameSceneId>,
+        dt: f32,
+    }
+
+    struct LogFrame {
+        scene: GameSceneId,
+        dt: f32,
+    }
+
+    impl Mutation for LogFrame {
+        fn apply(&self, ecs: &mut ECS) {
+            let log = ecs.write_res::<FrameLog>();
+            log.frames += 1;
+            log.scene_frames.push(self.scene);
+            log.dt = self.dt;
+        }
+    }
 
-            let game_scene = self.game_scenes.get(&self.active_scene.unwrap()).unwrap();
-            let renderer = platform.get_renderer();
-            let cameras = self.ecs.read2::<Camera, TransformComponent>();
-            let camera = cameras[0].0;
-            let transform = cameras[0].1;
-            if let Some(mut scene) = renderer.begin_scene() {
-                scene.bind_camera(camera, transform);
-                game_scene.render_scene(&self.ecs, &scene);
-                let mut ecs = &mut self.ecs;
-                self.layers
-                    .iter_mut()
-                    .for_each(|layer| layer.render(&mut ecs, &mut scene));
-                scene.unbind_camera();
-                renderer.end_scene(scene);
+    fn menu_system(_ecs: &ECS, dt: f32) -> Mutations {
+        return vec![Box::new(LogFrame {
+            scene: MENU_SCENE,
+            dt,
+        })];
+    }
+
+    fn game_system(_ecs: &ECS, dt: f32) -> Mutations {
+        return vec![Box::new(LogFrame {
+            scene: GAME_SCENE,
+            dt,
+        })];
+    }
+
+    struct MenuScene;
+
+    impl GameScene for MenuScene {
+        fn on_scene_start(&self, ecs: &mut ECS) {
+            ecs.add_system(Box::new(menu_system));
+        }
+
+        fn on_scene_stop(&self, _ecs: &mut ECS) {}
+
+        fn render_scene(&self, _ecs: &ECS, _scene: &Scene) {}
+
+        fn next_scene(&self, ecs: &ECS) -> Option<GameSceneId> {
+            let input = ecs.read_res::<GameInput>();
+            if input.keyboard.special_key_pressed(SpecialKeys::Enter) {
+                return Some(GAME_SCENE);
             }
+            return None;
+        }
+
+        fn active_layers(&self, _ecs: &ECS) -> Vec<RenderLayer> {
+            return vec![];
+        }
+    }
+
+    struct GameplayScene;
+
+    impl GameScene for GameplayScene {
+        fn on_scene_start(&self, ecs: &mut ECS) {
+            ecs.add_system(Box::new(game_system));
+        }
+
+        fn on_scene_stop(&self, _ecs: &mut ECS) {}
+
+        fn render_scene(&self, _ecs: &ECS, _scene: &Scene) {}
+
+        fn next_scene(&self, _ecs: &ECS) -> Option<GameSceneId> {
+            return None;
+        }
+
+        fn active_layers(&self, _ecs: &ECS) -> Vec<RenderLayer> {
+            return vec![];
+        }
+    }
+
+    struct TestGame;
+
+    impl GameLogic for TestGame {
+        fn window_props(&self) -> WindowProps {
+            return WindowProps {
+                width: 32.,
+                height: 32.,
+                title: "Test".to_string(),
+                target_ms_per_frame: 20.,
+            };
+        }
+
+        fn register_events(&self, _ecs: &mut ECS) {}
+
+        fn game_scenes(&self) -> HashMap<GameSceneId, Box<dyn GameScene>> {
+            let mut scenes: HashMap<GameSceneId, Box<dyn GameScene>> = HashMap::new();
+            scenes.insert(MENU_SCENE, Box::new(MenuScene));
+            scenes.insert(GAME_SCENE, Box::new(GameplayScene));
+            return scenes;
+        }
+
+        fn initial_game_scene(&self) -> GameSceneId {
+            return MENU_SCENE;
+        }
+
+        fn setup(&mut self, ecs: &mut ECS) {
+            ecs.add_res(FrameLog {
+                frames: 0,
+                scene_frames: vec![],
+                dt: 0.,
+            });
+            ecs.build_entity()
+                .add_component(Camera::Orthographic(OrthographicCamera::new(1.)))
+                .add_component(TransformComponent::builder().build());
         }
     }
+
+    struct CountingLayer {
+        updates: Rc<Cell<u32>>,
+        renders: Rc<Cell<u32>>,
+    }
+
+    impl Layer for CountingLayer {
+        fn setup(&mut self, _ecs: &ECS) {}
+
+        fn update(&mut self, _ecs: &ECS, _dt: f32) {
+            self.updates.set(self.updates.get() + 1);
+        }
+
+        fn render(&mut self, _ecs: &mut ECS, _scene: &mut Scene) {
+            self.renders.set(self.renders.get() + 1);
+        }
+    }
+
+    fn pressed(key: SpecialKeys) -> GameInput {
+        let mut input = GameInput::new();
+        input.keyboard.special_keys[key].ended_down = true;
+        input.keyboard.special_keys[key].half_transition_count = 1;
+        return input;
+    }
+
+    #[test]
+    fn test_runs_fixed_number_of_frames() {
+        let updates = Rc::new(Cell::new(0));
+        let renders = Rc::new(Cell::new(0));
+        let mut gouda = Gouda::new(TestGame);
+        gouda.push_layer(Box::new(CountingLayer {
+            updates: updates.clone(),
+            renders: renders.clone(),
+        }));
+
+        assert_eq!(gouda.run_headless(vec![], 5), 5);
+
+        let log = gouda.ecs().read_res::<FrameLog>();
+        assert_eq!(log.frames, 5);
+        assert_eq!(log.dt, 0.02);
+        assert_eq!(updates.get(), 5);
+        assert_eq!(renders.get(), 5);
+    }
+
+    #[test]
+    fn test_time_advances_by_fixed_step() {
+        let mut gouda = Gouda::new(TestGame);
+        assert_eq!(gouda.run_headless(vec![], 5), 5);
+
+        let time = gouda.ecs().read_res::<Time>();
+        assert_eq!(time.frame_count, 5);
+        assert_eq!(time.delta, 0.02);
+        assert!((time.elapsed - 0.1).abs() < 1e-6);
+        assert_eq!(time.alpha, 0.);
+    }
+
+    #[test]
+    fn test_scripted_input_switches_scenes() {
+        let mut gouda = Gouda::new(TestGame);
+        let frames = vec![
+            ScriptedFrame::default(),
+            ScriptedFrame::new(pressed(SpecialKeys::Enter), vec![]),
+        ];
+
+        assert_eq!(gouda.run_headless(frames, 4), 4);
+
+        // The scene only changes at the start of the frame after the input was seen
+        let log = gouda.ecs().read_res::<FrameLog>();
+        assert_eq!(
+            log.scene_frames,
+            vec![MENU_SCENE, MENU_SCENE, GAME_SCENE, GAME_SCENE]
+        );
+    }
+
+    #[test]
+    fn test_close_event_stops_run() {
+        let mut gouda = Gouda::new(TestGame);
+        let frames = vec![
+            ScriptedFrame::default(),
+            ScriptedFrame::new(GameInput::new(), vec![WindowEvent::CloseEvent]),
+        ];
+
+        assert_eq!(gouda.run_headless(frames, 10), 1);
+        assert_eq!(gouda.ecs().read_res::<FrameLog>().frames, 1);
+    }
+
+    #[test]
+    fn test_cmd_q_quits() {
+        let mut gouda = Gouda::new(TestGame);
+        let mut quit = GameInput::new();
+        quit.keyboard.cmd_down = true;
+        quit.keyboard.letter_keys[LetterKeys::Q].ended_down = true;
+
+        // The quit event is only visible once events are updated on the next frame
+        assert_eq!(
+            gouda.run_headless(vec![ScriptedFrame::new(quit, vec![])], 10),
+            1
+        );
+        assert_eq!(gouda.ecs().read_res::<FrameLog>().frames, 2);
+    }
+
+    #[test]
+    fn test_input_from_frames_without_a_step_is_kept() {
+        let mut gouda = Gouda::new(TestGame);
+        let held = GameInput::from(&pressed(SpecialKeys::Enter));
+        let frames = vec![
+            ScriptedFrame::new(pressed(SpecialKeys::Enter), vec![]).with_frame_time(0.015),
+            ScriptedFrame::new(held, vec![]).with_frame_time(0.015),
+        ];
+
+        assert_eq!(gouda.run_headless(frames, 3), 3);
+
+        // The press lands in a frame too short for a step, the next frame's step still sees it
+        let log = gouda.ecs().read_res::<FrameLog>();
+        assert_eq!(log.scene_frames, vec![MENU_SCENE, GAME_SCENE]);
+    }
 }
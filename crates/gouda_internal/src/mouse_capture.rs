@@ -2,7 +2,7 @@ use cgmath::{SquareMatrix, Vector4};
 
 use gouda_ecs::{Entity, Mutation, Mutations, ECS};
 use gouda_input::GameInput;
-use gouda_rendering::camera::{Camera, OrthographicCamera};
+use gouda_rendering::camera::Camera;
 use gouda_transform::TransformComponent;
 use gouda_types::Bounds;
 
@@ -29,7 +29,7 @@ impl MouseCaptureArea {
     pub fn new(is_gui: bool, bounds: Bounds) -> MouseCaptureArea {
         MouseCaptureArea {
             is_hovered: false,
-            is_gui: is_gui,
+            is_gui,
             down_buttons: [false; 5],
             clicked_buttons: [false; 5],
             released_buttons: [false; 5],
@@ -112,8 +112,7 @@ impl Mutation for ClearOthersMutation {
                     return e != excluded;
                 }
                 return true;
-            })
-            .map(|&e| e.clone())
+            }).copied()
             .collect();
 
         for e in to_clear {
@@ -132,8 +131,7 @@ impl Mutation for ClearOthersMutation {
                     return e != excluded;
                 }
                 return true;
-            })
-            .map(|&e| e.clone())
+            }).copied()
             .collect();
 
         for e in to_clear {
@@ -146,11 +144,22 @@ impl Mutation for ClearOthersMutation {
     }
 }
 
+/// Drops a capture area from every layer when its component is removed or its entity deleted.
+pub fn remove_from_capture_layers(ecs: &mut ECS, area: &Entity) {
+    for layer in ecs.get1::<MouseCaptureLayer>() {
+        let layer = ecs.write::<MouseCaptureLayer>(&layer).unwrap();
+        layer.capture_areas.retain(|a| a != area);
+    }
+}
+
 pub fn mouse_capture_system(ecs: &ECS, _dt: f32) -> Mutations {
-    let (camera, transform, _) = ecs.read2::<Camera, TransformComponent>()[0];
+    let (camera, transform) = ecs
+        .query::<(&Camera, &TransformComponent)>()
+        .next()
+        .unwrap();
 
     let mut layers = ecs.read2::<MouseCaptureLayer, ActiveCaptureLayer>();
-    layers.sort_by(|a, b| b.0.sort_index.cmp(&a.0.sort_index));
+    layers.sort_by_key(|b| std::cmp::Reverse(b.0.sort_index));
 
     let input = ecs.read_res::<GameInput>();
     let mouse_x = input.mouse.x;
@@ -192,7 +201,7 @@ pub fn mouse_capture_system(ecs: &ECS, _dt: f32) -> Mutations {
 
     for (layer, _, _) in layers {
         for area_e in &layer.capture_areas {
-            let area = ecs.read::<MouseCaptureArea>(&area_e);
+            let area = ecs.read::<MouseCaptureArea>(area_e);
             if let Some(area) = area {
                 let (x, y) = if area.is_gui {
                     (mouse_x as f32, mouse_y as f32)
@@ -202,30 +211,30 @@ pub fn mouse_capture_system(ecs: &ECS, _dt: f32) -> Mutations {
                 if area.bounds.contains_point(x, y) {
                     return vec![
                         Box::new(MouseCaptureMutation {
-                            area: area_e.clone(),
+                            area: *area_e,
                             down_buttons,
                             clicked_buttons,
                             released_buttons,
                         }),
                         Box::new(ClearOthersMutation {
-                            excluded: Some(area_e.clone()),
+                            excluded: Some(*area_e),
                         }),
                     ];
                 }
             }
 
-            let hex_area = ecs.read::<HexMouseCaptureArea>(&area_e);
+            let hex_area = ecs.read::<HexMouseCaptureArea>(area_e);
             if let Some(hex_area) = hex_area {
-                if hex_area.overlaps_mouse([mouse_world_pos.x as f32, mouse_world_pos.y as f32]) {
+                if hex_area.overlaps_mouse([mouse_world_pos.x, mouse_world_pos.y]) {
                     return vec![
                         Box::new(MouseCaptureMutation {
-                            area: area_e.clone(),
+                            area: *area_e,
                             down_buttons,
                             clicked_buttons,
                             released_buttons,
                         }),
                         Box::new(ClearOthersMutation {
-                            excluded: Some(area_e.clone()),
+                            excluded: Some(*area_e),
                         }),
                     ];
                 }
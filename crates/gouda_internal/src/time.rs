@@ -0,0 +1,115 @@
+use gouda_window::WindowProps;
+
+/// Controls how the main loop advances the simulation. Added as a resource when the engine starts,
+/// using the window's target frame time as the fixed step, and read again every frame so games can
+/// change it from `setup` or from a system.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeSettings {
+    /// Seconds of simulation advanced by every run of the systems
+    pub fixed_step: f32,
+    /// The most simulation steps run before a single render. Any time beyond that is dropped so a
+    /// slow frame can't snowball into ever longer catch-ups.
+    pub max_steps_per_frame: u32,
+}
+
+impl TimeSettings {
+    pub fn from_props(props: &WindowProps) -> Self {
+        TimeSettings {
+            fixed_step: props.target_ms_per_frame / 1000.,
+            max_steps_per_frame: 5,
+        }
+    }
+}
+
+impl Default for TimeSettings {
+    fn default() -> Self {
+        TimeSettings::from_props(&WindowProps::default())
+    }
+}
+
+/// Game time as seen by systems and render code.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Time {
+    /// Total seconds simulated so far
+    pub elapsed: f32,
+    /// Number of frames rendered so far
+    pub frame_count: u64,
+    /// Seconds advanced by the most recent simulation step
+    pub delta: f32,
+    /// How far between the last simulation step and the next one the current render falls, from 0
+    /// to 1. Render code can use it to interpolate between previous and current state.
+    pub alpha: f32,
+}
+
+/// Accumulates real frame time and hands it out in whole fixed steps.
+#[derive(Debug, Default)]
+pub(crate) struct FixedTimestep {
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    /// Adds `frame_time` seconds and returns how many fixed steps should run, capped at
+    /// `max_steps_per_frame`. Time that doesn't fit under the cap is discarded.
+    pub(crate) fn advance(&mut self, frame_time: f32, settings: &TimeSettings) -> u32 {
+        self.accumulator += frame_time;
+        let mut steps = 0;
+        while self.accumulator >= settings.fixed_step && steps < settings.max_steps_per_frame {
+            self.accumulator -= settings.fixed_step;
+            steps += 1;
+        }
+        if self.accumulator >= settings.fixed_step {
+            self.accumulator %= settings.fixed_step;
+        }
+        return steps;
+    }
+
+    /// The fraction of a fixed step left in the accumulator
+    pub(crate) fn alpha(&self, settings: &TimeSettings) -> f32 {
+        return (self.accumulator / settings.fixed_step).clamp(0., 1.);
+    }
+
+    /// Seconds until enough time has accumulated for the next step
+    pub(crate) fn until_next_step(&self, settings: &TimeSettings) -> f32 {
+        return (settings.fixed_step - self.accumulator).max(0.);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> TimeSettings {
+        TimeSettings {
+            fixed_step: 0.25,
+            max_steps_per_frame: 3,
+        }
+    }
+
+    #[test]
+    fn test_accumulates_partial_steps() {
+        let mut timestep = FixedTimestep::default();
+        assert_eq!(timestep.advance(0.125, &settings()), 0);
+        assert_eq!(timestep.alpha(&settings()), 0.5);
+        assert_eq!(timestep.until_next_step(&settings()), 0.125);
+
+        assert_eq!(timestep.advance(0.5, &settings()), 2);
+        assert_eq!(timestep.alpha(&settings()), 0.5);
+    }
+
+    #[test]
+    fn test_caps_catch_up() {
+        let mut timestep = FixedTimestep::default();
+        assert_eq!(timestep.advance(10.125, &settings()), 3);
+        assert_eq!(timestep.alpha(&settings()), 0.5);
+        assert_eq!(timestep.advance(0.125, &settings()), 1);
+    }
+
+    #[test]
+    fn test_settings_from_props() {
+        let props = WindowProps {
+            target_ms_per_frame: 20.,
+            ..WindowProps::default()
+        };
+        assert_eq!(TimeSettings::from_props(&props).fixed_step, 0.02);
+    }
+}
@@ -73,19 +73,19 @@ impl Mat4x4 {
                     2. / (right - left),
                     0.,
                     0.,
-                    -1. * ((right + left) / (right - left)),
+                    -((right + left) / (right - left)),
                 ],
                 [
                     0.,
                     2. / (top - bottom),
                     0.,
-                    -1. * ((top + bottom) / (top - bottom)),
+                    -((top + bottom) / (top - bottom)),
                 ],
                 [
                     0.,
                     0.,
                     -2. / (far - near),
-                    -1. * ((far + near) / (far - near)),
+                    -((far + near) / (far - near)),
                 ],
                 [0., 0., 0., 1.],
             ],
@@ -128,8 +128,8 @@ impl Mat4x4 {
         };
     }
 
-    pub fn get_val(&self, row: usize, col: usize) {
-        self.data[row][col];
+    pub fn get_val(&self, row: usize, col: usize) -> f32 {
+        return self.data[row][col];
     }
 
     pub fn raw_data(&self) -> [f32; 16] {
@@ -0,0 +1,35 @@
+#![cfg(not(any(target_os = "macos", target_os = "windows")))]
+
+use gouda_rendering::Renderer;
+use gouda_window::headless::{HeadlessWindow, ScriptedFrame};
+use gouda_window::WindowProps;
+use std::rc::Rc;
+
+/// A platform layer with no real window behind it. Frames are rendered into the software
+/// renderer's framebuffer and input comes from a script, so games can run without a display.
+pub struct HeadlessPlatformLayer {
+    window: HeadlessWindow,
+    renderer: Rc<Renderer>,
+}
+
+impl HeadlessPlatformLayer {
+    pub fn new(props: WindowProps) -> Self {
+        return HeadlessPlatformLayer::scripted(props, vec![]);
+    }
+
+    pub fn scripted(props: WindowProps, frames: Vec<ScriptedFrame>) -> Self {
+        let renderer = Renderer::new_headless(props.width as usize, props.height as usize);
+        HeadlessPlatformLayer {
+            window: HeadlessWindow::scripted(props, frames),
+            renderer: Rc::new(renderer),
+        }
+    }
+
+    pub fn get_window(&mut self) -> &mut HeadlessWindow {
+        return &mut self.window;
+    }
+
+    pub fn get_renderer(&mut self) -> &Rc<Renderer> {
+        return &self.renderer;
+    }
+}
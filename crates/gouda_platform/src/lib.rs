@@ -9,3 +9,15 @@ pub mod win32;
 
 #[cfg(target_os = "windows")]
 pub use win32::Win32PlatformLayer as PlatformLayer;
+
+#[cfg(target_os = "linux")]
+pub mod linux;
+
+#[cfg(target_os = "linux")]
+pub use linux::LinuxPlatformLayer as PlatformLayer;
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub mod headless;
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub use headless::HeadlessPlatformLayer;
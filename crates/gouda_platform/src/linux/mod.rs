@@ -0,0 +1,33 @@
+#![cfg(target_os = "linux")]
+
+use gouda_rendering::Renderer;
+use gouda_window::{GameWindow, PlatformWindow, WindowProps};
+use std::rc::Rc;
+
+pub struct LinuxPlatformLayer {
+    window: GameWindow,
+    renderer: Rc<Renderer>,
+}
+
+impl LinuxPlatformLayer {
+    pub fn new(props: WindowProps) -> Self {
+        let mut window = PlatformWindow::new(props);
+        let renderer = Renderer::new(&mut window).unwrap();
+        renderer
+            .platform_renderer
+            .attach_presenter(Box::new(window.presenter()));
+
+        LinuxPlatformLayer {
+            window: GameWindow::new(window),
+            renderer: Rc::new(renderer),
+        }
+    }
+
+    pub fn get_window(&mut self) -> &mut GameWindow {
+        return &mut self.window;
+    }
+
+    pub fn get_renderer(&mut self) -> &Rc<Renderer> {
+        return &self.renderer;
+    }
+}
@@ -31,10 +31,19 @@ impl ShaderDataType {
     }
 }
 
+/// How often the shader moves on to the next element of a vertex buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepRate {
+    PerVertex,
+    /// Advances once every this many instances.
+    PerInstance(u32),
+}
+
 #[derive(Debug)]
 pub struct BufferLayout {
     pub elements: Vec<BufferElement>,
     pub stride: u32,
+    pub step_rate: StepRate,
 }
 
 impl BufferLayout {
@@ -42,11 +51,23 @@ impl BufferLayout {
         let mut res = BufferLayout {
             elements,
             stride: 0,
+            step_rate: StepRate::PerVertex,
         };
         res.calculate_offsets_and_stride();
         res
     }
 
+    /// A layout for per-instance data, which advances once per instance. Buffers with this layout
+    /// are bound to the instance slot rather than replacing the vertex buffer.
+    pub fn per_instance(elements: Vec<BufferElement>) -> BufferLayout {
+        return BufferLayout::new(elements).with_step_rate(StepRate::PerInstance(1));
+    }
+
+    pub fn with_step_rate(mut self, step_rate: StepRate) -> BufferLayout {
+        self.step_rate = step_rate;
+        return self;
+    }
+
     fn calculate_offsets_and_stride(&mut self) {
         let mut offset = 0;
         self.stride = 0;
@@ -1,4 +1,4 @@
-use cgmath::{ortho, perspective, Deg, Matrix4, Rad, SquareMatrix, Vector3};
+use cgmath::{ortho, perspective, Deg, Matrix4, SquareMatrix, Vector3};
 
 pub fn matrix_to_vec<T>(matrix: Matrix4<T>) -> Vec<T> {
     return vec![
@@ -26,8 +26,8 @@ pub enum Camera {
 impl Camera {
     pub fn get_projection_matrix(&self) -> Matrix4<f32> {
         match self {
-            Camera::Orthographic(orthographicCamera) => orthographicCamera.get_projection_matrix(),
-            Camera::Perspective(perspectiveCamera) => perspectiveCamera.get_projection_matrix(),
+            Camera::Orthographic(orthographic_camera) => orthographic_camera.get_projection_matrix(),
+            Camera::Perspective(perspective_camera) => perspective_camera.get_projection_matrix(),
         }
     }
 
@@ -71,7 +71,7 @@ impl OrthographicCamera {
     pub fn new(size: f32) -> Self {
         let mut res = Self {
             projection_matrix: Matrix4::identity(),
-            size: size,
+            size,
         };
         res.recalculate();
         return res;
@@ -110,7 +110,7 @@ impl PerspectiveCamera {
     pub fn new(size: f32) -> Self {
         let mut res = Self {
             projection_matrix: Matrix4::identity(),
-            size: size,
+            size,
         };
         res.recalculate();
         return res;
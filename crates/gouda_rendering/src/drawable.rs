@@ -167,7 +167,7 @@ impl QuadDrawable {
         self.vertex_buffer.bind(scene);
         self.transform_buffer.bind(scene);
         self.index_buffer.bind(scene);
-        self.color_buffer.bind(&scene);
+        self.color_buffer.bind(scene);
 
         scene.draw_indexed(&self.index_buffer);
     }
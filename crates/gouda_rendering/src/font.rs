@@ -101,8 +101,8 @@ impl TextDrawable {
                     let offset = config.size[0] - line_width;
                     let offset = offset / 2.;
 
-                    for i in line_start_index..current_char_index {
-                        vertices[i][0] += offset;
+                    for vertex in &mut vertices[line_start_index..current_char_index] {
+                        vertex[0] += offset;
                     }
                 }
 
@@ -118,8 +118,8 @@ impl TextDrawable {
             let offset = config.size[0] - line_width;
             let offset = offset / 2.;
 
-            for i in line_start_index..current_char_index {
-                vertices[i][0] += offset;
+            for vertex in &mut vertices[line_start_index..current_char_index] {
+                vertex[0] += offset;
             }
         }
 
@@ -128,8 +128,8 @@ impl TextDrawable {
             let offset = config.size[1] - text_height;
             let offset = offset / 2.;
 
-            for i in 0..vertices.len() {
-                vertices[i][1] -= offset;
+            for vertex in vertices.iter_mut() {
+                vertex[1] -= offset;
             }
         }
 
@@ -172,7 +172,7 @@ pub struct Font {
     line_height: f32,
 }
 
-fn collect_elements(line: &String) -> HashMap<String, String> {
+fn collect_elements(line: &str) -> HashMap<String, String> {
     let mut result = HashMap::new();
     let lines: Vec<&str> = line.split_whitespace().collect();
     for line in lines {
@@ -192,10 +192,10 @@ impl Font {
         line_iter.next().unwrap();
         line_iter.next().unwrap();
 
-        let info = collect_elements(&info_line.to_string());
-        let common = collect_elements(&common_line.to_string());
+        let info = collect_elements(&info_line);
+        let common = collect_elements(&common_line);
 
-        while let Some(line) = line_iter.next() {
+        for line in &mut *line_iter {
             font_entries.push(collect_elements(&line.to_string()));
         }
 
@@ -278,7 +278,7 @@ impl Font {
         let texture = PNG::from_file(font_png_path).unwrap().image();
         let texture = Texture::new(renderer, &texture);
 
-        let mut line_iter = font_file_reader.lines().filter_map(|result| result.ok());
+        let mut line_iter = font_file_reader.lines().map_while(Result::ok);
         return Font::new_impl(texture, &mut line_iter);
     }
 }
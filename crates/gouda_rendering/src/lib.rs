@@ -7,14 +7,16 @@ pub mod material_library;
 pub mod model;
 pub mod obj;
 pub mod platform;
+pub mod render_queue;
 pub mod shader_lib;
 pub mod shaders;
 pub mod shapes;
+pub mod sprite_batch;
 pub mod sprites;
 pub mod texture_library;
 
 use buffers::{IndexBuffer, VertexBuffer};
-use camera::{Camera, OrthographicCamera};
+use camera::Camera;
 use cgmath::{Matrix4, SquareMatrix};
 use font::Font;
 use font_library::FontLibrary;
@@ -22,14 +24,21 @@ use gouda_images::Image;
 use gouda_transform::TransformComponent;
 use gouda_window::PlatformWindow;
 use material_library::Material;
-use model::Model;
+use model::{Mesh, Model, Submesh};
 use obj::ObjMesh;
+use render_queue::{
+    DepthMode, Geometry, Instances, RenderCommand, RenderLayer, RenderQueue, RenderStats,
+};
 use rendering_platform::texture::PlatformTexture;
 use rendering_platform::{PlatformRenderer, PlatformScene};
 use shader_lib::imgui_shader::imgui_shader_layout;
+use shader_lib::instance_transform_layout;
 use shader_lib::ShaderLibrary;
 use shaders::{Shader, ShaderUniform};
 use shapes::{Shape2d, ShapeLibrary};
+use std::cell::RefCell;
+use std::rc::Rc;
+use texture_library::{TextureHandle, TextureLibrary, TextureLoadError};
 
 #[cfg(target_os = "macos")]
 pub use crate::platform::metal as rendering_platform;
@@ -40,6 +49,12 @@ pub use crate::platform::d3d11 as rendering_platform;
 #[cfg(all(target_os = "windows", feature = "use_d3d12"))]
 pub use crate::platform::d3d12 as rendering_platform;
 
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub use crate::platform::software as rendering_platform;
+
+/// Position of the single light used by the lit obj shaders.
+pub const LIGHT_POSITION: [f32; 3] = [-20., 2.5, -2.5];
+
 pub trait Renderable {
     fn bind(&self, scene: &Scene);
     fn num_indices(&self) -> u64;
@@ -85,6 +100,7 @@ pub struct Renderer {
     pub shader_lib: ShaderLibrary,
     pub shape_lib: ShapeLibrary,
     pub font_lib: FontLibrary,
+    pub texture_lib: RefCell<TextureLibrary>,
 }
 
 impl Renderer {
@@ -92,20 +108,30 @@ impl Renderer {
         let platform_renderer = PlatformRenderer::new(window);
 
         match platform_renderer {
-            Ok(platform_renderer) => {
-                let mut renderer = Renderer {
-                    platform_renderer,
-                    shader_lib: ShaderLibrary::new(),
-                    shape_lib: ShapeLibrary::new(),
-                    font_lib: FontLibrary::new(),
-                };
-                renderer.initialize_libs();
-                return Ok(renderer);
-            }
+            Ok(platform_renderer) => Ok(Renderer::from_platform_renderer(platform_renderer)),
             Err(e) => Err(e.to_string()),
         }
     }
 
+    /// Creates a renderer that draws into an offscreen framebuffer of the given size, without
+    /// needing a window.
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    pub fn new_headless(width: usize, height: usize) -> Renderer {
+        return Renderer::from_platform_renderer(PlatformRenderer::with_size(width, height));
+    }
+
+    fn from_platform_renderer(platform_renderer: PlatformRenderer) -> Renderer {
+        let mut renderer = Renderer {
+            platform_renderer,
+            shader_lib: ShaderLibrary::new(),
+            shape_lib: ShapeLibrary::new(),
+            font_lib: FontLibrary::new(),
+            texture_lib: RefCell::new(TextureLibrary::new()),
+        };
+        renderer.initialize_libs();
+        return renderer;
+    }
+
     fn initialize_libs(&mut self) {
         let mut shader_lib = ShaderLibrary::new();
         shader_lib.initialize(self);
@@ -130,10 +156,29 @@ impl Renderer {
         return self.font_lib.get(name).unwrap();
     }
 
+    /// Loads the PNG at `path` through the texture library, so repeated loads share one texture.
+    /// Panics if the file can't be loaded.
+    pub fn load_texture(&self, path: &str) -> TextureHandle {
+        match self.try_load_texture(path) {
+            Ok(texture) => texture,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    pub fn try_load_texture(&self, path: &str) -> Result<TextureHandle, TextureLoadError> {
+        return self.texture_lib.borrow_mut().load(self, path);
+    }
+
     pub fn end_scene(&self, scene: Scene) {
         scene.end();
     }
 
+    /// Reads back the most recently rendered frame.
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    pub fn capture_frame(&self) -> Image {
+        return self.platform_renderer.capture_frame();
+    }
+
     pub fn get_width(&self) -> usize {
         return 900;
     }
@@ -142,24 +187,29 @@ impl Renderer {
         return 900;
     }
 
-    pub fn begin_scene(&self) -> Option<Scene> {
+    pub fn begin_scene(&self) -> Option<Scene<'_>> {
         let scene = Scene {
             renderer: self,
             camera_view_projection_matrix: Matrix4::identity(),
             platform_scene: self.platform_renderer.begin_scene().unwrap(),
+            queue: RefCell::new(RenderQueue::new()),
         };
 
         return Some(scene);
     }
 }
 
+/// Draws submitted to a scene are queued and executed when the scene ends, sorted by
+/// `RenderLayer`. The lower level `bind_*` and `draw_*` calls act immediately, so they flush the
+/// queue first to keep drawing on top of everything submitted before them.
 pub struct Scene<'a> {
     pub renderer: &'a Renderer,
     pub camera_view_projection_matrix: Matrix4<f32>,
     pub platform_scene: PlatformScene<'a>,
+    queue: RefCell<RenderQueue<'a>>,
 }
 
-impl Scene<'_> {
+impl<'a> Scene<'a> {
     pub fn bind_camera(&mut self, camera: &Camera, transform: &TransformComponent) {
         self.camera_view_projection_matrix = camera
             .calculate_view_projection_matrix(transform.position, transform.rotation.z)
@@ -171,35 +221,57 @@ impl Scene<'_> {
     }
 
     pub fn end(self) {
+        self.flush();
         self.platform_scene.end();
     }
 
-    fn submit_impl(
+    /// Executes everything queued so far. Anything drawn afterwards ends up on top of it.
+    pub fn flush(&self) -> RenderStats {
+        let mut queue = std::mem::take(&mut *self.queue.borrow_mut());
+        return queue.execute(self);
+    }
+
+    /// The number of draws waiting for the next flush.
+    pub fn queued(&self) -> usize {
+        return self.queue.borrow().len();
+    }
+
+    pub fn enqueue(&self, command: RenderCommand<'a>) {
+        self.queue.borrow_mut().push(command);
+    }
+
+    fn submit_renderable(
         &self,
-        shader: &Shader,
-        renderable: &impl Renderable,
+        layer: RenderLayer,
+        shader: &'a Shader,
+        renderable: &'a dyn Renderable,
         transform: Matrix4<f32>,
         projection: Matrix4<f32>,
         color: [f32; 4],
     ) {
-        shader.bind(&self);
-        shader.upload_vertex_uniform_mat4(&self, 0, projection);
-        shader.upload_vertex_uniform_mat4(&self, 1, transform);
-        shader.upload_fragment_uniform_float4(&self, 0, color);
-
-        renderable.bind(&self);
-
-        self.draw_indexed(renderable.index_buffer());
+        self.enqueue(RenderCommand {
+            layer,
+            shader,
+            material: None,
+            texture: None,
+            geometry: Geometry::Renderable(renderable),
+            projection,
+            transform,
+            fragment_uniforms: vec![(0, ShaderUniform::Float4(color))],
+            instances: None,
+        });
     }
 
+    /// Shapes are alpha blended, so they're queued as transparent and drawn back to front.
     pub fn submit(
         &self,
-        shader: &Shader,
-        renderable: &impl Renderable,
+        shader: &'a Shader,
+        renderable: &'a dyn Renderable,
         transform: Matrix4<f32>,
         color: [f32; 4],
     ) {
-        self.submit_impl(
+        self.submit_renderable(
+            RenderLayer::Transparent,
             shader,
             renderable,
             transform,
@@ -208,42 +280,151 @@ impl Scene<'_> {
         );
     }
 
-    pub fn submit_model(&self, model: Model) {
-        model
-            .mesh
-            .submeshes
-            .iter()
-            .enumerate()
-            .for_each(|(index, submesh)| {
-                let mat = model.materials.get(index).unwrap();
-            })
+    /// Draws each submesh of the model with its material. The camera and `transform` go to vertex
+    /// slots 0 and 1, and the light to fragment slot 2 unless the material sets that slot itself.
+    /// Materials that aren't fully opaque are drawn with the transparent layer.
+    pub fn submit_model(&self, model: &Model, transform: Matrix4<f32>) {
+        for (submesh, material) in model.mesh.submeshes.iter().zip(model.materials.iter()) {
+            self.submit_submesh(&model.mesh, submesh, material, transform, None);
+        }
     }
 
-    pub fn bind_material(&self, material: &Material) {}
-
-    pub fn submit_obj(&self, obj_model: &ObjMesh, transform: Matrix4<f32>) {
-        let shader = self.renderer.get_shader("obj_model");
-        shader.bind(&self);
-        shader.upload_vertex_uniform_mat4(&self, 0, self.camera_view_projection_matrix);
-        shader.upload_vertex_uniform_mat4(&self, 1, transform);
-
-        obj_model.vertex_buffer.bind(&self);
+    /// Draws every submesh of `mesh` with `material`, once for each of `transforms`, in one draw
+    /// per submesh. Uses the instanced variant of the material's shader, which is named after it
+    /// with an `_instanced` suffix.
+    pub fn submit_instanced(
+        &self,
+        mesh: &Mesh,
+        material: &Rc<Material>,
+        transforms: &[Matrix4<f32>],
+    ) {
+        if let Some(instances) = self.transform_instances(transforms) {
+            for submesh in &mesh.submeshes {
+                let instances = Some(instances.clone());
+                self.submit_submesh(mesh, submesh, material, Matrix4::identity(), instances);
+            }
+        }
+    }
 
-        if let Some(no_mat_ibuf) = &obj_model.no_material_index_buffer {
-            no_mat_ibuf.bind(&self);
-            self.draw_indexed_tris(no_mat_ibuf.num_indices, &no_mat_ibuf);
+    /// Like `submit_instanced`, with each submesh drawn with its own material.
+    pub fn submit_model_instanced(&self, model: &Model, transforms: &[Matrix4<f32>]) {
+        if let Some(instances) = self.transform_instances(transforms) {
+            for (submesh, material) in model.mesh.submeshes.iter().zip(model.materials.iter()) {
+                let instances = Some(instances.clone());
+                self.submit_submesh(
+                    &model.mesh,
+                    submesh,
+                    material,
+                    Matrix4::identity(),
+                    instances,
+                );
+            }
         }
+    }
 
-        obj_model.submeshes.iter().for_each(|submesh| {
-            shader.upload_fragment_uniform_float3(&self, 0, submesh.ambient);
-            shader.upload_fragment_uniform_float3(&self, 1, submesh.diffuse);
-            shader.upload_fragment_uniform_float3(&self, 2, [-20., 2.5, -2.5]);
+    fn transform_instances(&self, transforms: &[Matrix4<f32>]) -> Option<Instances> {
+        if transforms.is_empty() {
+            return None;
+        }
+        let buffer = VertexBuffer::new(
+            self.renderer,
+            instance_transform_layout(),
+            0,
+            transforms.to_vec(),
+        );
+        return Some(Instances {
+            buffer: Rc::new(buffer),
+            count: transforms.len() as u32,
+        });
+    }
 
-            submesh.index_buffer.bind(&self);
-            self.draw_indexed_tris(submesh.index_buffer.num_indices, &submesh.index_buffer);
+    fn submit_submesh(
+        &self,
+        mesh: &Mesh,
+        submesh: &Submesh,
+        material: &Rc<Material>,
+        transform: Matrix4<f32>,
+        instances: Option<Instances>,
+    ) {
+        let shader = match instances {
+            Some(_) => self
+                .renderer
+                .shader_lib
+                .get(&format!("{}_instanced", material.shader)),
+            None => self.renderer.shader_lib.get(&material.shader),
+        };
+        let shader = match shader {
+            Some(shader) => shader,
+            None => return,
+        };
+        let layer = if material.dissolve < 1. {
+            RenderLayer::Transparent
+        } else {
+            RenderLayer::Opaque
+        };
+        self.enqueue(RenderCommand {
+            layer,
+            shader,
+            material: Some(material.clone()),
+            texture: material.texture.clone(),
+            geometry: Geometry::Indexed {
+                vertex_buffer: mesh.vertex_buffer.clone(),
+                index_buffer: mesh.index_buffer.clone(),
+                first_index: submesh.starting_index,
+                index_count: submesh.index_count,
+            },
+            projection: self.camera_view_projection_matrix,
+            transform,
+            fragment_uniforms: vec![(2, ShaderUniform::Float3(LIGHT_POSITION))],
+            instances,
         });
     }
 
+    /// Binds the material right away. Returns false if the material's shader isn't in the
+    /// library.
+    pub fn bind_material(&self, material: &Material) -> bool {
+        self.flush();
+        return material.bind(self);
+    }
+
+    /// Geometry without a material is drawn in white.
+    pub fn submit_obj(&self, obj_model: &ObjMesh, transform: Matrix4<f32>) {
+        let shader = self.renderer.get_shader("obj_model");
+        let white = ([1., 1., 1.], [1., 1., 1.]);
+        let no_material = obj_model
+            .no_material_index_buffer
+            .iter()
+            .map(|index_buffer| (index_buffer, white));
+        let submeshes = obj_model
+            .submeshes
+            .iter()
+            .map(|submesh| (&submesh.index_buffer, (submesh.ambient, submesh.diffuse)));
+
+        for (index_buffer, (ambient, diffuse)) in no_material.chain(submeshes) {
+            self.enqueue(RenderCommand {
+                layer: RenderLayer::Opaque,
+                shader,
+                material: None,
+                texture: None,
+                geometry: Geometry::Indexed {
+                    vertex_buffer: obj_model.vertex_buffer.clone(),
+                    index_buffer: index_buffer.clone(),
+                    first_index: 0,
+                    index_count: index_buffer.num_indices as u32,
+                },
+                projection: self.camera_view_projection_matrix,
+                transform,
+                fragment_uniforms: vec![
+                    (0, ShaderUniform::Float3(ambient)),
+                    (1, ShaderUniform::Float3(diffuse)),
+                    (2, ShaderUniform::Float3(LIGHT_POSITION)),
+                ],
+                instances: None,
+            });
+        }
+    }
+
+    /// Queued with the overlay layer, so it's drawn over everything else in the scene.
     pub fn submit_shape_gui(
         &self,
         shader_name: &'static str,
@@ -254,7 +435,8 @@ impl Scene<'_> {
         let shader = self.renderer.get_shader(shader_name);
         let shape = self.renderer.get_shape(shape_name);
 
-        self.submit_impl(
+        self.submit_renderable(
+            RenderLayer::Overlay,
             shader,
             shape,
             transform,
@@ -273,7 +455,8 @@ impl Scene<'_> {
         let shader = self.renderer.get_shader(shader_name);
         let shape = self.renderer.get_shape(shape_name);
 
-        self.submit_impl(
+        self.submit_renderable(
+            RenderLayer::Transparent,
             shader,
             shape,
             transform,
@@ -284,32 +467,62 @@ impl Scene<'_> {
 
     fn submit_texture_with_projection(
         &self,
-        texture: &Texture,
+        layer: RenderLayer,
+        texture: &TextureHandle,
         transform: Matrix4<f32>,
         projection: Matrix4<f32>,
     ) {
-        let shader = self.renderer.get_shader("texture");
-        let shape = self.renderer.get_shape("texture");
-        texture.bind(self);
-        shader.bind(self);
-        shader.upload_vertex_uniform_mat4(self, 0, projection);
-        shader.upload_vertex_uniform_mat4(self, 1, transform);
-
-        shape.bind(self);
-
-        self.draw_indexed(shape.index_buffer());
+        self.enqueue(RenderCommand {
+            layer,
+            shader: self.renderer.get_shader("texture"),
+            material: None,
+            texture: Some(texture.clone()),
+            geometry: Geometry::Renderable(self.renderer.get_shape("texture")),
+            projection,
+            transform,
+            fragment_uniforms: vec![],
+            instances: None,
+        });
     }
 
-    pub fn submit_gui_texture(&self, texture: &Texture, transform: Matrix4<f32>) {
-        self.submit_texture_with_projection(texture, transform, Matrix4::identity())
+    pub fn submit_gui_texture(&self, texture: &TextureHandle, transform: Matrix4<f32>) {
+        self.submit_texture_with_projection(
+            RenderLayer::Overlay,
+            texture,
+            transform,
+            Matrix4::identity(),
+        )
     }
 
-    pub fn submit_texture(&self, texture: &Texture, transform: Matrix4<f32>) {
-        self.submit_texture_with_projection(&texture, transform, self.camera_view_projection_matrix)
+    pub fn submit_texture(&self, texture: &TextureHandle, transform: Matrix4<f32>) {
+        self.submit_texture_with_projection(
+            RenderLayer::Transparent,
+            texture,
+            transform,
+            self.camera_view_projection_matrix,
+        )
+    }
+
+    /// Draws the texture once for each of `transforms` with a single instanced draw.
+    pub fn submit_texture_instanced(&self, texture: &TextureHandle, transforms: &[Matrix4<f32>]) {
+        if let Some(instances) = self.transform_instances(transforms) {
+            self.enqueue(RenderCommand {
+                layer: RenderLayer::Transparent,
+                shader: self.renderer.get_shader("texture_instanced"),
+                material: None,
+                texture: Some(texture.clone()),
+                geometry: Geometry::Renderable(self.renderer.get_shape("texture")),
+                projection: self.camera_view_projection_matrix,
+                transform: Matrix4::identity(),
+                fragment_uniforms: vec![],
+                instances: Some(instances),
+            });
+        }
     }
 
     pub fn bind_shader(&self, shader: &'static str) {
-        self.renderer.shader_lib.bind_shader(&self, shader);
+        self.flush();
+        self.renderer.shader_lib.bind_shader(self, shader);
     }
 
     pub fn bind_shader_with_uniforms(
@@ -318,34 +531,38 @@ impl Scene<'_> {
         vertex_uniforms: Vec<ShaderUniform>,
         fragment_uniforms: Vec<ShaderUniform>,
     ) {
+        self.flush();
         let shader = self.renderer.shader_lib.get(shader);
         if let Some(shader) = shader {
             for (i, uniform) in vertex_uniforms.iter().enumerate() {
-                shader.upload_vertex_uniform(&self, i as u32, *uniform);
+                shader.upload_vertex_uniform(self, i as u32, *uniform);
             }
             for (i, uniform) in fragment_uniforms.iter().enumerate() {
-                shader.upload_fragment_uniform(&self, i as u32, *uniform);
+                shader.upload_fragment_uniform(self, i as u32, *uniform);
             }
-            shader.bind(&self);
+            shader.bind(self);
         }
     }
 
     pub fn draw_shape(&self, shape: &'static str) {
-        self.renderer.shape_lib.bind_shape(&self, shape);
+        self.flush();
+        self.renderer.shape_lib.bind_shape(self, shape);
         let shape = self.renderer.shape_lib.get(shape).unwrap();
 
-        shape.bind(&self);
+        shape.bind(self);
 
         self.draw_indexed(shape.index_buffer());
     }
 
     pub fn bind_font(&self, font: &'static str) {
-        self.renderer.get_font(font).texture.bind(&self);
+        self.flush();
+        self.renderer.get_font(font).texture.bind(self);
     }
 
+    /// Draws immediately, since the vertices are only borrowed for this call.
     pub fn submit_imgui(
         &self,
-        vbuf: &Vec<[f32; 8]>,
+        vbuf: &[[f32; 8]],
         ibuf: &[u16],
         count: usize,
         _vtx_offset: usize,
@@ -355,15 +572,21 @@ impl Scene<'_> {
     ) {
         self.bind_shader_with_uniforms("imgui", vec![ShaderUniform::Mat4(matrix)], vec![]);
         let vertex_buffer =
-            VertexBuffer::new::<[f32; 8]>(&self.renderer, imgui_shader_layout(), 0, vbuf.clone());
-        vertex_buffer.bind(&self);
+            VertexBuffer::new::<[f32; 8]>(self.renderer, imgui_shader_layout(), 0, vbuf.to_vec());
+        vertex_buffer.bind(self);
         let index_buffer = IndexBuffer::new(self.renderer, ibuf.to_vec());
-        index_buffer.bind_with_offset(&self, idx_offset as u32);
-        texture.bind(&self);
+        index_buffer.bind_with_offset(self, idx_offset as u32);
+        texture.bind(self);
 
         self.draw_indexed_tris(count as u64, &index_buffer);
     }
 
+    /// Sets how the draws that follow use the depth buffer. The queue sets this for each layer
+    /// and disables it again once it has executed.
+    pub fn set_depth_mode(&self, mode: DepthMode) {
+        self.platform_scene.set_depth_mode(mode);
+    }
+
     pub fn draw_indexed(&self, index_buffer: &buffers::IndexBuffer) {
         self.platform_scene
             .draw_indexed(index_buffer.num_indices, &index_buffer.platform_buffer);
@@ -374,6 +597,20 @@ impl Scene<'_> {
             .draw_indexed_tris(num_indices, &index_buffer.platform_buffer);
     }
 
+    /// Draws the indexed triangles once per instance in the bound instance buffer.
+    pub fn draw_indexed_tris_instanced(
+        &self,
+        num_indices: u64,
+        num_instances: u64,
+        index_buffer: &buffers::IndexBuffer,
+    ) {
+        self.platform_scene.draw_indexed_tris_instanced(
+            num_indices,
+            num_instances,
+            &index_buffer.platform_buffer,
+        );
+    }
+
     pub fn draw_tri_strip(&self, num_verts: u64) {
         self.platform_scene.draw_tri_strip(num_verts);
     }
@@ -382,3 +619,68 @@ impl Scene<'_> {
         self.platform_scene.draw_triangles(num_verts);
     }
 }
+
+#[cfg(all(test, not(any(target_os = "macos", target_os = "windows"))))]
+mod tests {
+    use super::*;
+    use crate::camera::OrthographicCamera;
+    use crate::font::{TextConfig, TextDrawable};
+    use cgmath::Vector3;
+    use gouda_images::golden::assert_matches_golden;
+
+    const TOLERANCE: u8 = 2;
+
+    fn golden(name: &str) -> String {
+        return format!("{}/goldens/{}.png", env!("CARGO_MANIFEST_DIR"), name);
+    }
+
+    fn translation(x: f32, y: f32, scale: f32) -> Matrix4<f32> {
+        return Matrix4::from_translation(Vector3::new(x, y, 0.)) * Matrix4::from_scale(scale);
+    }
+
+    #[test]
+    fn test_shapes_match_golden() {
+        let renderer = Renderer::new_headless(64, 64);
+        let camera = Camera::Orthographic(OrthographicCamera::new(2.));
+        let camera_transform = TransformComponent::builder().build();
+
+        let mut scene = renderer.begin_scene().unwrap();
+        scene.bind_camera(&camera, &camera_transform);
+        scene.submit_shape_by_name("quad", "quad", translation(-1., 1., 0.8), [1., 0., 0., 1.]);
+        scene.submit_shape_by_name("hex", "hex", translation(1., 1., 0.8), [0., 0., 1., 1.]);
+        scene.submit_shape_by_name(
+            "quad",
+            "triangle",
+            translation(0., -1., 0.8),
+            [1., 1., 0., 0.5],
+        );
+        scene.unbind_camera();
+        renderer.end_scene(scene);
+
+        assert_matches_golden(&renderer.capture_frame(), &golden("shapes"), TOLERANCE);
+    }
+
+    #[test]
+    fn test_text_matches_golden() {
+        let renderer = Renderer::new_headless(128, 64);
+        let text = TextDrawable::new(
+            &renderer,
+            TextConfig {
+                position: [-1., -1.],
+                size: [2., 2.],
+                center_x: true,
+                center_y: true,
+                font_name: "arial",
+                color: [1., 1., 1.],
+                text: "Gouda".to_string(),
+                font_size: 160.,
+            },
+        );
+
+        let scene = renderer.begin_scene().unwrap();
+        text.draw(&scene);
+        renderer.end_scene(scene);
+
+        assert_matches_golden(&renderer.capture_frame(), &golden("text"), TOLERANCE);
+    }
+}
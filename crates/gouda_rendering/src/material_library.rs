@@ -1,39 +1,152 @@
 use std::collections::HashMap;
 
 use crate::obj::{MtlFile, MtlMaterialDefinition};
-use crate::shaders::ShaderUniform;
+use crate::shaders::{Shader, ShaderUniform};
+use crate::texture_library::TextureHandle;
+use crate::{Renderer, Scene};
 
 pub type MaterialKey = String;
 pub type ShaderKey = String;
 
+/// A shader from the `ShaderLibrary` together with the fragment uniforms and texture it is drawn
+/// with. Fragment uniforms are uploaded to slots in order, starting at 0, and the texture is bound
+/// to the first texture slot.
+#[derive(Clone)]
 pub struct Material {
-    name: String,
-    shader: ShaderKey,
-    ambient_color: [f32; 3],
-    diffuse_color: [f32; 3],
-    specular_color: [f32; 3],
-    emission_color: [f32; 3],
-    specular_highlights: f32,
-    optical_density: f32,
-    dissolve: f32,
-    illumination: u8,
+    pub name: String,
+    pub shader: ShaderKey,
+    pub fragment_uniforms: Vec<ShaderUniform>,
+    pub texture: Option<TextureHandle>,
+    pub ambient_color: [f32; 3],
+    pub diffuse_color: [f32; 3],
+    pub specular_color: [f32; 3],
+    pub emission_color: [f32; 3],
+    pub specular_highlights: f32,
+    pub optical_density: f32,
+    pub dissolve: f32,
+    pub illumination: u8,
+}
+
+impl Material {
+    pub fn new(name: &str, shader: &str) -> Material {
+        return Material {
+            name: name.to_string(),
+            shader: shader.to_string(),
+            fragment_uniforms: vec![],
+            texture: None,
+            ambient_color: [0.; 3],
+            diffuse_color: [0.; 3],
+            specular_color: [0.; 3],
+            emission_color: [0.; 3],
+            specular_highlights: 0.,
+            optical_density: 0.,
+            dissolve: 1.,
+            illumination: 0,
+        };
+    }
+
+    pub fn with_uniforms(mut self, fragment_uniforms: Vec<ShaderUniform>) -> Material {
+        self.fragment_uniforms = fragment_uniforms;
+        return self;
+    }
+
+    pub fn with_texture(mut self, texture: TextureHandle) -> Material {
+        self.texture = Some(texture);
+        return self;
+    }
+
+    /// The lit obj shader in plain white, for geometry that has no material of its own.
+    pub fn default_obj() -> Material {
+        return Material::new("default", "obj_model").with_uniforms(vec![
+            ShaderUniform::Float3([1., 1., 1.]),
+            ShaderUniform::Float3([1., 1., 1.]),
+        ]);
+    }
+
+    /// Uses the lit obj shader with the ambient and diffuse colors as its uniforms. A diffuse map
+    /// is loaded through the renderer's texture library and switches to the textured variant. If
+    /// the map can't be loaded the material is drawn untextured.
+    pub fn from_mtl(renderer: &Renderer, definition: &MtlMaterialDefinition) -> Material {
+        let texture = definition
+            .diffuse_map
+            .as_ref()
+            .and_then(|path| renderer.try_load_texture(path).ok());
+        let shader = match texture {
+            Some(_) => "obj_model_textured",
+            None => "obj_model",
+        };
+        return Material {
+            name: definition.name.clone(),
+            shader: shader.to_string(),
+            fragment_uniforms: vec![
+                ShaderUniform::Float3(definition.ambient_color),
+                ShaderUniform::Float3(definition.diffuse_color),
+            ],
+            texture,
+            ambient_color: definition.ambient_color,
+            diffuse_color: definition.diffuse_color,
+            specular_color: definition.specular_color,
+            emission_color: definition.emission_color,
+            specular_highlights: definition.specular_highlights,
+            optical_density: definition.optical_density,
+            dissolve: definition.dissolve,
+            illumination: definition.illumination,
+        };
+    }
+
+    /// Binds the shader, uploads the fragment uniforms and binds the texture. Returns false if the
+    /// shader isn't in the library.
+    pub fn bind(&self, scene: &Scene) -> bool {
+        let shader = match scene.renderer.shader_lib.get(&self.shader) {
+            Some(shader) => shader,
+            None => return false,
+        };
+        shader.bind(scene);
+        self.upload_uniforms(scene, shader);
+        if let Some(texture) = &self.texture {
+            texture.bind(scene);
+        }
+        return true;
+    }
+
+    pub fn upload_uniforms(&self, scene: &Scene, shader: &Shader) {
+        for (i, uniform) in self.fragment_uniforms.iter().enumerate() {
+            shader.upload_fragment_uniform(scene, i as u32, *uniform);
+        }
+    }
 }
 
 pub struct MaterialLibrary {
     pub materials: HashMap<MaterialKey, Material>,
 }
 
-impl MaterialLibrary {}
+impl MaterialLibrary {
+    pub fn new() -> MaterialLibrary {
+        return MaterialLibrary {
+            materials: HashMap::new(),
+        };
+    }
 
-impl Material {
-    pub fn from_mtl(mtl_material_definition: MtlMaterialDefinition) {
-        // match mtl_material_definition.illumination {
-        //     0 => {}
-        //     1 => {}
-        //     2 => {}
-        //     3 => {}
-        //     4 => {}
-        //     5 => {}
-        // }
+    pub fn from_mtl(renderer: &Renderer, mtl_file: &MtlFile) -> MaterialLibrary {
+        let materials = mtl_file
+            .materials
+            .iter()
+            .map(|(name, definition)| (name.clone(), Material::from_mtl(renderer, definition)))
+            .collect();
+        return MaterialLibrary { materials };
+    }
+
+    pub fn add(&mut self, material: Material) {
+        self.materials.insert(material.name.clone(), material);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Material> {
+        return self.materials.get(name);
+    }
+}
+
+impl Default for MaterialLibrary {
+    fn default() -> Self {
+        MaterialLibrary::new()
     }
 }
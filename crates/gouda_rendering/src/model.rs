@@ -1,31 +1,55 @@
 use std::collections::HashMap;
+use std::rc::Rc;
 
 use crate::material_library::Material;
-use crate::obj::ObjFile;
+use crate::obj::{MtlFile, ObjFile};
 use crate::shader_lib::obj_model_shader::obj_model_shader_layout;
 use crate::Renderer;
 
 use super::buffers::{IndexBuffer, VertexBuffer};
 
+/// A mesh with one material per submesh, in the same order.
 pub struct Model {
     pub mesh: Mesh,
-    pub materials: Vec<Material>,
+    pub materials: Vec<Rc<Material>>,
 }
 
-impl Model {}
+impl Model {
+    /// Builds the mesh and a material for each of its submeshes. Submeshes without a material, or
+    /// whose material is missing from the mtl file, use `Material::default_obj`.
+    pub fn new(renderer: &Renderer, obj_file: ObjFile, mtl_file: &MtlFile) -> Model {
+        let mesh = Mesh::new_from_obj(renderer, obj_file);
+        let materials = mesh
+            .submeshes
+            .iter()
+            .map(|submesh| {
+                return match submesh
+                    .material
+                    .as_ref()
+                    .and_then(|name| mtl_file.materials.get(name))
+                {
+                    Some(definition) => Rc::new(Material::from_mtl(renderer, definition)),
+                    None => Rc::new(Material::default_obj()),
+                };
+            })
+            .collect();
+        return Model { mesh, materials };
+    }
+}
 
 pub struct Mesh {
-    pub vertex_buffer: VertexBuffer,
-    pub index_buffer: IndexBuffer,
+    pub vertex_buffer: Rc<VertexBuffer>,
+    pub index_buffer: Rc<IndexBuffer>,
     pub submeshes: Vec<Submesh>,
 }
 
 pub struct Submesh {
     pub starting_index: u32,
     pub index_count: u32,
+    pub material: Option<String>,
 }
 
-#[repr(packed(1))]
+#[repr(C, packed(1))]
 pub struct Vert {
     pub pos: [f32; 4],
     pub tex: [f32; 2],
@@ -64,7 +88,7 @@ impl Mesh {
                 indices.push(index + i as u16 + 2);
             }
 
-            index = index + numverts as u16;
+            index += numverts as u16;
         }
 
         let mut submeshes: Vec<Submesh> = vec![];
@@ -74,11 +98,12 @@ impl Mesh {
 
         let mut all_indices: Vec<u16> = vec![];
         let mut submesh_idx = 0;
-        if no_material_indices.len() > 0 {
+        if !no_material_indices.is_empty() {
             let len = no_material_indices.len() as u32;
             submeshes.push(Submesh {
                 starting_index: submesh_idx,
                 index_count: len,
+                material: None,
             });
             all_indices.append(&mut no_material_indices);
 
@@ -92,6 +117,7 @@ impl Mesh {
                 submeshes.push(Submesh {
                     starting_index: submesh_idx,
                     index_count: len,
+                    material: Some(material),
                 });
                 all_indices.append(&mut indices);
                 submesh_idx += len;
@@ -99,9 +125,160 @@ impl Mesh {
         let index_buffer = IndexBuffer::new(renderer, all_indices);
 
         return Mesh {
-            vertex_buffer,
-            index_buffer,
+            vertex_buffer: Rc::new(vertex_buffer),
+            index_buffer: Rc::new(index_buffer),
             submeshes,
         };
     }
 }
+
+#[cfg(all(test, not(any(target_os = "macos", target_os = "windows"))))]
+mod tests {
+    use super::*;
+    use crate::obj::{load_mtl_file, MtlMaterialDefinition, ObjFace, ObjVert};
+    use cgmath::{Matrix4, SquareMatrix};
+    use gouda_images::png::PNG;
+    use gouda_images::Image;
+    use std::env;
+    use std::fs;
+
+    fn definition(
+        name: &str,
+        diffuse: [f32; 3],
+        diffuse_map: Option<String>,
+    ) -> MtlMaterialDefinition {
+        return MtlMaterialDefinition {
+            name: name.to_string(),
+            ambient_color: diffuse,
+            diffuse_color: diffuse,
+            specular_color: [0.; 3],
+            emission_color: [0.; 3],
+            specular_highlights: 0.,
+            optical_density: 0.,
+            dissolve: 1.,
+            illumination: 1,
+            ambient_map: None,
+            diffuse_map,
+            specular_map: None,
+        };
+    }
+
+    /// One quad per material, side by side across clip space, facing the camera.
+    fn quads(materials: &[&str]) -> ObjFile {
+        let mut obj = ObjFile {
+            vertices: vec![],
+            tex_coords: vec![[0., 0., 0.], [1., 0., 0.], [1., 1., 0.], [0., 1., 0.]],
+            vertex_normals: vec![[0., 0., 1.]],
+            faces: vec![],
+        };
+        let width = 2. / materials.len() as f32;
+        for (i, material) in materials.iter().enumerate() {
+            let left = -1. + width * i as f32;
+            let first = obj.vertices.len() + 1;
+            obj.vertices.extend_from_slice(&[
+                [left, -1., 0., 1.],
+                [left + width, -1., 0., 1.],
+                [left + width, 1., 0., 1.],
+                [left, 1., 0., 1.],
+            ]);
+            obj.faces.push(ObjFace {
+                material: Some(material.to_string()),
+                verts: (0..4)
+                    .map(|v| ObjVert {
+                        index: first + v,
+                        texcoord_index: v + 1,
+                        normal_index: 1,
+                    })
+                    .collect(),
+            });
+        }
+        return obj;
+    }
+
+    fn draw(renderer: &Renderer, model: &Model) {
+        let scene = renderer.begin_scene().unwrap();
+        scene.submit_model(model, Matrix4::identity());
+        renderer.end_scene(scene);
+    }
+
+    #[test]
+    fn test_submeshes_draw_with_their_materials() {
+        let renderer = Renderer::new_headless(16, 16);
+        let mut materials = HashMap::new();
+        materials.insert("red".to_string(), definition("red", [1., 0., 0.], None));
+        materials.insert("blue".to_string(), definition("blue", [0., 0., 1.], None));
+        let model = Model::new(
+            &renderer,
+            quads(&["red", "blue", "missing"]),
+            &MtlFile { materials },
+        );
+        assert_eq!(model.materials.len(), 3);
+
+        draw(&renderer, &model);
+
+        let framebuffer = renderer.platform_renderer.framebuffer();
+        assert_eq!(framebuffer.pixel(2, 8), [255, 0, 0, 255]);
+        assert_eq!(framebuffer.pixel(8, 8), [0, 0, 255, 255]);
+        assert_eq!(framebuffer.pixel(13, 8), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_diffuse_map_is_sampled() {
+        let dir = env::temp_dir().join(format!("gouda_material_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let raw = vec![
+            255, 0, 0, 255, 255, 0, 0, 255, 0, 0, 255, 255, 0, 0, 255, 255,
+        ];
+        let image = Image {
+            width: 1,
+            height: 4,
+            data: Image::data_from_raw_pixels(1, 4, raw),
+        };
+        let texture_path = dir.join("stripes.png");
+        PNG::from_image(&image)
+            .write_file(texture_path.to_str().unwrap())
+            .unwrap();
+        let mtl_path = dir.join("stripes.mtl");
+        fs::write(
+            &mtl_path,
+            "newmtl stripes\nKd 1 1 1\nmap_Kd -s 1 1 1 stripes.png\n",
+        )
+        .unwrap();
+
+        let mtl = load_mtl_file(mtl_path.to_str().unwrap()).unwrap();
+        let stripes = mtl.materials.get("stripes").unwrap();
+        assert_eq!(
+            stripes.diffuse_map,
+            Some(texture_path.to_string_lossy().into_owned())
+        );
+
+        let renderer = Renderer::new_headless(16, 16);
+        let model = Model::new(&renderer, quads(&["stripes"]), &mtl);
+        assert_eq!(model.materials[0].shader, "obj_model_textured");
+        draw(&renderer, &model);
+        fs::remove_dir_all(&dir).unwrap();
+
+        // Obj texture coordinates are bottom up, so the top of the image is drawn at the top
+        let framebuffer = renderer.platform_renderer.framebuffer();
+        assert_eq!(framebuffer.pixel(8, 2), [255, 0, 0, 255]);
+        assert_eq!(framebuffer.pixel(8, 13), [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn test_missing_diffuse_map_draws_untextured() {
+        let renderer = Renderer::new_headless(16, 16);
+        let mut materials = HashMap::new();
+        materials.insert(
+            "red".to_string(),
+            definition("red", [1., 0., 0.], Some("missing.png".to_string())),
+        );
+        let model = Model::new(&renderer, quads(&["red"]), &MtlFile { materials });
+        assert_eq!(model.materials[0].shader, "obj_model");
+        assert!(model.materials[0].texture.is_none());
+
+        draw(&renderer, &model);
+
+        let framebuffer = renderer.platform_renderer.framebuffer();
+        assert_eq!(framebuffer.pixel(8, 8), [255, 0, 0, 255]);
+    }
+}
@@ -2,13 +2,15 @@ use std::collections::HashMap;
 use std::error::Error;
 use std::fs::{self};
 use std::io::BufRead;
+use std::path::Path;
+use std::rc::Rc;
 
 use crate::shader_lib::obj_model_shader::obj_model_shader_layout;
 use crate::Renderer;
 
 use super::buffers::{IndexBuffer, VertexBuffer};
 
-#[repr(packed(1))]
+#[repr(C, packed(1))]
 pub struct Vert {
     pub pos: [f32; 4],
     pub tex: [f32; 2],
@@ -16,14 +18,14 @@ pub struct Vert {
 }
 
 pub struct ObjMeshSubset {
-    pub index_buffer: IndexBuffer,
+    pub index_buffer: Rc<IndexBuffer>,
     pub ambient: [f32; 3],
     pub diffuse: [f32; 3],
 }
 
 pub struct ObjMesh {
-    pub vertex_buffer: VertexBuffer,
-    pub no_material_index_buffer: Option<IndexBuffer>,
+    pub vertex_buffer: Rc<VertexBuffer>,
+    pub no_material_index_buffer: Option<Rc<IndexBuffer>>,
     pub submeshes: Vec<ObjMeshSubset>,
 }
 
@@ -65,7 +67,7 @@ impl ObjMesh {
         let vertex_buffer =
             VertexBuffer::new::<Vert>(renderer, obj_model_shader_layout(), 0, verts);
         let index_buffer = if !no_material_indices.is_empty() {
-            Some(IndexBuffer::new(renderer, no_material_indices))
+            Some(Rc::new(IndexBuffer::new(renderer, no_material_indices)))
         } else {
             None
         };
@@ -74,7 +76,7 @@ impl ObjMesh {
             .into_iter()
             .filter_map(|(material, indices)| {
                 if let Some(material) = mtl_file.materials.get(&material) {
-                    let index_buffer = IndexBuffer::new(renderer, indices);
+                    let index_buffer = Rc::new(IndexBuffer::new(renderer, indices));
                     Some(ObjMeshSubset {
                         index_buffer,
                         ambient: material.ambient_color,
@@ -87,7 +89,7 @@ impl ObjMesh {
             .collect();
 
         ObjMesh {
-            vertex_buffer,
+            vertex_buffer: Rc::new(vertex_buffer),
             no_material_index_buffer: index_buffer,
             submeshes: objs,
         }
@@ -112,7 +114,7 @@ pub struct ObjVert {
     pub normal_index: usize,
 }
 
-pub fn load_obj_file(path: &'static str) -> Result<ObjFile, Box<dyn Error>> {
+pub fn load_obj_file(path: &str) -> Result<ObjFile, Box<dyn Error>> {
     let file = fs::read(path)?;
 
     let mut material_file_names = vec![];
@@ -218,11 +220,20 @@ pub struct MtlMaterialDefinition {
     pub optical_density: f32,
     pub dissolve: f32,
     pub illumination: u8,
+    pub ambient_map: Option<String>,
+    pub diffuse_map: Option<String>,
+    pub specular_map: Option<String>,
 }
 
-pub fn load_mtl_file(path: &'static str) -> Result<MtlFile, Box<dyn Error>> {
+pub fn load_mtl_file(path: &str) -> Result<MtlFile, Box<dyn Error>> {
     let file = fs::read(path)?;
+    // Texture maps are relative to the mtl file
+    let directory = Path::new(path).parent().unwrap_or_else(|| Path::new(""));
+    return Ok(parse_mtl(&file, directory));
+}
 
+/// Parses the contents of an mtl file. Texture map paths are joined onto `directory`.
+pub fn parse_mtl(file: &[u8], directory: &Path) -> MtlFile {
     let mut materials: Vec<MtlMaterialDefinition> = vec![];
 
     let mut name: Option<String> = None;
@@ -232,8 +243,17 @@ pub fn load_mtl_file(path: &'static str) -> Result<MtlFile, Box<dyn Error>> {
     let mut emission_color = [0.; 3];
     let mut specular_highlights = 0.;
     let mut optical_density = 0.;
-    let mut dissolve = 0.;
+    let mut dissolve = 1.;
     let mut illumination = 0;
+    let mut ambient_map = None;
+    let mut diffuse_map = None;
+    let mut specular_map = None;
+
+    // The file name comes after any options
+    let map_path = |line: &[&str]| {
+        let file_name = line[line.len() - 1];
+        return Some(directory.join(file_name).to_string_lossy().into_owned());
+    };
 
     file.lines().for_each(|l| if let Ok(line) = l {
         if line.is_empty() || line.starts_with('#') {
@@ -255,13 +275,17 @@ pub fn load_mtl_file(path: &'static str) -> Result<MtlFile, Box<dyn Error>> {
                         optical_density,
                         dissolve,
                         illumination,
+                        ambient_map: ambient_map.take(),
+                        diffuse_map: diffuse_map.take(),
+                        specular_map: specular_map.take(),
                     });
                     ambient_color = [0.; 3];
                     diffuse_color = [0.; 3];
                     specular_color = [0.; 3];
+                    emission_color = [0.; 3];
                     specular_highlights = 0.;
                     optical_density = 0.;
-                    dissolve = 0.;
+                    dissolve = 1.;
                     illumination = 0;
                 }
                 name = Some(line[1].to_string());
@@ -306,6 +330,15 @@ pub fn load_mtl_file(path: &'static str) -> Result<MtlFile, Box<dyn Error>> {
             "illum" => {
                 illumination = line[1].parse::<u8>().unwrap();
             }
+            "map_Ka" => {
+                ambient_map = map_path(&line);
+            }
+            "map_Kd" => {
+                diffuse_map = map_path(&line);
+            }
+            "map_Ks" => {
+                specular_map = map_path(&line);
+            }
             _default => {}
         }
     });
@@ -320,6 +353,9 @@ pub fn load_mtl_file(path: &'static str) -> Result<MtlFile, Box<dyn Error>> {
             optical_density,
             dissolve,
             illumination,
+            ambient_map,
+            diffuse_map,
+            specular_map,
         });
     }
 
@@ -327,7 +363,23 @@ pub fn load_mtl_file(path: &'static str) -> Result<MtlFile, Box<dyn Error>> {
         .into_iter()
         .map(|item| (item.name.clone(), item))
         .collect();
-    Ok(MtlFile {
+    return MtlFile {
         materials: material_definitions,
-    })
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dissolve_defaults_to_opaque() {
+        let mtl = parse_mtl(
+            b"newmtl plain\nKd 1 1 1\nnewmtl glass\nd 0.5\nnewmtl stone\nKd 1 1 1\n",
+            Path::new(""),
+        );
+        assert_eq!(mtl.materials.get("plain").unwrap().dissolve, 1.);
+        assert_eq!(mtl.materials.get("glass").unwrap().dissolve, 0.5);
+        assert_eq!(mtl.materials.get("stone").unwrap().dissolve, 1.);
+    }
 }
@@ -1,4 +1,4 @@
-use crate::buffers::BufferLayout;
+use crate::buffers::{BufferLayout, StepRate};
 pub use crate::Renderer;
 use std::mem;
 use std::mem::size_of;
@@ -59,10 +59,15 @@ impl PlatformVertexBuffer {
         self.bind_to_offset(scene, self.offset);
     }
 
+    /// Per-instance buffers go to input slot 1, leaving the vertex buffer in slot 0 alone.
     pub fn bind_to_offset(&self, scene: &PlatformScene, offset: u32) {
+        let slot = match self.layout.step_rate {
+            StepRate::PerVertex => 0,
+            StepRate::PerInstance(_) => 1,
+        };
         unsafe {
             (*scene.device_context).IASetVertexBuffers(
-                0,
+                slot,
                 1,
                 &self.buffer,
                 &(self.layout.stride),
@@ -7,10 +7,11 @@ use winapi::shared::dxgiformat::*;
 use winapi::shared::dxgitype::*;
 use winapi::um::d3d11::*;
 use winapi::Interface;
-use winapi::_core::ptr::null_mut;
+use winapi::_core::ptr::{null, null_mut};
 use winapi::shared::winerror::FAILED;
 use winapi::um::d3dcommon::*;
 
+use crate::render_queue::DepthMode;
 use crate::TextureDesc;
 
 use self::buffers::PlatformIndexBuffer;
@@ -33,11 +34,78 @@ pub struct RenderContext {}
 
 impl RenderContext {}
 
+struct DepthStates {
+    disabled: *mut ID3D11DepthStencilState,
+    test: *mut ID3D11DepthStencilState,
+    test_and_write: *mut ID3D11DepthStencilState,
+}
+
+unsafe fn create_depth_state(
+    device: &ID3D11Device,
+    test: bool,
+    write: bool,
+) -> *mut ID3D11DepthStencilState {
+    let mut desc: D3D11_DEPTH_STENCIL_DESC = mem::zeroed();
+    desc.DepthEnable = test as i32;
+    desc.DepthWriteMask = if write {
+        D3D11_DEPTH_WRITE_MASK_ALL
+    } else {
+        D3D11_DEPTH_WRITE_MASK_ZERO
+    };
+    desc.DepthFunc = D3D11_COMPARISON_LESS_EQUAL;
+    let mut state: *mut ID3D11DepthStencilState = null_mut();
+    let result = device.CreateDepthStencilState(&desc, &mut state);
+    if FAILED(result) {
+        panic!("Failed to create depth stencil state {:x}", result);
+    }
+    return state;
+}
+
+/// A depth buffer the size of the swap chain's back buffers.
+unsafe fn create_depth_view(
+    device: &ID3D11Device,
+    swap_chain: &IDXGISwapChain,
+) -> *mut ID3D11DepthStencilView {
+    let mut swap_chain_desc: DXGI_SWAP_CHAIN_DESC = mem::zeroed();
+    swap_chain.GetDesc(&mut swap_chain_desc);
+    let texture_desc = D3D11_TEXTURE2D_DESC {
+        Width: swap_chain_desc.BufferDesc.Width,
+        Height: swap_chain_desc.BufferDesc.Height,
+        MipLevels: 1,
+        ArraySize: 1,
+        Format: DXGI_FORMAT_D32_FLOAT,
+        SampleDesc: DXGI_SAMPLE_DESC {
+            Count: 1,
+            Quality: 0,
+        },
+        Usage: D3D11_USAGE_DEFAULT,
+        BindFlags: D3D11_BIND_DEPTH_STENCIL,
+        CPUAccessFlags: 0,
+        MiscFlags: 0,
+    };
+    let mut texture: *mut ID3D11Texture2D = null_mut();
+    let result = device.CreateTexture2D(&texture_desc, null(), &mut texture);
+    if FAILED(result) {
+        panic!("Failed to create depth buffer {:x}", result);
+    }
+
+    let mut depth_view: *mut ID3D11DepthStencilView = null_mut();
+    let result =
+        device.CreateDepthStencilView(texture as *mut ID3D11Resource, null(), &mut depth_view);
+    (*texture).Release();
+    if FAILED(result) {
+        panic!("Failed to create depth stencil view {:x}", result);
+    }
+    return depth_view;
+}
+
 pub struct PlatformRenderer {
     swap_chain: Box<IDXGISwapChain>,
     device: *mut ID3D11Device,
     device_context: *mut ID3D11DeviceContext,
     render_target: *mut ID3D11RenderTargetView,
+    depth_view: *mut ID3D11DepthStencilView,
+    depth_states: DepthStates,
 }
 
 impl PlatformRenderer {
@@ -202,11 +270,20 @@ impl PlatformRenderer {
             // back_buffer = Box::from_raw(back_buffer_ptr);
             render_target = Box::from_raw(render_target_ptr);
 
+            let depth_view = create_depth_view(&device, &swap_chain);
+            let depth_states = DepthStates {
+                disabled: create_depth_state(&device, false, false),
+                test: create_depth_state(&device, true, false),
+                test_and_write: create_depth_state(&device, true, true),
+            };
+
             let res = PlatformRenderer {
                 swap_chain,
                 device: Box::into_raw(device),
                 device_context: Box::into_raw(device_context),
                 render_target: Box::into_raw(render_target),
+                depth_view,
+                depth_states,
             };
             return Ok(res);
         }
@@ -224,7 +301,13 @@ impl PlatformRenderer {
         unsafe {
             (*scene.device_context)
                 .ClearRenderTargetView(scene.render_target, &[0.43, 0.73, 0.36, 1.0]);
-            (*scene.device_context).OMSetRenderTargets(1, &self.render_target, null_mut());
+            (*scene.device_context).ClearDepthStencilView(
+                self.depth_view,
+                D3D11_CLEAR_DEPTH,
+                1.0,
+                0,
+            );
+            (*scene.device_context).OMSetRenderTargets(1, &self.render_target, self.depth_view);
             let viewport = D3D11_VIEWPORT {
                 TopLeftX: 0.0,
                 TopLeftY: 0.0,
@@ -235,6 +318,7 @@ impl PlatformRenderer {
             };
             (*scene.device_context).RSSetViewports(1, &viewport);
         }
+        scene.set_depth_mode(DepthMode::Disabled);
         return Some(scene);
     }
 }
@@ -256,6 +340,19 @@ impl PlatformScene<'_> {
             }
         }
     }
+
+    pub fn set_depth_mode(&self, mode: DepthMode) {
+        let depth_states = &self.renderer.depth_states;
+        let state = match mode {
+            DepthMode::Disabled => depth_states.disabled,
+            DepthMode::Test => depth_states.test,
+            DepthMode::TestAndWrite => depth_states.test_and_write,
+        };
+        unsafe {
+            (*self.device_context).OMSetDepthStencilState(state, 0);
+        }
+    }
+
     pub fn draw_indexed(&self, num_indices: u64, _index_buffer: &PlatformIndexBuffer) {
         unsafe {
             (*self.device_context).IASetPrimitiveTopology(D3D11_PRIMITIVE_TOPOLOGY_TRIANGLESTRIP);
@@ -269,6 +366,25 @@ impl PlatformScene<'_> {
             (*self.device_context).DrawIndexed(num_indices as u32, 0, 0);
         }
     }
+
+    pub fn draw_indexed_tris_instanced(
+        &self,
+        num_indices: u64,
+        num_instances: u64,
+        _index_buffer: &PlatformIndexBuffer,
+    ) {
+        unsafe {
+            (*self.device_context).IASetPrimitiveTopology(D3D11_PRIMITIVE_TOPOLOGY_TRIANGLELIST);
+            (*self.device_context).DrawIndexedInstanced(
+                num_indices as u32,
+                num_instances as u32,
+                0,
+                0,
+                0,
+            );
+        }
+    }
+
     pub fn draw_tri_strip(&self, num_verts: u64) {
         unsafe {
             (*self.device_context).IASetPrimitiveTopology(D3D11_PRIMITIVE_TOPOLOGY_TRIANGLESTRIP);
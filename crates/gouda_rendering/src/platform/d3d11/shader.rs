@@ -1,4 +1,4 @@
-use crate::buffers::{BufferLayout, ShaderDataType};
+use crate::buffers::{BufferLayout, ShaderDataType, StepRate};
 pub use crate::platform::d3d11::PlatformScene;
 use crate::Scene;
 use std::ffi::OsStr;
@@ -11,7 +11,7 @@ use winapi::shared::dxgiformat::{DXGI_FORMAT_R32G32_FLOAT, *};
 use winapi::shared::winerror::FAILED;
 use winapi::um::d3d11::{
     ID3D11InputLayout, ID3D11PixelShader, ID3D11VertexShader, D3D11_APPEND_ALIGNED_ELEMENT,
-    D3D11_INPUT_ELEMENT_DESC, D3D11_INPUT_PER_VERTEX_DATA,
+    D3D11_INPUT_ELEMENT_DESC, D3D11_INPUT_PER_INSTANCE_DATA, D3D11_INPUT_PER_VERTEX_DATA,
 };
 use winapi::um::d3dcommon::ID3DBlob;
 use winapi::um::d3dcompiler::{
@@ -45,10 +45,16 @@ impl PlatformShader {
     pub fn new(
         gfx: &PlatformRenderer,
         buffer_layout: BufferLayout,
+        instance_layout: Option<BufferLayout>,
         vertex_src: &str,
         fragment_src: &str,
     ) -> PlatformShader {
-        let vx = PlatformVertexShader::new(gfx, &buffer_layout, vertex_src);
+        let vx = PlatformVertexShader::new(
+            gfx,
+            &buffer_layout,
+            instance_layout.as_ref(),
+            vertex_src,
+        );
         let fx = PlatformFragmentShader::new(gfx, fragment_src);
 
         return PlatformShader {
@@ -73,6 +79,7 @@ impl PlatformVertexShader {
     pub fn new(
         renderer: &PlatformRenderer,
         layout: &BufferLayout,
+        instance_layout: Option<&BufferLayout>,
         vertex_src: &str,
     ) -> PlatformVertexShader {
         unsafe {
@@ -119,37 +126,40 @@ impl PlatformVertexShader {
                 panic!("Failed to create vertex shader {:x}", result);
             }
 
-            let mut first = true;
+            // Vertices come from input slot 0 and instance data from slot 1
             let mut names = vec![];
-            let input_desc: Vec<D3D11_INPUT_ELEMENT_DESC> = layout
-                .elements
-                .iter()
-                .map(|element| {
-                    let aligned_byte_offset: u32 = if first {
+            let mut input_desc: Vec<D3D11_INPUT_ELEMENT_DESC> = vec![];
+            let slots = once((0, layout)).chain(instance_layout.map(|layout| (1, layout)));
+            for (slot, slot_layout) in slots {
+                let (slot_class, step_rate) = match slot_layout.step_rate {
+                    StepRate::PerVertex => (D3D11_INPUT_PER_VERTEX_DATA, 0),
+                    StepRate::PerInstance(rate) => (D3D11_INPUT_PER_INSTANCE_DATA, rate),
+                };
+                for (index, element) in slot_layout.elements.iter().enumerate() {
+                    let aligned_byte_offset: u32 = if index == 0 {
                         0
                     } else {
                         D3D11_APPEND_ALIGNED_ELEMENT
                     };
-                    first = false;
 
                     let name = win32_string_short(element.name);
                     names.push(name);
-                    D3D11_INPUT_ELEMENT_DESC {
+                    input_desc.push(D3D11_INPUT_ELEMENT_DESC {
                         SemanticName: names.last().unwrap().as_ptr() as *const i8,
                         SemanticIndex: 0,
                         Format: element.data_type.to_d3d11(),
-                        InputSlot: 0,
+                        InputSlot: slot,
                         AlignedByteOffset: aligned_byte_offset,
-                        InputSlotClass: D3D11_INPUT_PER_VERTEX_DATA,
-                        InstanceDataStepRate: 0,
-                    }
-                })
-                .collect();
+                        InputSlotClass: slot_class,
+                        InstanceDataStepRate: step_rate,
+                    });
+                }
+            }
             let input_layout: Box<ID3D11InputLayout> = Box::new(mem::zeroed());
             let mut input_layout_ptr: *mut ID3D11InputLayout = Box::into_raw(input_layout);
             let result = (*renderer.device).CreateInputLayout(
                 input_desc.as_ptr(),
-                layout.elements.len() as u32,
+                input_desc.len() as u32,
                 vs_buffer.GetBufferPointer(),
                 vs_buffer.GetBufferSize(),
                 &mut input_layout_ptr,
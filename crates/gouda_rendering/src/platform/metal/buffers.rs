@@ -1,10 +1,14 @@
 use crate::{
-    buffers::{BufferLayout, ShaderDataType},
+    buffers::{BufferLayout, ShaderDataType, StepRate},
     platform::metal::{PlatformRenderer, PlatformScene},
 };
 use metal::*;
+use std::cell::Cell;
 use std::mem;
 
+/// Vertex buffer index for per-instance data, clear of the constant buffers which start at 1.
+pub const INSTANCE_BUFFER_INDEX: u64 = 30;
+
 impl ShaderDataType {
     pub fn to_metal(&self) -> MTLVertexFormat {
         match self {
@@ -42,18 +46,31 @@ fn update_buffer<T>(buffer: &Buffer, mut data: Vec<T>) {
 #[derive(Debug)]
 pub struct PlatformIndexBuffer {
     pub data: Buffer,
+    /// Metal takes the index buffer with each draw call instead of binding it, so the first
+    /// index set by `bind_with_offset` is kept here until then.
+    first_index: Cell<u32>,
 }
 
 impl PlatformIndexBuffer {
     pub fn new(renderer: &PlatformRenderer, indices: Vec<u16>) -> PlatformIndexBuffer {
         return PlatformIndexBuffer {
             data: create_buffer(renderer, indices),
+            first_index: Cell::new(0),
         };
     }
 
-    pub fn bind(&self, _scene: &PlatformScene) {}
+    pub fn bind(&self, scene: &PlatformScene) {
+        self.bind_with_offset(scene, 0);
+    }
+
+    pub fn bind_with_offset(&self, _scene: &PlatformScene, offset: u32) {
+        self.first_index.set(offset);
+    }
 
-    pub fn bind_with_offset(&self, _scene: &PlatformScene, offset: u32) {}
+    /// The byte offset of the bound first index, for passing to the draw call.
+    pub fn byte_offset(&self) -> u64 {
+        return self.first_index.get() as u64 * mem::size_of::<u16>() as u64;
+    }
 }
 
 #[derive(Debug)]
@@ -124,6 +141,7 @@ impl PlatformVertexConstantBuffer {
 pub struct PlatformVertexBuffer {
     data: Buffer,
     offset: u32,
+    step_rate: StepRate,
 }
 
 impl PlatformVertexBuffer {
@@ -136,6 +154,7 @@ impl PlatformVertexBuffer {
         return PlatformVertexBuffer {
             offset,
             data: create_buffer(renderer, position_data),
+            step_rate: layout.step_rate,
         };
     }
 
@@ -144,9 +163,11 @@ impl PlatformVertexBuffer {
     }
 
     pub fn bind(&self, scene: &PlatformScene) {
-        scene
-            .encoder
-            .set_vertex_buffer(self.offset as u64, Some(&self.data), 0);
+        let index = match self.step_rate {
+            StepRate::PerVertex => self.offset as u64,
+            StepRate::PerInstance(_) => INSTANCE_BUFFER_INDEX,
+        };
+        scene.encoder.set_vertex_buffer(index, Some(&self.data), 0);
     }
 
     pub fn update_data<T>(&self, _renderer: &PlatformRenderer, data: Vec<T>) {
@@ -8,6 +8,7 @@ use std::mem::size_of;
 use std::{f32, mem};
 
 use self::buffers::PlatformIndexBuffer;
+use crate::render_queue::DepthMode;
 
 pub mod buffers;
 pub mod drawable;
@@ -18,6 +19,7 @@ pub struct PlatformScene<'a> {
     encoder: &'a RenderCommandEncoderRef,
     command_buffer: &'a CommandBufferRef,
     drawable: &'a DrawableRef,
+    renderer: &'a PlatformRenderer,
 }
 
 impl PlatformScene<'_> {
@@ -28,13 +30,23 @@ impl PlatformScene<'_> {
         self.command_buffer.commit();
     }
 
+    pub fn set_depth_mode(&self, mode: DepthMode) {
+        let depth_states = &self.renderer.depth_states;
+        let state = match mode {
+            DepthMode::Disabled => &depth_states.disabled,
+            DepthMode::Test => &depth_states.test,
+            DepthMode::TestAndWrite => &depth_states.test_and_write,
+        };
+        self.encoder.set_depth_stencil_state(state);
+    }
+
     pub fn draw_indexed(&self, num_indices: u64, index_buffer: &PlatformIndexBuffer) {
         self.encoder.draw_indexed_primitives(
             MTLPrimitiveType::TriangleStrip,
             num_indices,
             MTLIndexType::UInt16,
             &index_buffer.data,
-            0,
+            index_buffer.byte_offset(),
         );
     }
 
@@ -44,7 +56,23 @@ impl PlatformScene<'_> {
             num_indices,
             MTLIndexType::UInt16,
             &index_buffer.data,
-            0,
+            index_buffer.byte_offset(),
+        );
+    }
+
+    pub fn draw_indexed_tris_instanced(
+        &self,
+        num_indices: u64,
+        num_instances: u64,
+        index_buffer: &PlatformIndexBuffer,
+    ) {
+        self.encoder.draw_indexed_primitives_instanced(
+            MTLPrimitiveType::Triangle,
+            num_indices,
+            MTLIndexType::UInt16,
+            &index_buffer.data,
+            index_buffer.byte_offset(),
+            num_instances,
         );
     }
 
@@ -59,19 +87,71 @@ impl PlatformScene<'_> {
     }
 }
 
-fn prepare_render_pass_descriptor(descriptor: &RenderPassDescriptorRef, texture: &TextureRef) {
+/// Every shader's pipeline is built for this depth attachment format.
+pub const DEPTH_FORMAT: MTLPixelFormat = MTLPixelFormat::Depth32Float;
+
+fn prepare_render_pass_descriptor(
+    descriptor: &RenderPassDescriptorRef,
+    texture: &TextureRef,
+    depth_texture: &TextureRef,
+) {
     let color_attachment = descriptor.color_attachments().object_at(0).unwrap();
 
     color_attachment.set_texture(Some(texture));
     color_attachment.set_load_action(MTLLoadAction::Clear);
     color_attachment.set_clear_color(MTLClearColor::new(0.43, 0.73, 0.36, 1.0));
     color_attachment.set_store_action(MTLStoreAction::Store);
+
+    let depth_attachment = descriptor.depth_attachment().unwrap();
+    depth_attachment.set_texture(Some(depth_texture));
+    depth_attachment.set_load_action(MTLLoadAction::Clear);
+    depth_attachment.set_clear_depth(1.0);
+    depth_attachment.set_store_action(MTLStoreAction::DontCare);
+}
+
+fn new_depth_texture(device: &DeviceRef, width: usize, height: usize) -> Texture {
+    let descriptor = TextureDescriptor::new();
+    descriptor.set_pixel_format(DEPTH_FORMAT);
+    descriptor.set_width(width as u64);
+    descriptor.set_height(height as u64);
+    descriptor.set_storage_mode(MTLStorageMode::Private);
+    descriptor.set_usage(MTLTextureUsage::RenderTarget);
+    return device.new_texture(&descriptor);
+}
+
+fn new_depth_state(
+    device: &DeviceRef,
+    compare: MTLCompareFunction,
+    write: bool,
+) -> DepthStencilState {
+    let descriptor = DepthStencilDescriptor::new();
+    descriptor.set_depth_compare_function(compare);
+    descriptor.set_depth_write_enabled(write);
+    return device.new_depth_stencil_state(&descriptor);
+}
+
+struct DepthStates {
+    disabled: DepthStencilState,
+    test: DepthStencilState,
+    test_and_write: DepthStencilState,
+}
+
+impl DepthStates {
+    fn new(device: &DeviceRef) -> DepthStates {
+        return DepthStates {
+            disabled: new_depth_state(device, MTLCompareFunction::Always, false),
+            test: new_depth_state(device, MTLCompareFunction::LessEqual, false),
+            test_and_write: new_depth_state(device, MTLCompareFunction::LessEqual, true),
+        };
+    }
 }
 
 pub struct PlatformRenderer {
     device: Device,
     layer: CoreAnimationLayer,
     command_queue: CommandQueue,
+    depth_texture: Texture,
+    depth_states: DepthStates,
     width: usize,
     height: usize,
 }
@@ -99,11 +179,15 @@ impl PlatformRenderer {
         layer.set_drawable_size(CGSize::new(width as f64, height as f64));
 
         let command_queue = device.new_command_queue();
+        let depth_texture = new_depth_texture(&device, width, height);
+        let depth_states = DepthStates::new(&device);
 
         let mut res = PlatformRenderer {
             device,
             layer,
             command_queue,
+            depth_texture,
+            depth_states,
             width,
             height,
         };
@@ -116,6 +200,7 @@ impl PlatformRenderer {
         println!("Resizing {} {}", width, height);
         self.layer
             .set_drawable_size(CGSize::new(width as f64, height as f64));
+        self.depth_texture = new_depth_texture(&self.device, self.width, self.height);
     }
 
     pub fn get_width(&self) -> usize {
@@ -129,7 +214,11 @@ impl PlatformRenderer {
     pub fn begin_scene(&self) -> Option<PlatformScene> {
         if let Some(drawable) = self.layer.next_drawable() {
             let render_pass_descriptor = RenderPassDescriptor::new();
-            prepare_render_pass_descriptor(&render_pass_descriptor, drawable.texture());
+            prepare_render_pass_descriptor(
+                &render_pass_descriptor,
+                drawable.texture(),
+                &self.depth_texture,
+            );
 
             let command_buffer = self.command_queue.new_command_buffer();
 
@@ -138,7 +227,9 @@ impl PlatformRenderer {
                 encoder,
                 command_buffer,
                 drawable,
+                renderer: self,
             };
+            scene.set_depth_mode(DepthMode::Disabled);
             return Some(scene);
         } else {
             return None;
@@ -1,7 +1,11 @@
-use crate::{buffers::BufferLayout, Scene};
+use crate::{
+    buffers::{BufferLayout, StepRate},
+    Scene,
+};
 use metal::*;
 
-use super::PlatformRenderer;
+use super::buffers::INSTANCE_BUFFER_INDEX;
+use super::{PlatformRenderer, DEPTH_FORMAT};
 
 #[derive(Debug)]
 pub struct PlatformShader {
@@ -12,6 +16,7 @@ impl PlatformShader {
     pub fn new(
         gfx: &PlatformRenderer,
         buffer_layout: BufferLayout,
+        instance_layout: Option<BufferLayout>,
         vertex_src: &str,
         fragment_src: &str,
     ) -> PlatformShader {
@@ -50,6 +55,31 @@ impl PlatformShader {
             .unwrap()
             .set_stride(buffer_layout.stride as u64);
 
+        if let Some(instance_layout) = &instance_layout {
+            let first = buffer_layout.elements.len();
+            for (index, attribute) in instance_layout.elements.iter().enumerate() {
+                let vertex_attribute_descriptor = &VertexAttributeDescriptor::new();
+                vertex_attribute_descriptor.set_format(attribute.data_type.to_metal());
+                vertex_attribute_descriptor.set_buffer_index(INSTANCE_BUFFER_INDEX);
+                vertex_attribute_descriptor.set_offset(attribute.offset as u64);
+                vertex_descriptor
+                    .attributes()
+                    .set_object_at(first + index, Some(vertex_attribute_descriptor));
+            }
+
+            let step_rate = match instance_layout.step_rate {
+                StepRate::PerVertex => 1,
+                StepRate::PerInstance(rate) => rate as u64,
+            };
+            let layout = vertex_descriptor
+                .layouts()
+                .object_at(INSTANCE_BUFFER_INDEX)
+                .unwrap();
+            layout.set_stride(instance_layout.stride as u64);
+            layout.set_step_function(MTLVertexStepFunction::PerInstance);
+            layout.set_step_rate(step_rate);
+        }
+
         pipeline_state_descriptor.set_vertex_descriptor(Some(&vertex_descriptor));
 
         let render_buffer_attachment = pipeline_state_descriptor
@@ -68,6 +98,8 @@ impl PlatformShader {
         render_buffer_attachment
             .set_destination_alpha_blend_factor(MTLBlendFactor::OneMinusSourceAlpha);
 
+        pipeline_state_descriptor.set_depth_attachment_pixel_format(DEPTH_FORMAT);
+
         let pipeline_state = gfx
             .device
             .new_render_pipeline_state(&pipeline_state_descriptor)
@@ -6,3 +6,6 @@ pub mod d3d11;
 
 #[cfg(all(target_os = "windows", feature = "use_d3d12"))]
 pub mod d3d12;
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub mod software;
@@ -0,0 +1,140 @@
+use crate::buffers::{BufferLayout, StepRate};
+use std::cell::RefCell;
+use std::mem::size_of_val;
+use std::rc::Rc;
+use std::slice;
+
+use super::{PlatformRenderer, PlatformScene};
+
+pub type SharedBytes = Rc<RefCell<Vec<u8>>>;
+
+fn to_bytes<T>(data: &[T]) -> Vec<u8> {
+    unsafe { slice::from_raw_parts(data.as_ptr() as *const u8, size_of_val(data)) }.to_vec()
+}
+
+#[derive(Debug)]
+pub struct PlatformIndexBuffer {
+    pub(super) indices: Rc<Vec<u16>>,
+}
+
+impl PlatformIndexBuffer {
+    pub fn new(_renderer: &PlatformRenderer, indices: Vec<u16>) -> PlatformIndexBuffer {
+        return PlatformIndexBuffer {
+            indices: Rc::new(indices),
+        };
+    }
+
+    pub fn bind(&self, scene: &PlatformScene) {
+        self.bind_with_offset(scene, 0);
+    }
+
+    pub fn bind_with_offset(&self, scene: &PlatformScene, offset: u32) {
+        scene.state.borrow_mut().index_buffer = Some((self.indices.clone(), offset));
+    }
+}
+
+#[derive(Debug)]
+pub struct PlatformFragmentConstantBuffer {
+    offset: u64,
+    data: SharedBytes,
+}
+
+impl PlatformFragmentConstantBuffer {
+    pub fn new<T>(
+        _renderer: &PlatformRenderer,
+        offset: u64,
+        data: Vec<T>,
+    ) -> PlatformFragmentConstantBuffer {
+        return PlatformFragmentConstantBuffer {
+            offset,
+            data: Rc::new(RefCell::new(to_bytes(&data))),
+        };
+    }
+
+    pub fn bind(&self, scene: &PlatformScene) {
+        scene
+            .state
+            .borrow_mut()
+            .fragment_constants
+            .insert(self.offset as u32, self.data.clone());
+    }
+
+    pub fn update_data<T>(&mut self, _renderer: &PlatformRenderer, data: Vec<T>) {
+        *self.data.borrow_mut() = to_bytes(&data);
+    }
+}
+
+#[derive(Debug)]
+pub struct PlatformVertexConstantBuffer {
+    offset: u32,
+    data: SharedBytes,
+}
+
+impl PlatformVertexConstantBuffer {
+    pub fn new<T>(
+        _renderer: &PlatformRenderer,
+        offset: u32,
+        data: Vec<T>,
+    ) -> PlatformVertexConstantBuffer {
+        return PlatformVertexConstantBuffer {
+            offset,
+            data: Rc::new(RefCell::new(to_bytes(&data))),
+        };
+    }
+
+    pub fn update_data<T>(&self, _renderer: &PlatformRenderer, data: Vec<T>) {
+        *self.data.borrow_mut() = to_bytes(&data);
+    }
+
+    pub fn bind_to_offset(&self, scene: &PlatformScene, offset: u32) {
+        scene
+            .state
+            .borrow_mut()
+            .vertex_constants
+            .insert(offset, self.data.clone());
+    }
+
+    pub fn bind(&self, scene: &PlatformScene) {
+        self.bind_to_offset(scene, self.offset);
+    }
+}
+
+#[derive(Debug)]
+pub struct PlatformVertexBuffer {
+    data: SharedBytes,
+    offset: u32,
+    step_rate: StepRate,
+}
+
+impl PlatformVertexBuffer {
+    pub fn new<T>(
+        _renderer: &PlatformRenderer,
+        layout: BufferLayout,
+        offset: u32,
+        data: Vec<T>,
+    ) -> PlatformVertexBuffer {
+        return PlatformVertexBuffer {
+            data: Rc::new(RefCell::new(to_bytes(&data))),
+            offset,
+            step_rate: layout.step_rate,
+        };
+    }
+
+    /// Per-instance buffers go to the instance slot, leaving the bound vertex buffer alone.
+    pub fn bind(&self, scene: &PlatformScene) {
+        match self.step_rate {
+            StepRate::PerVertex => self.bind_to_offset(scene, self.offset),
+            StepRate::PerInstance(rate) => {
+                scene.state.borrow_mut().instance_buffer = Some((self.data.clone(), rate.max(1)));
+            }
+        }
+    }
+
+    pub fn bind_to_offset(&self, scene: &PlatformScene, offset: u32) {
+        scene.state.borrow_mut().vertex_buffer = Some((self.data.clone(), offset));
+    }
+
+    pub fn update_data<T>(&self, _renderer: &PlatformRenderer, data: Vec<T>) {
+        *self.data.borrow_mut() = to_bytes(&data);
+    }
+}
@@ -0,0 +1,539 @@
+#![cfg(not(any(target_os = "macos", target_os = "windows")))]
+
+use cgmath::Vector4;
+use gouda_images::Image;
+use gouda_window::{FramePresenter, GameWindowImpl, PlatformWindow};
+use std::cell::{Ref, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use self::buffers::{PlatformIndexBuffer, SharedBytes};
+use self::shader::{
+    FragmentInput, PlatformFragmentShader, PlatformVertexShader, Uniforms, VertexInput,
+    VertexOutput, MAX_VARYINGS,
+};
+use self::texture::TextureData;
+use crate::render_queue::DepthMode;
+
+pub mod buffers;
+pub mod shader;
+pub mod texture;
+
+pub const CLEAR_COLOR: [f32; 4] = [0.43, 0.73, 0.36, 1.0];
+
+/// An RGBA8 render target with a depth buffer. Pixel (0, 0) is the top left corner.
+#[derive(Debug, Clone)]
+pub struct Framebuffer {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+    depth: Vec<f32>,
+}
+
+fn to_unorm(value: f32) -> u8 {
+    return (value.clamp(0., 1.) * 255.).round() as u8;
+}
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize) -> Framebuffer {
+        return Framebuffer {
+            width,
+            height,
+            pixels: vec![0; width * height * 4],
+            depth: vec![1.; width * height],
+        };
+    }
+
+    /// Fills the color buffer with `color` and resets the depth buffer to the far plane.
+    pub fn clear(&mut self, color: [f32; 4]) {
+        let color = [
+            to_unorm(color[0]),
+            to_unorm(color[1]),
+            to_unorm(color[2]),
+            to_unorm(color[3]),
+        ];
+        self.pixels
+            .chunks_exact_mut(4)
+            .for_each(|pixel| pixel.copy_from_slice(&color));
+        self.depth.iter_mut().for_each(|depth| *depth = 1.);
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 4] {
+        let i = (y * self.width + x) * 4;
+        return [
+            self.pixels[i],
+            self.pixels[i + 1],
+            self.pixels[i + 2],
+            self.pixels[i + 3],
+        ];
+    }
+
+    /// Whether a fragment at depth `z` is drawn, storing its depth if the mode writes it.
+    fn test_depth(&mut self, x: usize, y: usize, z: f32, mode: DepthMode) -> bool {
+        let depth = &mut self.depth[y * self.width + x];
+        let passes = mode == DepthMode::Disabled || z <= *depth;
+        if passes && mode == DepthMode::TestAndWrite {
+            *depth = z;
+        }
+        return passes;
+    }
+
+    /// Blends with SrcAlpha / OneMinusSrcAlpha, matching the GPU backends.
+    fn blend(&mut self, x: usize, y: usize, color: [f32; 4]) {
+        let i = (y * self.width + x) * 4;
+        let alpha = color[3].clamp(0., 1.);
+        for (dst, src) in self.pixels[i..i + 4].iter_mut().zip(color.iter()) {
+            let src = src.clamp(0., 1.);
+            *dst = to_unorm(src * alpha + *dst as f32 / 255. * (1. - alpha));
+        }
+    }
+}
+
+pub struct PlatformRenderer {
+    framebuffer: RefCell<Framebuffer>,
+    presenter: RefCell<Option<Box<dyn FramePresenter>>>,
+}
+
+impl PlatformRenderer {
+    pub fn new(window: &mut PlatformWindow) -> Result<Self, String> {
+        return Ok(Self::with_size(window.get_width(), window.get_height()));
+    }
+
+    pub fn with_size(width: usize, height: usize) -> Self {
+        return PlatformRenderer {
+            framebuffer: RefCell::new(Framebuffer::new(width, height)),
+            presenter: RefCell::new(None),
+        };
+    }
+
+    /// Finished frames are handed to the presenter at the end of each scene.
+    pub fn attach_presenter(&self, presenter: Box<dyn FramePresenter>) {
+        *self.presenter.borrow_mut() = Some(presenter);
+    }
+
+    pub fn resize(&mut self, width: f32, height: f32) {
+        *self.framebuffer.get_mut() = Framebuffer::new(width as usize, height as usize);
+    }
+
+    pub fn get_width(&self) -> usize {
+        return self.framebuffer.borrow().width;
+    }
+
+    pub fn get_height(&self) -> usize {
+        return self.framebuffer.borrow().height;
+    }
+
+    /// The most recently rendered frame.
+    pub fn framebuffer(&self) -> Ref<'_, Framebuffer> {
+        return self.framebuffer.borrow();
+    }
+
+    pub fn capture_frame(&self) -> Image {
+        let framebuffer = self.framebuffer.borrow();
+        return Image {
+            width: framebuffer.width,
+            height: framebuffer.height,
+            data: Image::data_from_raw_pixels(
+                framebuffer.width,
+                framebuffer.height,
+                framebuffer.pixels.clone(),
+            ),
+        };
+    }
+
+    pub fn begin_scene(&self) -> Option<PlatformScene<'_>> {
+        self.framebuffer.borrow_mut().clear(CLEAR_COLOR);
+        return Some(PlatformScene {
+            renderer: self,
+            state: RefCell::new(PipelineState::default()),
+        });
+    }
+
+    pub fn end_scene(&self, scene: PlatformScene) {
+        scene.end();
+    }
+}
+
+#[derive(Default)]
+struct PipelineState {
+    vertex_shader: Option<PlatformVertexShader>,
+    fragment_shader: Option<PlatformFragmentShader>,
+    vertex_buffer: Option<(SharedBytes, u32)>,
+    instance_buffer: Option<(SharedBytes, u32)>,
+    index_buffer: Option<(Rc<Vec<u16>>, u32)>,
+    vertex_constants: HashMap<u32, SharedBytes>,
+    fragment_constants: HashMap<u32, SharedBytes>,
+    texture: Option<Rc<TextureData>>,
+    depth_mode: DepthMode,
+}
+
+enum Topology {
+    TriangleList,
+    TriangleStrip,
+}
+
+pub struct PlatformScene<'a> {
+    renderer: &'a PlatformRenderer,
+    state: RefCell<PipelineState>,
+}
+
+impl PlatformScene<'_> {
+    pub fn end(self) {
+        if let Some(presenter) = self.renderer.presenter.borrow().as_ref() {
+            let framebuffer = self.renderer.framebuffer.borrow();
+            presenter.present(framebuffer.width, framebuffer.height, &framebuffer.pixels);
+        }
+    }
+
+    pub fn set_depth_mode(&self, mode: DepthMode) {
+        self.state.borrow_mut().depth_mode = mode;
+    }
+
+    pub fn draw_indexed(&self, num_indices: u64, index_buffer: &PlatformIndexBuffer) {
+        let indices = self.resolve_indices(num_indices, index_buffer);
+        self.draw(Topology::TriangleStrip, &indices, 1);
+    }
+
+    pub fn draw_indexed_tris(&self, num_indices: u64, index_buffer: &PlatformIndexBuffer) {
+        let indices = self.resolve_indices(num_indices, index_buffer);
+        self.draw(Topology::TriangleList, &indices, 1);
+    }
+
+    pub fn draw_indexed_tris_instanced(
+        &self,
+        num_indices: u64,
+        num_instances: u64,
+        index_buffer: &PlatformIndexBuffer,
+    ) {
+        let indices = self.resolve_indices(num_indices, index_buffer);
+        self.draw(Topology::TriangleList, &indices, num_instances as usize);
+    }
+
+    pub fn draw_tri_strip(&self, num_verts: u64) {
+        let indices: Vec<usize> = (0..num_verts as usize).collect();
+        self.draw(Topology::TriangleStrip, &indices, 1);
+    }
+
+    pub fn draw_triangles(&self, num_verts: u64) {
+        let indices: Vec<usize> = (0..num_verts as usize).collect();
+        self.draw(Topology::TriangleList, &indices, 1);
+    }
+
+    /// Indexed draws read from the buffer passed in, like the GPU backends. The offset from
+    /// `bind_with_offset` only applies if that same buffer is the one bound.
+    fn resolve_indices(&self, num_indices: u64, index_buffer: &PlatformIndexBuffer) -> Vec<usize> {
+        let state = self.state.borrow();
+        let offset = match &state.index_buffer {
+            Some((bound, offset)) if Rc::ptr_eq(bound, &index_buffer.indices) => *offset as usize,
+            _ => 0,
+        };
+        return index_buffer
+            .indices
+            .iter()
+            .skip(offset)
+            .take(num_indices as usize)
+            .map(|i| *i as usize)
+            .collect();
+    }
+
+    /// Shaders with an instance layout read it from the bound instance buffer, one element per
+    /// `step_rate` instances.
+    fn draw(&self, topology: Topology, indices: &[usize], instances: usize) {
+        let state = self.state.borrow();
+        let (vertex_shader, fragment_shader) = match (&state.vertex_shader, &state.fragment_shader)
+        {
+            (Some(vs), Some(fs)) => (vs, fs),
+            _ => return,
+        };
+        let vertex_uniforms = Uniforms::from_bound(&state.vertex_constants);
+        let fragment_uniforms = Uniforms::from_bound(&state.fragment_constants);
+
+        let empty = vec![];
+        let vertex_bytes = state.vertex_buffer.as_ref().map(|(data, _)| data.borrow());
+        let vertex_bytes: &[u8] = vertex_bytes.as_deref().unwrap_or(&empty);
+        let vertex_offset = state.vertex_buffer.as_ref().map_or(0, |(_, o)| *o as usize);
+
+        let instance_bytes = state.instance_buffer.as_ref().map(|(data, _)| data.borrow());
+        let instance_bytes: &[u8] = instance_bytes.as_deref().unwrap_or(&empty);
+        let step_rate = state.instance_buffer.as_ref().map_or(1, |(_, r)| *r as usize);
+
+        let triangles: Vec<[usize; 3]> = match topology {
+            Topology::TriangleList => (0..indices.len() / 3)
+                .map(|t| [t * 3, t * 3 + 1, t * 3 + 2])
+                .collect(),
+            Topology::TriangleStrip => (0..indices.len().saturating_sub(2))
+                .map(|t| {
+                    if t % 2 == 0 {
+                        [t, t + 1, t + 2]
+                    } else {
+                        [t + 1, t, t + 2]
+                    }
+                })
+                .collect(),
+        };
+
+        let layout = &vertex_shader.layout;
+        let mut framebuffer = self.renderer.framebuffer.borrow_mut();
+        let texture = state.texture.as_deref();
+        for instance in 0..instances {
+            let instance_attributes = match &vertex_shader.instance_layout {
+                Some(instance_layout) => instance_layout.fetch(
+                    instance_bytes,
+                    instance / step_rate * instance_layout.stride,
+                ),
+                None => vec![],
+            };
+            let vertices: Vec<VertexOutput> = indices
+                .iter()
+                .map(|index| {
+                    let mut attributes =
+                        layout.fetch(vertex_bytes, vertex_offset + index * layout.stride);
+                    attributes.extend_from_slice(&instance_attributes);
+                    (vertex_shader.program)(&VertexInput {
+                        attributes: &attributes,
+                        uniforms: &vertex_uniforms,
+                    })
+                })
+                .collect();
+
+            for [a, b, c] in &triangles {
+                rasterize(
+                    &mut framebuffer,
+                    [&vertices[*a], &vertices[*b], &vertices[*c]],
+                    state.depth_mode,
+                    |varyings| {
+                        (fragment_shader.program)(&FragmentInput {
+                            varyings,
+                            uniforms: &fragment_uniforms,
+                            texture,
+                        })
+                    },
+                );
+            }
+        }
+    }
+}
+
+struct ScreenVertex {
+    x: f32,
+    y: f32,
+    z: f32,
+    inv_w: f32,
+}
+
+fn to_screen(position: Vector4<f32>, width: usize, height: usize) -> ScreenVertex {
+    let inv_w = 1. / position.w;
+    return ScreenVertex {
+        x: (position.x * inv_w * 0.5 + 0.5) * width as f32,
+        y: (0.5 - position.y * inv_w * 0.5) * height as f32,
+        z: position.z * inv_w,
+        inv_w,
+    };
+}
+
+fn edge(a: &ScreenVertex, b: &ScreenVertex, x: f32, y: f32) -> f32 {
+    return (b.x - a.x) * (y - a.y) - (b.y - a.y) * (x - a.x);
+}
+
+/// Top-left fill rule, so pixels on an edge shared by two triangles are only drawn once.
+fn owns_edge(a: &ScreenVertex, b: &ScreenVertex) -> bool {
+    let dx = b.x - a.x;
+    let dy = b.y - a.y;
+    return dy > 0. || (dy == 0. && dx < 0.);
+}
+
+/// Draws a single triangle with perspective-correct varyings. There is no culling; fragments
+/// outside the [0, 1] depth range are clipped and the rest are depth tested by `depth_mode`.
+fn rasterize<F>(
+    framebuffer: &mut Framebuffer,
+    vertices: [&VertexOutput; 3],
+    depth_mode: DepthMode,
+    fragment: F,
+) where
+    F: Fn([f32; MAX_VARYINGS]) -> [f32; 4],
+{
+    if vertices.iter().any(|v| v.position.w <= f32::EPSILON) {
+        return;
+    }
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    let mut verts = vertices;
+    let mut screen: Vec<ScreenVertex> = verts
+        .iter()
+        .map(|v| to_screen(v.position, width, height))
+        .collect();
+
+    let mut area = edge(&screen[0], &screen[1], screen[2].x, screen[2].y);
+    if area == 0. || !area.is_finite() {
+        return;
+    }
+    if area < 0. {
+        screen.swap(1, 2);
+        verts.swap(1, 2);
+        area = -area;
+    }
+
+    let min_x = screen
+        .iter()
+        .map(|v| v.x)
+        .fold(f32::MAX, f32::min)
+        .floor()
+        .max(0.) as usize;
+    let min_y = screen
+        .iter()
+        .map(|v| v.y)
+        .fold(f32::MAX, f32::min)
+        .floor()
+        .max(0.) as usize;
+    let max_x = (screen.iter().map(|v| v.x).fold(f32::MIN, f32::max).ceil() as usize).min(width);
+    let max_y = (screen.iter().map(|v| v.y).fold(f32::MIN, f32::max).ceil() as usize).min(height);
+
+    let edges = [(1, 2), (2, 0), (0, 1)];
+    let owned: Vec<bool> = edges
+        .iter()
+        .map(|(a, b)| owns_edge(&screen[*a], &screen[*b]))
+        .collect();
+
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+            let mut weights = [0.; 3];
+            let mut inside = true;
+            for (i, (a, b)) in edges.iter().enumerate() {
+                let w = edge(&screen[*a], &screen[*b], px, py);
+                if w < 0. || (w == 0. && !owned[i]) {
+                    inside = false;
+                    break;
+                }
+                weights[i] = w / area;
+            }
+            if !inside {
+                continue;
+            }
+
+            let z: f32 = (0..3).map(|i| weights[i] * screen[i].z).sum();
+            if !(0. ..=1.).contains(&z) || !framebuffer.test_depth(x, y, z, depth_mode) {
+                continue;
+            }
+
+            let inv_w: f32 = (0..3).map(|i| weights[i] * screen[i].inv_w).sum();
+            let mut varyings = [0.; MAX_VARYINGS];
+            for (v, varying) in varyings.iter_mut().enumerate() {
+                let sum: f32 = (0..3)
+                    .map(|i| weights[i] * screen[i].inv_w * verts[i].varyings[v])
+                    .sum();
+                *varying = sum / inv_w;
+            }
+
+            framebuffer.blend(x, y, fragment(varyings));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::buffers::{IndexBuffer, VertexBuffer};
+    use crate::shader_lib::quad_shader::quad_shader_layout;
+    use crate::shaders::ShaderUniform;
+    use crate::texture_library::TextureHandle;
+    use crate::Renderer;
+    use cgmath::{Matrix4, SquareMatrix};
+    use gouda_images::Image;
+
+    #[test]
+    fn test_begin_scene_clears_to_clear_color() {
+        let renderer = Renderer::new_headless(16, 16);
+        let scene = renderer.begin_scene().unwrap();
+        renderer.end_scene(scene);
+
+        let framebuffer = renderer.platform_renderer.framebuffer();
+        assert_eq!(framebuffer.pixel(0, 0), [110, 186, 92, 255]);
+        assert_eq!(framebuffer.pixel(15, 15), [110, 186, 92, 255]);
+    }
+
+    #[test]
+    fn test_quad_covers_expected_pixels() {
+        let renderer = Renderer::new_headless(16, 16);
+        let scene = renderer.begin_scene().unwrap();
+        // The unit square scaled by 1 covers the middle half of clip space.
+        scene.submit_shape_by_name("quad", "square", Matrix4::identity(), [1., 0., 0., 1.]);
+        renderer.end_scene(scene);
+
+        let framebuffer = renderer.platform_renderer.framebuffer();
+        assert_eq!(framebuffer.pixel(8, 8), [255, 0, 0, 255]);
+        assert_eq!(framebuffer.pixel(4, 4), [255, 0, 0, 255]);
+        assert_eq!(framebuffer.pixel(11, 11), [255, 0, 0, 255]);
+        assert_eq!(framebuffer.pixel(3, 3), [110, 186, 92, 255]);
+        assert_eq!(framebuffer.pixel(12, 12), [110, 186, 92, 255]);
+    }
+
+    #[test]
+    fn test_shared_edges_are_not_blended_twice() {
+        let renderer = Renderer::new_headless(16, 16);
+        let scene = renderer.begin_scene().unwrap();
+        scene.submit_shape_by_name("quad", "square", Matrix4::identity(), [0., 0., 0., 0.5]);
+        renderer.end_scene(scene);
+
+        let framebuffer = renderer.platform_renderer.framebuffer();
+        let expected = framebuffer.pixel(5, 9);
+        for i in 4..12 {
+            assert_eq!(framebuffer.pixel(i, i), expected);
+            assert_eq!(framebuffer.pixel(i, 15 - i), expected);
+        }
+    }
+
+    #[test]
+    fn test_texture_is_sampled_top_down() {
+        let renderer = Renderer::new_headless(16, 16);
+        let raw = vec![
+            255, 0, 0, 255, 255, 0, 0, 255, 0, 0, 255, 255, 0, 0, 255, 255,
+        ];
+        let image = Image {
+            width: 1,
+            height: 4,
+            data: Image::data_from_raw_pixels(1, 4, raw),
+        };
+        let texture = TextureHandle::new(crate::Texture::new(&renderer, &image));
+
+        let scene = renderer.begin_scene().unwrap();
+        scene.submit_gui_texture(&texture, Matrix4::from_scale(2.));
+        renderer.end_scene(scene);
+
+        let framebuffer = renderer.platform_renderer.framebuffer();
+        assert_eq!(framebuffer.pixel(8, 2), [255, 0, 0, 255]);
+        assert_eq!(framebuffer.pixel(8, 13), [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn test_indexed_draws_use_the_buffer_passed_in() {
+        let renderer = Renderer::new_headless(16, 16);
+        let vertices: Vec<[f32; 3]> = vec![
+            [-1., -1., 0.],
+            [0., -1., 0.],
+            [0., 1., 0.],
+            [-1., 1., 0.],
+            [1., -1., 0.],
+            [1., 1., 0.],
+        ];
+        let vertex_buffer = VertexBuffer::new(&renderer, quad_shader_layout(), 0, vertices);
+        let left = IndexBuffer::new(&renderer, vec![0, 1, 2, 0, 2, 3]);
+        let right = IndexBuffer::new(&renderer, vec![1, 4, 5, 1, 5, 2]);
+
+        let scene = renderer.begin_scene().unwrap();
+        scene.bind_shader_with_uniforms(
+            "quad",
+            vec![
+                ShaderUniform::Mat4(Matrix4::identity()),
+                ShaderUniform::Mat4(Matrix4::identity()),
+            ],
+            vec![ShaderUniform::Float4([1., 0., 0., 1.])],
+        );
+        vertex_buffer.bind(&scene);
+        left.bind_with_offset(&scene, 3);
+        scene.draw_indexed_tris(6, &right);
+        renderer.end_scene(scene);
+
+        let framebuffer = renderer.platform_renderer.framebuffer();
+        assert_eq!(framebuffer.pixel(4, 8), [110, 186, 92, 255]);
+        assert_eq!(framebuffer.pixel(12, 8), [255, 0, 0, 255]);
+    }
+}
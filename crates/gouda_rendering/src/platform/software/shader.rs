@@ -0,0 +1,514 @@
+use crate::buffers::{BufferLayout, ShaderDataType};
+use crate::Scene;
+use cgmath::{InnerSpace, Matrix4, Vector3, Vector4};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::buffers::SharedBytes;
+use super::texture::TextureData;
+use super::{PlatformRenderer, PlatformScene};
+
+pub const MAX_VARYINGS: usize = 8;
+
+pub type VertexProgram = fn(&VertexInput) -> VertexOutput;
+pub type FragmentProgram = fn(&FragmentInput) -> [f32; 4];
+
+/// Snapshot of the constant buffers bound to one shader stage, read as f32 slots.
+#[derive(Debug, Default)]
+pub struct Uniforms {
+    slots: HashMap<u32, Vec<f32>>,
+}
+
+impl Uniforms {
+    pub fn from_bound(bound: &HashMap<u32, SharedBytes>) -> Uniforms {
+        let slots = bound
+            .iter()
+            .map(|(slot, bytes)| {
+                let floats = bytes
+                    .borrow()
+                    .chunks_exact(4)
+                    .map(|c| f32::from_ne_bytes([c[0], c[1], c[2], c[3]]))
+                    .collect();
+                (*slot, floats)
+            })
+            .collect();
+        return Uniforms { slots };
+    }
+
+    fn component(&self, slot: u32, index: usize) -> f32 {
+        return self
+            .slots
+            .get(&slot)
+            .and_then(|v| v.get(index))
+            .copied()
+            .unwrap_or(0.);
+    }
+
+    pub fn float(&self, slot: u32) -> f32 {
+        return self.component(slot, 0);
+    }
+
+    pub fn float2(&self, slot: u32) -> [f32; 2] {
+        return [self.component(slot, 0), self.component(slot, 1)];
+    }
+
+    pub fn float3(&self, slot: u32) -> Vector3<f32> {
+        return Vector3::new(
+            self.component(slot, 0),
+            self.component(slot, 1),
+            self.component(slot, 2),
+        );
+    }
+
+    pub fn float4(&self, slot: u32) -> [f32; 4] {
+        return [
+            self.component(slot, 0),
+            self.component(slot, 1),
+            self.component(slot, 2),
+            self.component(slot, 3),
+        ];
+    }
+
+    /// Matrices are uploaded column-major, see `camera::matrix_to_vec`.
+    pub fn mat4(&self, slot: u32) -> Matrix4<f32> {
+        let c = |i| self.component(slot, i);
+        return Matrix4::new(
+            c(0),
+            c(1),
+            c(2),
+            c(3),
+            c(4),
+            c(5),
+            c(6),
+            c(7),
+            c(8),
+            c(9),
+            c(10),
+            c(11),
+            c(12),
+            c(13),
+            c(14),
+            c(15),
+        );
+    }
+}
+
+pub struct VertexInput<'a> {
+    pub attributes: &'a [Vector4<f32>],
+    pub uniforms: &'a Uniforms,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct VertexOutput {
+    pub position: Vector4<f32>,
+    pub varyings: [f32; MAX_VARYINGS],
+}
+
+impl VertexOutput {
+    fn new(position: Vector4<f32>) -> VertexOutput {
+        return VertexOutput {
+            position,
+            varyings: [0.; MAX_VARYINGS],
+        };
+    }
+
+    fn with(mut self, start: usize, values: &[f32]) -> VertexOutput {
+        self.varyings[start..start + values.len()].copy_from_slice(values);
+        return self;
+    }
+}
+
+pub struct FragmentInput<'a> {
+    pub varyings: [f32; MAX_VARYINGS],
+    pub uniforms: &'a Uniforms,
+    pub texture: Option<&'a TextureData>,
+}
+
+impl FragmentInput<'_> {
+    /// Unbound textures sample as transparent black, as they do on the GPU backends.
+    pub fn sample(&self, u: f32, v: f32) -> [f32; 4] {
+        return match self.texture {
+            Some(texture) => texture.sample(u, v),
+            None => [0.; 4],
+        };
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct VertexAttribute {
+    pub offset: usize,
+    pub components: usize,
+    pub integer: bool,
+}
+
+#[derive(Debug)]
+pub struct VertexLayout {
+    pub stride: usize,
+    pub attributes: Vec<VertexAttribute>,
+}
+
+impl From<&BufferLayout> for VertexLayout {
+    fn from(layout: &BufferLayout) -> VertexLayout {
+        let attributes = layout
+            .elements
+            .iter()
+            .map(|element| {
+                let (components, integer) = match element.data_type {
+                    ShaderDataType::Float => (1, false),
+                    ShaderDataType::Float2 => (2, false),
+                    ShaderDataType::Float3 => (3, false),
+                    ShaderDataType::Float4 => (4, false),
+                    ShaderDataType::Int => (1, true),
+                    ShaderDataType::Int2 => (2, true),
+                    ShaderDataType::Int3 => (3, true),
+                    ShaderDataType::Int4 => (4, true),
+                };
+                VertexAttribute {
+                    offset: element.offset as usize,
+                    components,
+                    integer,
+                }
+            })
+            .collect();
+        return VertexLayout {
+            stride: layout.stride as usize,
+            attributes,
+        };
+    }
+}
+
+impl VertexLayout {
+    /// Reads one vertex out of a buffer. Missing components expand to (0, 0, 0, 1) and reads past
+    /// the end of the buffer return zeros rather than panicking.
+    pub fn fetch(&self, bytes: &[u8], base: usize) -> Vec<Vector4<f32>> {
+        return self
+            .attributes
+            .iter()
+            .map(|attribute| {
+                let mut value = [0., 0., 0., 1.];
+                for (c, v) in value.iter_mut().enumerate().take(attribute.components) {
+                    let start = base + attribute.offset + c * 4;
+                    *v = match bytes.get(start..start + 4) {
+                        Some(b) if attribute.integer => {
+                            i32::from_ne_bytes([b[0], b[1], b[2], b[3]]) as f32
+                        }
+                        Some(b) => f32::from_ne_bytes([b[0], b[1], b[2], b[3]]),
+                        None => 0.,
+                    };
+                }
+                Vector4::new(value[0], value[1], value[2], value[3])
+            })
+            .collect();
+    }
+}
+
+#[derive(Debug)]
+pub struct PlatformShader {
+    vertex_shader: PlatformVertexShader,
+    fragment_shader: PlatformFragmentShader,
+}
+
+impl PlatformShader {
+    pub fn new(
+        renderer: &PlatformRenderer,
+        buffer_layout: BufferLayout,
+        instance_layout: Option<BufferLayout>,
+        vertex_src: &str,
+        fragment_src: &str,
+    ) -> PlatformShader {
+        return PlatformShader {
+            vertex_shader: PlatformVertexShader::new(
+                renderer,
+                &buffer_layout,
+                instance_layout.as_ref(),
+                vertex_src,
+            ),
+            fragment_shader: PlatformFragmentShader::new(renderer, fragment_src),
+        };
+    }
+
+    pub fn bind(&self, scene: &Scene) {
+        self.vertex_shader.bind(&scene.platform_scene);
+        self.fragment_shader.bind(&scene.platform_scene);
+    }
+}
+
+/// Instance attributes follow the vertex attributes in `VertexInput::attributes`.
+#[derive(Debug, Clone)]
+pub struct PlatformVertexShader {
+    pub(super) layout: Rc<VertexLayout>,
+    pub(super) instance_layout: Option<Rc<VertexLayout>>,
+    pub(super) program: VertexProgram,
+}
+
+impl PlatformVertexShader {
+    pub fn new(
+        _renderer: &PlatformRenderer,
+        layout: &BufferLayout,
+        instance_layout: Option<&BufferLayout>,
+        vertex_src: &str,
+    ) -> PlatformVertexShader {
+        let program = vertex_program(vertex_src)
+            .unwrap_or_else(|| panic!("Unknown software vertex shader {}", vertex_src));
+        return PlatformVertexShader {
+            layout: Rc::new(VertexLayout::from(layout)),
+            instance_layout: instance_layout.map(|layout| Rc::new(VertexLayout::from(layout))),
+            program,
+        };
+    }
+
+    pub fn bind(&self, scene: &PlatformScene) {
+        scene.state.borrow_mut().vertex_shader = Some(self.clone());
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PlatformFragmentShader {
+    pub(super) program: FragmentProgram,
+}
+
+impl PlatformFragmentShader {
+    pub fn new(_renderer: &PlatformRenderer, fragment_src: &str) -> PlatformFragmentShader {
+        let program = fragment_program(fragment_src)
+            .unwrap_or_else(|| panic!("Unknown software fragment shader {}", fragment_src));
+        return PlatformFragmentShader { program };
+    }
+
+    pub fn bind(&self, scene: &PlatformScene) {
+        scene.state.borrow_mut().fragment_shader = Some(self.clone());
+    }
+}
+
+/// The software backend has no shader compiler, so shader sources name one of the programs below.
+pub fn vertex_program(name: &str) -> Option<VertexProgram> {
+    let program: VertexProgram = match name {
+        "basic" => basic_vertex,
+        "quad" | "hex" => quad_vertex,
+        "texture" => texture_vertex,
+        "sprite" => sprite_vertex,
+        "gui" => gui_vertex,
+        "font" => font_vertex,
+        "texture_instanced" => texture_instanced_vertex,
+        "obj_model" | "obj_model_textured" => obj_model_vertex,
+        "obj_model_instanced" | "obj_model_textured_instanced" => obj_model_instanced_vertex,
+        "imgui" => imgui_vertex,
+        _ => return None,
+    };
+    return Some(program);
+}
+
+pub fn fragment_program(name: &str) -> Option<FragmentProgram> {
+    let program: FragmentProgram = match name {
+        "basic" => basic_fragment,
+        "quad" | "hex" => color_fragment,
+        "texture" | "texture_instanced" => texture_fragment,
+        "sprite" => sprite_fragment,
+        "gui" => gui_fragment,
+        "font" => font_fragment,
+        "obj_model" | "obj_model_instanced" => obj_model_fragment,
+        "obj_model_textured" | "obj_model_textured_instanced" => obj_model_textured_fragment,
+        "imgui" => imgui_fragment,
+        _ => return None,
+    };
+    return Some(program);
+}
+
+fn model_view_projection(input: &VertexInput, position: Vector4<f32>) -> Vector4<f32> {
+    return input.uniforms.mat4(0) * input.uniforms.mat4(1) * position;
+}
+
+/// Instanced shaders read the model transform as four column attributes starting at `first`.
+fn instance_transform(input: &VertexInput, first: usize) -> Matrix4<f32> {
+    let c = &input.attributes[first..first + 4];
+    return Matrix4::from_cols(c[0], c[1], c[2], c[3]);
+}
+
+fn basic_vertex(input: &VertexInput) -> VertexOutput {
+    let pos = input.attributes[0];
+    let color = input.attributes[1];
+    return VertexOutput::new(Vector4::new(pos.x, pos.y, 0., 1.))
+        .with(0, &[color.x, color.y, color.z, color.w]);
+}
+
+fn basic_fragment(input: &FragmentInput) -> [f32; 4] {
+    let v = input.varyings;
+    return [v[0], v[1], v[2], v[3]];
+}
+
+fn quad_vertex(input: &VertexInput) -> VertexOutput {
+    let pos = input.attributes[0];
+    return VertexOutput::new(model_view_projection(
+        input,
+        Vector4::new(pos.x, pos.y, pos.z, 1.),
+    ));
+}
+
+fn color_fragment(input: &FragmentInput) -> [f32; 4] {
+    return input.uniforms.float4(0);
+}
+
+fn texture_vertex(input: &VertexInput) -> VertexOutput {
+    let pos = input.attributes[0];
+    let tex = input.attributes[1];
+    return VertexOutput::new(model_view_projection(
+        input,
+        Vector4::new(pos.x, pos.y, 0., 1.),
+    ))
+    .with(0, &[tex.x, tex.y]);
+}
+
+fn texture_instanced_vertex(input: &VertexInput) -> VertexOutput {
+    let pos = input.attributes[0];
+    let tex = input.attributes[1];
+    let transform = instance_transform(input, 2);
+    return VertexOutput::new(
+        input.uniforms.mat4(0) * transform * Vector4::new(pos.x, pos.y, 0., 1.),
+    )
+    .with(0, &[tex.x, tex.y]);
+}
+
+fn texture_fragment(input: &FragmentInput) -> [f32; 4] {
+    return input.sample(input.varyings[0], input.varyings[1]);
+}
+
+fn sprite_vertex(input: &VertexInput) -> VertexOutput {
+    let pos = input.attributes[0];
+    let tex = input.attributes[1];
+    let tint = input.attributes[2];
+    return VertexOutput::new(input.uniforms.mat4(0) * Vector4::new(pos.x, pos.y, 0., 1.))
+        .with(0, &[tex.x, tex.y])
+        .with(2, &[tint.x, tint.y, tint.z, tint.w]);
+}
+
+fn sprite_fragment(input: &FragmentInput) -> [f32; 4] {
+    let v = input.varyings;
+    let tex = input.sample(v[0], v[1]);
+    return [v[2] * tex[0], v[3] * tex[1], v[4] * tex[2], v[5] * tex[3]];
+}
+
+fn gui_vertex(input: &VertexInput) -> VertexOutput {
+    let pos = input.attributes[0];
+    return VertexOutput::new(input.uniforms.mat4(0) * Vector4::new(pos.x, pos.y, 0., 1.))
+        .with(0, &[pos.x / 2., 1. - pos.y / 2.]);
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0., 1.);
+    return t * t * (3. - 2. * t);
+}
+
+fn calc_rounded_corners(coord: [f32; 2], radius: f32, width: f32, height: f32) -> f32 {
+    if radius <= 0. {
+        return 1.;
+    }
+    let corner_smooth = 0.002;
+
+    let pixel_pos = [coord[0] * width, coord[1] * height];
+    let corner_point = [
+        pixel_pos[0].max(radius).min(width - radius),
+        pixel_pos[1].max(radius).min(height - radius),
+    ];
+    let lower_bound = (radius - corner_smooth).powi(2);
+    let upper_bound = (radius + corner_smooth).powi(2);
+    let distance_squared =
+        (pixel_pos[0] - corner_point[0]).powi(2) + (pixel_pos[1] - corner_point[1]).powi(2);
+    return smoothstep(upper_bound, lower_bound, distance_squared);
+}
+
+fn gui_fragment(input: &FragmentInput) -> [f32; 4] {
+    let color = input.uniforms.float4(0);
+    let dimensions = input.uniforms.float2(1);
+    let radius = input.uniforms.float(2);
+    let coord = [input.varyings[0], input.varyings[1]];
+    let alpha = calc_rounded_corners(coord, radius, dimensions[0], dimensions[1]) * color[3];
+    return [color[0], color[1], color[2], alpha];
+}
+
+fn font_vertex(input: &VertexInput) -> VertexOutput {
+    let pos = input.attributes[0];
+    let tex = input.attributes[1];
+    return VertexOutput::new(Vector4::new(pos.x, pos.y, 0., 1.)).with(0, &[tex.x, tex.y]);
+}
+
+fn font_fragment(input: &FragmentInput) -> [f32; 4] {
+    let color = input.uniforms.float4(0);
+    let alpha = input.sample(input.varyings[0], input.varyings[1])[2];
+    return [color[0], color[1], color[2], alpha];
+}
+
+fn obj_model_vertex(input: &VertexInput) -> VertexOutput {
+    return obj_model_vertex_with_transform(input, input.uniforms.mat4(1));
+}
+
+fn obj_model_instanced_vertex(input: &VertexInput) -> VertexOutput {
+    return obj_model_vertex_with_transform(input, instance_transform(input, 3));
+}
+
+fn obj_model_vertex_with_transform(input: &VertexInput, transform: Matrix4<f32>) -> VertexOutput {
+    let pos = input.attributes[0];
+    let tex = input.attributes[1];
+    let normal = input.attributes[2];
+    let frag_pos = transform * pos;
+    let normal = transform * Vector4::new(normal.x, normal.y, normal.z, 0.);
+    return VertexOutput::new(input.uniforms.mat4(0) * frag_pos)
+        .with(0, &[frag_pos.x, frag_pos.y, frag_pos.z])
+        .with(3, &[tex.x, tex.y])
+        .with(5, &[normal.x, normal.y, normal.z]);
+}
+
+fn obj_model_fragment(input: &FragmentInput) -> [f32; 4] {
+    let level = obj_model_light_level(input);
+    let diffuse = input.uniforms.float3(1);
+    return [
+        level.x * diffuse.x,
+        level.y * diffuse.y,
+        level.z * diffuse.z,
+        1.,
+    ];
+}
+
+/// Obj texture coordinates start at the bottom left, so v is flipped before sampling.
+fn obj_model_textured_fragment(input: &FragmentInput) -> [f32; 4] {
+    let level = obj_model_light_level(input);
+    let diffuse = input.uniforms.float3(1);
+    let tex = input.sample(input.varyings[3], 1. - input.varyings[4]);
+    return [
+        level.x * diffuse.x * tex[0],
+        level.y * diffuse.y * tex[1],
+        level.z * diffuse.z * tex[2],
+        tex[3],
+    ];
+}
+
+fn obj_model_light_level(input: &FragmentInput) -> Vector3<f32> {
+    let v = input.varyings;
+    let frag_pos = Vector3::new(v[0], v[1], v[2]);
+    let normal = Vector3::new(v[5], v[6], v[7]);
+    let light_pos = input.uniforms.float3(2);
+
+    let ambient_strength = 1.2;
+    let light_color = Vector3::new(1., 1., 1.);
+    let ambient_level = light_color * ambient_strength;
+
+    let norm = normal.normalize();
+    let light_dir = (light_pos - frag_pos).normalize();
+    let diff = norm.dot(light_dir).max(0.);
+    let diffuse_level = light_color * diff;
+
+    return ambient_level + diffuse_level;
+}
+
+fn imgui_vertex(input: &VertexInput) -> VertexOutput {
+    let pos = input.attributes[0];
+    let uv = input.attributes[1];
+    let col = input.attributes[2];
+    return VertexOutput::new(input.uniforms.mat4(0) * Vector4::new(pos.x, pos.y, 0., 1.))
+        .with(0, &[uv.x, uv.y])
+        .with(2, &[col.x, col.y, col.z, col.w]);
+}
+
+fn imgui_fragment(input: &FragmentInput) -> [f32; 4] {
+    let v = input.varyings;
+    let tex = input.sample(v[0], v[1]);
+    return [v[2] * tex[0], v[3] * tex[1], v[4] * tex[2], v[5] * tex[3]];
+}
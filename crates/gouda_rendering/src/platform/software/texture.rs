@@ -0,0 +1,74 @@
+use gouda_images::Image;
+use std::rc::Rc;
+
+use super::{PlatformRenderer, PlatformScene};
+
+#[derive(Debug)]
+pub struct TextureData {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl TextureData {
+    fn texel(&self, x: i64, y: i64) -> [f32; 4] {
+        let x = x.rem_euclid(self.width as i64) as usize;
+        let y = y.rem_euclid(self.height as i64) as usize;
+        let i = (y * self.width + x) * 4;
+        return [
+            self.pixels[i] as f32 / 255.,
+            self.pixels[i + 1] as f32 / 255.,
+            self.pixels[i + 2] as f32 / 255.,
+            self.pixels[i + 3] as f32 / 255.,
+        ];
+    }
+
+    /// Bilinear sample with wrap addressing. (0, 0) is the top left of the image.
+    pub fn sample(&self, u: f32, v: f32) -> [f32; 4] {
+        if self.width == 0 || self.height == 0 {
+            return [0.; 4];
+        }
+        let x = u * self.width as f32 - 0.5;
+        let y = v * self.height as f32 - 0.5;
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let fx = x - x0;
+        let fy = y - y0;
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let tl = self.texel(x0, y0);
+        let tr = self.texel(x0 + 1, y0);
+        let bl = self.texel(x0, y0 + 1);
+        let br = self.texel(x0 + 1, y0 + 1);
+
+        let mut res = [0.; 4];
+        for c in 0..4 {
+            let top = tl[c] + (tr[c] - tl[c]) * fx;
+            let bottom = bl[c] + (br[c] - bl[c]) * fx;
+            res[c] = top + (bottom - top) * fy;
+        }
+        return res;
+    }
+}
+
+#[derive(Debug)]
+pub struct PlatformTexture {
+    data: Rc<TextureData>,
+}
+
+impl PlatformTexture {
+    pub fn new(_renderer: &PlatformRenderer, image: &Image) -> PlatformTexture {
+        let data = TextureData {
+            width: image.width,
+            height: image.height,
+            pixels: image.raw_pixels(),
+        };
+        return PlatformTexture {
+            data: Rc::new(data),
+        };
+    }
+
+    pub fn bind(&self, scene: &PlatformScene) {
+        scene.state.borrow_mut().texture = Some(self.data.clone());
+    }
+}
@@ -0,0 +1,546 @@
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+use cgmath::{Matrix4, Vector4};
+
+use crate::buffers::{IndexBuffer, VertexBuffer};
+use crate::material_library::Material;
+use crate::shaders::{Shader, ShaderUniform};
+use crate::texture_library::TextureHandle;
+use crate::{Renderable, Scene};
+
+/// Queued draws are executed one layer after another, in this order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RenderLayer {
+    /// Depth tested and written. Sorted by shader, material, texture and mesh so draws that
+    /// share them bind them once, then nearest first so hidden fragments are rejected early.
+    Opaque,
+    /// Alpha blended draws, depth tested against the opaque layer without writing depth.
+    /// Farthest first, draws at the same depth keep the order they were submitted in.
+    Transparent,
+    /// Drawn last in the order they were submitted with no depth test, for the GUI.
+    Overlay,
+}
+
+impl RenderLayer {
+    fn depth_mode(self) -> DepthMode {
+        return match self {
+            RenderLayer::Opaque => DepthMode::TestAndWrite,
+            RenderLayer::Transparent => DepthMode::Test,
+            RenderLayer::Overlay => DepthMode::Disabled,
+        };
+    }
+}
+
+/// How draws use the depth buffer, which every backend clears at the start of a scene. Nearer
+/// or equal depths pass the test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DepthMode {
+    /// Every fragment is drawn and depth is left untouched. Immediate draws use this unless the
+    /// mode is changed.
+    #[default]
+    Disabled,
+    Test,
+    TestAndWrite,
+}
+
+/// Per-instance data for a command, which is drawn once for each of the `count` instances.
+#[derive(Clone)]
+pub struct Instances {
+    pub buffer: Rc<VertexBuffer>,
+    pub count: u32,
+}
+
+/// The vertices and indices a command draws.
+pub enum Geometry<'a> {
+    /// A renderable owned by one of the renderer's libraries, drawn in full.
+    Renderable(&'a dyn Renderable),
+    /// A range of triangles from shared buffers, such as one submesh of a model.
+    Indexed {
+        vertex_buffer: Rc<VertexBuffer>,
+        index_buffer: Rc<IndexBuffer>,
+        first_index: u32,
+        index_count: u32,
+    },
+}
+
+impl Geometry<'_> {
+    fn key(&self) -> (usize, usize, u32) {
+        return match self {
+            Geometry::Renderable(renderable) => (
+                *renderable as *const dyn Renderable as *const () as usize,
+                0,
+                0,
+            ),
+            Geometry::Indexed {
+                vertex_buffer,
+                index_buffer,
+                first_index,
+                ..
+            } => (
+                Rc::as_ptr(vertex_buffer) as usize,
+                Rc::as_ptr(index_buffer) as usize,
+                *first_index,
+            ),
+        };
+    }
+
+    fn bind(&self, scene: &Scene) {
+        match self {
+            Geometry::Renderable(renderable) => renderable.bind(scene),
+            Geometry::Indexed {
+                vertex_buffer,
+                index_buffer,
+                first_index,
+                ..
+            } => {
+                vertex_buffer.bind(scene);
+                index_buffer.bind_with_offset(scene, *first_index);
+            }
+        }
+    }
+
+    fn draw(&self, scene: &Scene, instances: Option<&Instances>) {
+        match (self, instances) {
+            (Geometry::Renderable(renderable), None) => {
+                scene.draw_indexed(renderable.index_buffer())
+            }
+            (Geometry::Renderable(renderable), Some(instances)) => scene
+                .draw_indexed_tris_instanced(
+                    renderable.num_indices(),
+                    instances.count as u64,
+                    renderable.index_buffer(),
+                ),
+            (
+                Geometry::Indexed {
+                    index_buffer,
+                    index_count,
+                    ..
+                },
+                None,
+            ) => scene.draw_indexed_tris(*index_count as u64, index_buffer),
+            (
+                Geometry::Indexed {
+                    index_buffer,
+                    index_count,
+                    ..
+                },
+                Some(instances),
+            ) => scene.draw_indexed_tris_instanced(
+                *index_count as u64,
+                instances.count as u64,
+                index_buffer,
+            ),
+        }
+    }
+}
+
+/// One draw recorded by a `Scene::submit*` call. The projection and transform go to vertex slots
+/// 0 and 1. The material's uniforms are uploaded to the first fragment slots, and the command's
+/// own fragment uniforms to the slots they name. Instanced commands use a shader that reads the
+/// transform from the instance buffer instead.
+pub struct RenderCommand<'a> {
+    pub layer: RenderLayer,
+    pub shader: &'a Shader,
+    pub material: Option<Rc<Material>>,
+    pub texture: Option<TextureHandle>,
+    pub geometry: Geometry<'a>,
+    pub projection: Matrix4<f32>,
+    pub transform: Matrix4<f32>,
+    pub fragment_uniforms: Vec<(u32, ShaderUniform)>,
+    pub instances: Option<Instances>,
+}
+
+impl RenderCommand<'_> {
+    /// Depth of the transform's origin in clip space, larger is farther away. Only used to order
+    /// draws, overlapping opaque draws are resolved per pixel by the depth test.
+    fn depth(&self) -> f32 {
+        let position = self.projection * self.transform * Vector4::new(0., 0., 0., 1.);
+        return position.z / position.w;
+    }
+
+    fn shader_key(&self) -> usize {
+        return self.shader as *const Shader as usize;
+    }
+
+    fn material_key(&self) -> Option<usize> {
+        return self
+            .material
+            .as_ref()
+            .map(|material| Rc::as_ptr(material) as usize);
+    }
+
+    fn texture_key(&self) -> Option<usize> {
+        return self
+            .texture
+            .as_ref()
+            .map(|texture| &**texture as *const _ as usize);
+    }
+}
+
+/// How much work executing a queue took, for checking that sorting is paying off.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    pub draws: usize,
+    pub shader_binds: usize,
+    pub material_binds: usize,
+    pub mesh_binds: usize,
+}
+
+/// Draws recorded over a scene, sorted and executed together so shaders, materials and meshes
+/// are only bound when they change.
+#[derive(Default)]
+pub struct RenderQueue<'a> {
+    commands: Vec<(f32, RenderCommand<'a>)>,
+}
+
+impl<'a> RenderQueue<'a> {
+    pub fn new() -> RenderQueue<'a> {
+        return RenderQueue { commands: vec![] };
+    }
+
+    pub fn push(&mut self, command: RenderCommand<'a>) {
+        self.commands.push((command.depth(), command));
+    }
+
+    pub fn len(&self) -> usize {
+        return self.commands.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.commands.is_empty();
+    }
+
+    /// Sorts the queued commands by layer and draws them, emptying the queue.
+    pub fn execute(&mut self, scene: &Scene) -> RenderStats {
+        self.commands.sort_by(|(depth_a, a), (depth_b, b)| {
+            return a.layer.cmp(&b.layer).then_with(|| match a.layer {
+                RenderLayer::Opaque => a
+                    .shader_key()
+                    .cmp(&b.shader_key())
+                    .then(a.material_key().cmp(&b.material_key()))
+                    .then(a.texture_key().cmp(&b.texture_key()))
+                    .then(a.geometry.key().cmp(&b.geometry.key()))
+                    .then(depth_a.partial_cmp(depth_b).unwrap_or(Ordering::Equal)),
+                RenderLayer::Transparent => depth_b.partial_cmp(depth_a).unwrap_or(Ordering::Equal),
+                RenderLayer::Overlay => Ordering::Equal,
+            });
+        });
+
+        let mut stats = RenderStats::default();
+        let mut depth_mode = DepthMode::Disabled;
+        let mut bound_shader = None;
+        let mut bound_material = None;
+        let mut bound_texture = None;
+        let mut bound_geometry = None;
+        for (_, command) in self.commands.drain(..) {
+            if depth_mode != command.layer.depth_mode() {
+                depth_mode = command.layer.depth_mode();
+                scene.set_depth_mode(depth_mode);
+            }
+            let shader = command.shader;
+            if bound_shader != Some(command.shader_key()) {
+                shader.bind(scene);
+                bound_shader = Some(command.shader_key());
+                stats.shader_binds += 1;
+            }
+            shader.upload_vertex_uniform_mat4(scene, 0, command.projection);
+            shader.upload_vertex_uniform_mat4(scene, 1, command.transform);
+
+            for (slot, uniform) in &command.fragment_uniforms {
+                shader.upload_fragment_uniform(scene, *slot, *uniform);
+            }
+            match &command.material {
+                Some(material) => {
+                    let overwritten = command
+                        .fragment_uniforms
+                        .iter()
+                        .any(|(slot, _)| (*slot as usize) < material.fragment_uniforms.len());
+                    if bound_material != command.material_key() || overwritten {
+                        material.upload_uniforms(scene, shader);
+                        bound_material = command.material_key();
+                        stats.material_binds += 1;
+                    }
+                }
+                None => bound_material = None,
+            }
+
+            if let Some(texture) = &command.texture {
+                if bound_texture != command.texture_key() {
+                    texture.bind(scene);
+                    bound_texture = command.texture_key();
+                }
+            }
+
+            let geometry = command.geometry.key();
+            if bound_geometry != Some(geometry) {
+                command.geometry.bind(scene);
+                bound_geometry = Some(geometry);
+                stats.mesh_binds += 1;
+            }
+            if let Some(instances) = &command.instances {
+                instances.buffer.bind(scene);
+            }
+            command.geometry.draw(scene, command.instances.as_ref());
+            stats.draws += 1;
+        }
+        if depth_mode != DepthMode::Disabled {
+            scene.set_depth_mode(DepthMode::Disabled);
+        }
+        return stats;
+    }
+}
+
+#[cfg(all(test, not(any(target_os = "macos", target_os = "windows"))))]
+mod tests {
+    use super::*;
+    use crate::model::Model;
+    use crate::obj::{MtlFile, MtlMaterialDefinition, ObjFace, ObjFile, ObjVert};
+    use crate::{Renderer, Texture};
+    use cgmath::{SquareMatrix, Vector3};
+    use gouda_images::Image;
+    use std::collections::HashMap;
+
+    fn quad_model(renderer: &Renderer, material: &str, diffuse: [f32; 3]) -> Model {
+        let obj = ObjFile {
+            vertices: vec![
+                [-1., -1., 0., 1.],
+                [1., -1., 0., 1.],
+                [1., 1., 0., 1.],
+                [-1., 1., 0., 1.],
+            ],
+            tex_coords: vec![[0., 0., 0.]],
+            vertex_normals: vec![[0., 0., 1.]],
+            faces: vec![ObjFace {
+                material: Some(material.to_string()),
+                verts: (1..5)
+                    .map(|index| ObjVert {
+                        index,
+                        texcoord_index: 1,
+                        normal_index: 1,
+                    })
+                    .collect(),
+            }],
+        };
+        let mut materials = HashMap::new();
+        materials.insert(
+            material.to_string(),
+            MtlMaterialDefinition {
+                name: material.to_string(),
+                ambient_color: diffuse,
+                diffuse_color: diffuse,
+                specular_color: [0.; 3],
+                emission_color: [0.; 3],
+                specular_highlights: 0.,
+                optical_density: 0.,
+                dissolve: 1.,
+                illumination: 1,
+                ambient_map: None,
+                diffuse_map: None,
+                specular_map: None,
+            },
+        );
+        return Model::new(renderer, obj, &MtlFile { materials });
+    }
+
+    fn at(x: f32, z: f32) -> Matrix4<f32> {
+        return Matrix4::from_translation(Vector3::new(x, 0., z)) * Matrix4::from_scale(0.25);
+    }
+
+    #[test]
+    fn test_opaque_draws_are_grouped_by_material_and_mesh() {
+        let renderer = Renderer::new_headless(16, 16);
+        let red = quad_model(&renderer, "red", [1., 0., 0.]);
+        let blue = quad_model(&renderer, "blue", [0., 0., 1.]);
+
+        let scene = renderer.begin_scene().unwrap();
+        for i in 0..6 {
+            let model = if i % 2 == 0 { &red } else { &blue };
+            scene.submit_model(model, at(-0.75 + i as f32 * 0.3, 0.5));
+        }
+        let stats = scene.flush();
+        renderer.end_scene(scene);
+
+        assert_eq!(
+            stats,
+            RenderStats {
+                draws: 6,
+                shader_binds: 1,
+                material_binds: 2,
+                mesh_binds: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_opaque_draws_sharing_a_material_bind_it_once_at_any_depth() {
+        let renderer = Renderer::new_headless(16, 16);
+        let red = quad_model(&renderer, "red", [1., 0., 0.]);
+        let blue = quad_model(&renderer, "blue", [0., 0., 1.]);
+
+        // Alternating materials at increasing depth, a depth first sort would bind every draw
+        let scene = renderer.begin_scene().unwrap();
+        for i in 0..6 {
+            let model = if i % 2 == 0 { &red } else { &blue };
+            scene.submit_model(model, at(-0.75 + i as f32 * 0.3, 0.1 + i as f32 * 0.1));
+        }
+        let stats = scene.flush();
+        renderer.end_scene(scene);
+
+        assert_eq!(stats.draws, 6);
+        assert_eq!(stats.material_binds, 2);
+        assert_eq!(stats.mesh_binds, 2);
+    }
+
+    #[test]
+    fn test_transparent_draws_behind_opaque_ones_are_hidden() {
+        let renderer = Renderer::new_headless(16, 16);
+        let red = quad_model(&renderer, "red", [1., 0., 0.]);
+        let scene = renderer.begin_scene().unwrap();
+        scene.submit_model(&red, at(0., 0.2));
+        scene.submit_shape_by_name("quad", "quad", at(0., 0.8), [0., 0., 1., 0.5]);
+        renderer.end_scene(scene);
+
+        assert_eq!(
+            renderer.platform_renderer.framebuffer().pixel(8, 8),
+            [255, 0, 0, 255]
+        );
+    }
+
+    #[test]
+    fn test_nearer_opaque_draws_cover_farther_ones() {
+        let draw = |red_near: bool, far_first: bool| {
+            let renderer = Renderer::new_headless(16, 16);
+            let red = quad_model(&renderer, "red", [1., 0., 0.]);
+            let blue = quad_model(&renderer, "blue", [0., 0., 1.]);
+            let (near_model, far_model) = if red_near {
+                (&red, &blue)
+            } else {
+                (&blue, &red)
+            };
+            let scene = renderer.begin_scene().unwrap();
+            let near = (near_model, at(0., 0.2));
+            let far = (far_model, at(0., 0.8));
+            let order = if far_first { [far, near] } else { [near, far] };
+            for (model, transform) in order.iter() {
+                scene.submit_model(model, *transform);
+            }
+            renderer.end_scene(scene);
+            let pixel = renderer.platform_renderer.framebuffer().pixel(8, 8);
+            pixel
+        };
+
+        for far_first in [false, true] {
+            assert_eq!(draw(true, far_first), [255, 0, 0, 255]);
+            assert_eq!(draw(false, far_first), [0, 0, 255, 255]);
+        }
+    }
+
+    #[test]
+    fn test_transparent_draws_are_back_to_front() {
+        let draw = |far_first: bool| {
+            let renderer = Renderer::new_headless(16, 16);
+            let scene = renderer.begin_scene().unwrap();
+            let near = (at(0., 0.2), [1., 0., 0., 0.5]);
+            let far = (at(0., 0.8), [0., 0., 1., 0.5]);
+            let order = if far_first { [far, near] } else { [near, far] };
+            for (transform, color) in order.iter() {
+                scene.submit_shape_by_name("quad", "quad", *transform, *color);
+            }
+            renderer.end_scene(scene);
+            let pixel = renderer.platform_renderer.framebuffer().pixel(8, 8);
+            pixel
+        };
+
+        assert_eq!(draw(false), draw(true));
+        assert!(draw(false)[0] > draw(false)[2]);
+    }
+
+    #[test]
+    fn test_overlay_is_drawn_last() {
+        let renderer = Renderer::new_headless(16, 16);
+        let scene = renderer.begin_scene().unwrap();
+        scene.submit_shape_gui("quad", "quad", Matrix4::identity(), [1., 0., 0., 1.]);
+        scene.submit_shape_by_name("quad", "quad", Matrix4::identity(), [0., 0., 1., 1.]);
+        assert_eq!(scene.queued(), 2);
+        renderer.end_scene(scene);
+
+        assert_eq!(
+            renderer.platform_renderer.framebuffer().pixel(8, 8),
+            [255, 0, 0, 255]
+        );
+    }
+
+    #[test]
+    fn test_immediate_draws_flush_the_queue_first() {
+        let renderer = Renderer::new_headless(16, 16);
+        let scene = renderer.begin_scene().unwrap();
+        scene.submit_shape_by_name("quad", "quad", Matrix4::identity(), [0., 0., 1., 1.]);
+        scene.bind_shader_with_uniforms(
+            "quad",
+            vec![
+                ShaderUniform::Mat4(Matrix4::identity()),
+                ShaderUniform::Mat4(Matrix4::identity()),
+            ],
+            vec![ShaderUniform::Float4([1., 0., 0., 1.])],
+        );
+        assert_eq!(scene.queued(), 0);
+        scene.draw_shape("quad");
+        renderer.end_scene(scene);
+
+        assert_eq!(
+            renderer.platform_renderer.framebuffer().pixel(8, 8),
+            [255, 0, 0, 255]
+        );
+    }
+
+    #[test]
+    fn test_instanced_model_matches_individual_draws() {
+        let transforms: Vec<Matrix4<f32>> =
+            (0..6).map(|i| at(-0.75 + i as f32 * 0.3, 0.5)).collect();
+        let draw = |instanced: bool| {
+            let renderer = Renderer::new_headless(16, 16);
+            let model = quad_model(&renderer, "red", [1., 0., 0.]);
+            let scene = renderer.begin_scene().unwrap();
+            if instanced {
+                scene.submit_model_instanced(&model, &transforms);
+            } else {
+                for transform in &transforms {
+                    scene.submit_model(&model, *transform);
+                }
+            }
+            let stats = scene.flush();
+            renderer.end_scene(scene);
+            let pixels = renderer.platform_renderer.framebuffer().pixels.clone();
+            (stats.draws, pixels)
+        };
+
+        let (instanced_draws, instanced_pixels) = draw(true);
+        let (individual_draws, individual_pixels) = draw(false);
+        assert_eq!((instanced_draws, individual_draws), (1, 6));
+        assert!(instanced_pixels == individual_pixels);
+    }
+
+    #[test]
+    fn test_instanced_textures_are_drawn_at_each_transform() {
+        let renderer = Renderer::new_headless(16, 16);
+        let image = Image {
+            width: 1,
+            height: 1,
+            data: Image::data_from_raw_pixels(1, 1, vec![255, 0, 0, 255]),
+        };
+        let texture = TextureHandle::new(Texture::new(&renderer, &image));
+        let left = Matrix4::from_translation(Vector3::new(-0.5, 0., 0.)) * Matrix4::from_scale(0.5);
+        let right = Matrix4::from_translation(Vector3::new(0.5, 0., 0.)) * Matrix4::from_scale(0.5);
+
+        let scene = renderer.begin_scene().unwrap();
+        scene.submit_texture_instanced(&texture, &[left, right]);
+        assert_eq!(scene.flush().draws, 1);
+        renderer.end_scene(scene);
+
+        let framebuffer = renderer.platform_renderer.framebuffer();
+        assert_eq!(framebuffer.pixel(4, 8), [255, 0, 0, 255]);
+        assert_eq!(framebuffer.pixel(12, 8), [255, 0, 0, 255]);
+        assert_eq!(framebuffer.pixel(8, 8), [110, 186, 92, 255]);
+    }
+}
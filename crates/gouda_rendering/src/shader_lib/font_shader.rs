@@ -102,3 +102,9 @@ float4 PSMain(VSOut vOut) : SV_Target
     return float4(color[0], color[1], color[2], alpha);
 }
 ";
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub const FONT_VERTEX_SHADER: &str = "font";
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub const FONT_FRAGMENT_SHADER: &str = "font";
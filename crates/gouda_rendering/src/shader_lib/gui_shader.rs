@@ -153,3 +153,9 @@ float4 PSMain(VertexOut interpolated) : SV_TARGET
     return float4(color[0], color[1], color[2], alpha);
 }
 ";
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub const GUI_VERTEX_SHADER: &str = "gui";
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub const GUI_FRAGMENT_SHADER: &str = "gui";
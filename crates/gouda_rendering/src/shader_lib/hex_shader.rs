@@ -80,3 +80,9 @@ float4 PSMain() : SV_TARGET
     return float4(color[0], color[1], color[2], color[3]);
 }
 ";
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub const HEX_VERTEX_SHADER: &str = "hex";
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub const HEX_FRAGMENT_SHADER: &str = "hex";
@@ -131,3 +131,9 @@ float4 PSMain(VertexOut vout) : SV_TARGET
     return vout.color * tex.Sample(splr, vout.uv);
 }
 ";
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub const IMGUI_VERTEX_SHADER: &str = "imgui";
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub const IMGUI_FRAGMENT_SHADER: &str = "imgui";
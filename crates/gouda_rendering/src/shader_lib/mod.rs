@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use crate::buffers::{BufferElement, BufferLayout, ShaderDataType};
 use crate::shaders::Shader;
 use crate::{Renderer, Scene};
 
@@ -8,9 +9,13 @@ use self::font_shader::font_shader;
 use self::gui_shader::gui_shader;
 use self::hex_shader::hex_shader;
 use self::imgui_shader::imgui_shader;
-use self::obj_model_shader::obj_model_shader;
+use self::obj_model_shader::{
+    obj_model_instanced_shader, obj_model_shader, obj_model_textured_instanced_shader,
+    obj_model_textured_shader,
+};
 use self::quad_shader::quad_shader;
-use self::texture_shader::texture_shader;
+use self::sprite_shader::sprite_shader;
+use self::texture_shader::{texture_instanced_shader, texture_shader};
 
 pub mod basic_shader;
 pub mod font_shader;
@@ -19,8 +24,20 @@ pub mod hex_shader;
 pub mod imgui_shader;
 pub mod obj_model_shader;
 pub mod quad_shader;
+pub mod sprite_shader;
 pub mod texture_shader;
 
+/// One model transform per instance, as four columns. Instanced variants of the shaders below
+/// take their transform from this layout instead of from vertex slot 1.
+pub fn instance_transform_layout() -> BufferLayout {
+    return BufferLayout::per_instance(vec![
+        BufferElement::new("TRANSFORM_X", ShaderDataType::Float4),
+        BufferElement::new("TRANSFORM_Y", ShaderDataType::Float4),
+        BufferElement::new("TRANSFORM_Z", ShaderDataType::Float4),
+        BufferElement::new("TRANSFORM_W", ShaderDataType::Float4),
+    ]);
+}
+
 #[derive(Debug)]
 pub struct ShaderLibrary {
     shaders: HashMap<&'static str, Shader>,
@@ -40,16 +57,24 @@ impl ShaderLibrary {
         self.add("imgui", imgui_shader(renderer));
         self.add("quad", quad_shader(renderer));
         self.add("texture", texture_shader(renderer));
+        self.add("texture_instanced", texture_instanced_shader(renderer));
+        self.add("sprite", sprite_shader(renderer));
         self.add("hex", hex_shader(renderer));
         self.add("obj_model", obj_model_shader(renderer));
+        self.add("obj_model_textured", obj_model_textured_shader(renderer));
+        self.add("obj_model_instanced", obj_model_instanced_shader(renderer));
+        self.add(
+            "obj_model_textured_instanced",
+            obj_model_textured_instanced_shader(renderer),
+        );
     }
 
     pub fn add(&mut self, name: &'static str, shader: Shader) {
         self.shaders.insert(name, shader);
     }
 
-    pub fn get(&self, name: &'static str) -> Option<&Shader> {
-        return self.shaders.get(&name);
+    pub fn get(&self, name: &str) -> Option<&Shader> {
+        return self.shaders.get(name);
     }
 
     pub fn bind_shader(&self, scene: &Scene, name: &'static str) {
@@ -1,4 +1,5 @@
 use crate::buffers::{BufferElement, BufferLayout, ShaderDataType};
+use crate::shader_lib::instance_transform_layout;
 use crate::shaders::{Shader, ShaderUniformSpec};
 use crate::Renderer;
 
@@ -22,20 +23,63 @@ pub fn obj_model_shader(renderer: &Renderer) -> Shader {
     return shader;
 }
 
+/// The obj shader with the diffuse color multiplied by a texture.
+pub fn obj_model_textured_shader(renderer: &Renderer) -> Shader {
+    let shader = Shader::new(
+        renderer,
+        obj_model_shader_layout(),
+        OBJ_MODEL_VERTEX_SHADER,
+        OBJ_MODEL_TEXTURED_FRAGMENT_SHADER,
+        ShaderUniformSpec { uniforms: vec![] },
+        ShaderUniformSpec { uniforms: vec![] },
+    );
+    return shader;
+}
+
+/// The obj shader with the model transform read per instance instead of from vertex slot 1.
+pub fn obj_model_instanced_shader(renderer: &Renderer) -> Shader {
+    let shader = Shader::new_instanced(
+        renderer,
+        obj_model_shader_layout(),
+        instance_transform_layout(),
+        OBJ_MODEL_INSTANCED_VERTEX_SHADER,
+        OBJ_MODEL_FRAGMENT_SHADER,
+        ShaderUniformSpec { uniforms: vec![] },
+        ShaderUniformSpec { uniforms: vec![] },
+    );
+    return shader;
+}
+
+pub fn obj_model_textured_instanced_shader(renderer: &Renderer) -> Shader {
+    let shader = Shader::new_instanced(
+        renderer,
+        obj_model_shader_layout(),
+        instance_transform_layout(),
+        OBJ_MODEL_INSTANCED_VERTEX_SHADER,
+        OBJ_MODEL_TEXTURED_FRAGMENT_SHADER,
+        ShaderUniformSpec { uniforms: vec![] },
+        ShaderUniformSpec { uniforms: vec![] },
+    );
+    return shader;
+}
+
 #[cfg(target_os = "macos")]
 pub const OBJ_MODEL_VERTEX_SHADER: &str = "
 using namespace metal;
 
-struct VertexUniforms {
-    float4x4 mat;
-};
-
 struct VertexIn {
     float4 position   [[attribute(0)]];
     float2 texcoord   [[attribute(1)]];
     float3 normal   [[attribute(2)]];
 };
 
+struct VertexOut {
+    float4 position [[position]];
+    float3 fragPos;
+    float2 texcoord;
+    float3 normal;
+};
+
 struct ViewProjection {
     float4x4 mat;
 };
@@ -44,12 +88,17 @@ struct ModelTransform {
     float4x4 mat;
 };
 
-vertex float4 vertex_main(constant VertexIn *in [[buffer(0)]],
+vertex VertexOut vertex_main(VertexIn vIn [[stage_in]],
                                  constant ViewProjection &viewProjection [[buffer(1)]],
-                                 constant ModelTransform &modelTransform [[buffer(2)]],
-                                 uint vid [[vertex_id]])
+                                 constant ModelTransform &modelTransform [[buffer(2)]])
 {
-    return float4(in[vid].position) * modelTransform.mat * viewProjection.mat;
+    VertexOut vOut;
+    float4 fragPos = vIn.position * modelTransform.mat;
+    vOut.position = fragPos * viewProjection.mat;
+    vOut.fragPos = fragPos.xyz;
+    vOut.texcoord = vIn.texcoord;
+    vOut.normal = (float4(vIn.normal, 0.0) * modelTransform.mat).xyz;
+    return vOut;
 }
 ";
 
@@ -90,6 +139,83 @@ VSOut VSMain(VertexIn vertexIn)
 }
 ";
 
+#[cfg(target_os = "macos")]
+pub const OBJ_MODEL_INSTANCED_VERTEX_SHADER: &str = "
+using namespace metal;
+
+struct VertexIn {
+    float4 position   [[attribute(0)]];
+    float2 texcoord   [[attribute(1)]];
+    float3 normal   [[attribute(2)]];
+};
+
+struct VertexOut {
+    float4 position [[position]];
+    float3 fragPos;
+    float2 texcoord;
+    float3 normal;
+};
+
+struct ViewProjection {
+    float4x4 mat;
+};
+
+vertex VertexOut vertex_main(VertexIn vIn [[stage_in]],
+                                 constant ViewProjection &viewProjection [[buffer(1)]],
+                                 constant float4x4 *instances [[buffer(30)]],
+                                 uint iid [[instance_id]])
+{
+    VertexOut vOut;
+    float4 fragPos = vIn.position * instances[iid];
+    vOut.position = fragPos * viewProjection.mat;
+    vOut.fragPos = fragPos.xyz;
+    vOut.texcoord = vIn.texcoord;
+    vOut.normal = (float4(vIn.normal, 0.0) * instances[iid]).xyz;
+    return vOut;
+}
+";
+
+#[cfg(target_os = "windows")]
+pub const OBJ_MODEL_INSTANCED_VERTEX_SHADER: &str = "
+struct VSOut {
+    float4 position : SV_POSITION;
+    float3 fragPos  : POSITION;
+    float2 texcoord : TEXCOORD0;
+    float3 normal : NORMAL;
+};
+
+struct VertexIn {
+    float4 position : POSITION;
+    float2 texcoord : TEXCOORD0;
+    float3 normal : NORMAL;
+    float4 transformX : TRANSFORM_X;
+    float4 transformY : TRANSFORM_Y;
+    float4 transformZ : TRANSFORM_Z;
+    float4 transformW : TRANSFORM_W;
+};
+
+cbuffer CBuf1
+{
+    matrix projection;
+};
+
+VSOut VSMain(VertexIn vertexIn)
+{
+    VSOut vso;
+    float4x4 transformation = transpose(float4x4(
+        vertexIn.transformX, vertexIn.transformY, vertexIn.transformZ, vertexIn.transformW));
+    float4x4 worldViewProj = mul(projection, transformation);
+    vso.position = mul(worldViewProj, vertexIn.position);
+    vso.fragPos = mul(transformation, vertexIn.position);
+    vso.texcoord = vertexIn.texcoord;
+    vso.normal = mul(transformation, vertexIn.normal);
+    return vso;
+}
+";
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub const OBJ_MODEL_INSTANCED_VERTEX_SHADER: &str = "obj_model_instanced";
+
 #[cfg(target_os = "macos")]
 pub const OBJ_MODEL_FRAGMENT_SHADER: &str = "
 using namespace metal;
@@ -142,3 +268,91 @@ float4 PSMain(VSOut vsout) : SV_Target
     return float4(result[0], result[1], result[2], 1.0);
 }
 ";
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub const OBJ_MODEL_VERTEX_SHADER: &str = "obj_model";
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub const OBJ_MODEL_FRAGMENT_SHADER: &str = "obj_model";
+
+#[cfg(target_os = "macos")]
+pub const OBJ_MODEL_TEXTURED_FRAGMENT_SHADER: &str = "
+using namespace metal;
+
+struct VertexOut {
+    float4 position [[position]];
+    float3 fragPos;
+    float2 texcoord;
+    float3 normal;
+};
+
+fragment float4 fragment_main(VertexOut interpolated [[stage_in]],
+                                constant packed_float3 &ambient [[buffer(0)]],
+                                constant packed_float3 &diffuse [[buffer(1)]],
+                                texture2d<float> tex2D [[ texture(0) ]])
+{
+    constexpr sampler textureSampler (mag_filter::linear,
+                                      min_filter::linear);
+    // Materials don't upload a light position yet, so the light sits at the origin like an
+    // unset constant buffer on the other backends.
+    float3 lightpos = float3(0.0, 0.0, 0.0);
+
+    float ambientStrength = 1.2;
+    float3 lightColor = float3(1.0, 1.0, 1.0);
+    float3 ambientLevel = ambientStrength * lightColor;
+    float3 norm = normalize(interpolated.normal);
+    float3 lightdir = normalize(lightpos - interpolated.fragPos);
+    float diff = max(dot(norm, lightdir), 0.0);
+    float3 diffuseLevel = diff * lightColor;
+    float4 texColor = tex2D.sample(textureSampler,
+                                   float2(interpolated.texcoord.x, 1.0 - interpolated.texcoord.y));
+    float3 result = (ambientLevel + diffuseLevel) * float3(diffuse) * texColor.rgb;
+    return float4(result[0], result[1], result[2], texColor.a);
+}
+";
+
+#[cfg(target_os = "windows")]
+pub const OBJ_MODEL_TEXTURED_FRAGMENT_SHADER: &str = "
+Texture2D tex;
+
+SamplerState splr;
+
+struct VSOut {
+    float4 position : SV_POSITION;
+    float3 fragPos  : POSITION;
+    float2 texcoord : TEXCOORD0;
+    float3 normal : NORMAL;
+};
+
+cbuffer B1 : register(b0)
+{
+    float3 ambient;
+};
+
+cbuffer B2 : register(b1)
+{
+    float3 diffuse;
+};
+
+cbuffer B3 : register(b2)
+{
+    float3 lightpos;
+};
+
+float4 PSMain(VSOut vsout) : SV_Target
+{
+    float ambientStrength = 1.2;
+    float3 lightColor = float3(1.0, 1.0, 1.0);
+    float3 ambientLevel = ambientStrength * lightColor;
+    float3 norm = normalize(vsout.normal);
+    float3 lightdir = normalize(lightpos - vsout.fragPos);
+    float diff = max(dot(norm, lightdir), 0.0);
+    float3 diffuseLevel = diff * lightColor;
+    float4 texColor = tex.Sample(splr, float2(vsout.texcoord.x, 1.0 - vsout.texcoord.y));
+    float3 result = (ambientLevel + diffuseLevel) * diffuse * texColor.rgb;
+    return float4(result[0], result[1], result[2], texColor.a);
+}
+";
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub const OBJ_MODEL_TEXTURED_FRAGMENT_SHADER: &str = "obj_model_textured";
@@ -93,3 +93,9 @@ float4 PSMain() : SV_Target
     return float4(color[0], color[1], color[2], color[3]);
 }
 ";
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub const QUAD_VERTEX_SHADER: &str = "quad";
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub const QUAD_FRAGMENT_SHADER: &str = "quad";
@@ -0,0 +1,119 @@
+use crate::buffers::{BufferElement, BufferLayout, ShaderDataType};
+use crate::shaders::{Shader, ShaderUniformSpec};
+use crate::Renderer;
+
+pub fn sprite_shader_layout() -> BufferLayout {
+    return BufferLayout::new(vec![
+        BufferElement::new("position", ShaderDataType::Float2),
+        BufferElement::new("texCoord", ShaderDataType::Float2),
+        BufferElement::new("tint", ShaderDataType::Float4),
+    ]);
+}
+
+pub fn sprite_shader(renderer: &Renderer) -> Shader {
+    let shader = Shader::new(
+        renderer,
+        sprite_shader_layout(),
+        SPRITE_VERTEX_SHADER,
+        SPRITE_FRAGMENT_SHADER,
+        ShaderUniformSpec { uniforms: vec![] },
+        ShaderUniformSpec { uniforms: vec![] },
+    );
+    return shader;
+}
+
+#[cfg(target_os = "macos")]
+pub const SPRITE_VERTEX_SHADER: &str = "
+using namespace metal;
+
+struct ViewProjection {
+    float4x4 vp;
+};
+
+struct VertexOut {
+    float4 position [[position]];
+    float2 texCoord;
+    float4 tint;
+};
+
+struct VertexIn {
+    float2 position   [[attribute(0)]];
+    float2 texCoord   [[attribute(1)]];
+    float4 tint       [[attribute(2)]];
+};
+
+vertex VertexOut vertex_main(VertexIn vIn [[stage_in]],
+                                constant ViewProjection& viewProjection [[buffer(1)]]) {
+    VertexOut VertexOut;
+    VertexOut.position = viewProjection.vp * float4(vIn.position, 0.0, 1.0);
+    VertexOut.texCoord = vIn.texCoord;
+    VertexOut.tint = vIn.tint;
+    return VertexOut;
+}
+";
+
+#[cfg(target_os = "windows")]
+pub const SPRITE_VERTEX_SHADER: &str = "
+struct VertexIn {
+    float2 position : Position;
+    float2 texCoord : TexCoord;
+    float4 tint : Tint;
+};
+
+struct VSOut {
+    float2 texCoord : TEXCOORD;
+    float4 tint : COLOR;
+    float4 position : SV_POSITION;
+};
+
+cbuffer CBuf1
+{
+    matrix projection;
+};
+
+VSOut VSMain(VertexIn vIn)
+{
+    VSOut vso;
+    vso.position = mul(projection, float4(vIn.position.x, vIn.position.y, 0.0f, 1.0f));
+    vso.texCoord = vIn.texCoord;
+    vso.tint = vIn.tint;
+    return vso;
+}
+";
+
+#[cfg(target_os = "macos")]
+pub const SPRITE_FRAGMENT_SHADER: &str = "
+using namespace metal;
+
+struct VertexOut {
+    float4 position [[position]];
+    float2 texCoord;
+    float4 tint;
+};
+
+fragment float4 fragment_main(VertexOut interpolated [[stage_in]],
+                                texture2d<float> tex2D [[ texture(0) ]])
+{
+    constexpr sampler textureSampler (mag_filter::linear,
+                                      min_filter::linear);
+    return interpolated.tint * tex2D.sample(textureSampler, interpolated.texCoord);
+}
+";
+
+#[cfg(target_os = "windows")]
+pub const SPRITE_FRAGMENT_SHADER: &str = "
+Texture2D tex;
+
+SamplerState splr;
+
+float4 PSMain(float2 tc : TEXCOORD, float4 tint : COLOR) : SV_Target
+{
+    return tint * tex.Sample(splr, tc);
+}
+";
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub const SPRITE_VERTEX_SHADER: &str = "sprite";
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub const SPRITE_FRAGMENT_SHADER: &str = "sprite";
@@ -1,4 +1,5 @@
 use crate::buffers::{BufferElement, BufferLayout, ShaderDataType};
+use crate::shader_lib::instance_transform_layout;
 use crate::shaders::{Shader, ShaderUniformSpec};
 use crate::Renderer;
 
@@ -21,6 +22,20 @@ pub fn texture_shader(renderer: &Renderer) -> Shader {
     return shader;
 }
 
+/// The texture shader with the transform read per instance instead of from vertex slot 1.
+pub fn texture_instanced_shader(renderer: &Renderer) -> Shader {
+    let shader = Shader::new_instanced(
+        renderer,
+        texture_shader_layout(),
+        instance_transform_layout(),
+        TEXTURE_INSTANCED_VERTEX_SHADER,
+        TEXTURE_FRAGMENT_SHADER,
+        ShaderUniformSpec { uniforms: vec![] },
+        ShaderUniformSpec { uniforms: vec![] },
+    );
+    return shader;
+}
+
 #[cfg(target_os = "macos")]
 pub const TEXTURE_VERTEX_SHADER: &str = "
 using namespace metal;
@@ -80,6 +95,74 @@ VSOut VSMain(float4 pos : Position, float2 tex : TexCoord)
 }
 ";
 
+#[cfg(target_os = "macos")]
+pub const TEXTURE_INSTANCED_VERTEX_SHADER: &str = "
+using namespace metal;
+
+struct ViewProjection {
+    float4x4 vp;
+};
+
+struct VertexOut {
+    float4 position [[position]];
+    float2 texCoord;
+};
+
+struct VertexIn {
+    float4 position   [[attribute(0)]];
+    float2 texCoord   [[attribute(1)]];
+    float4 transformX [[attribute(2)]];
+    float4 transformY [[attribute(3)]];
+    float4 transformZ [[attribute(4)]];
+    float4 transformW [[attribute(5)]];
+};
+
+vertex VertexOut vertex_main(VertexIn vIn [[stage_in]],
+                                constant ViewProjection& viewProjection [[buffer(1)]]) {
+    VertexOut VertexOut;
+    float4x4 transform = float4x4(vIn.transformX, vIn.transformY, vIn.transformZ, vIn.transformW);
+    VertexOut.position = viewProjection.vp * transform * float4(vIn.position);
+    VertexOut.texCoord = vIn.texCoord;
+    return VertexOut;
+}
+";
+
+#[cfg(target_os = "windows")]
+pub const TEXTURE_INSTANCED_VERTEX_SHADER: &str = "
+struct VSOut {
+    float2 texCoord : TEXCOORD;
+    float4 position : SV_POSITION;
+};
+
+struct VertexIn {
+    float4 pos : Position;
+    float2 tex : TexCoord;
+    float4 transformX : TRANSFORM_X;
+    float4 transformY : TRANSFORM_Y;
+    float4 transformZ : TRANSFORM_Z;
+    float4 transformW : TRANSFORM_W;
+};
+
+cbuffer CBuf1
+{
+    matrix projection;
+};
+
+VSOut VSMain(VertexIn vIn)
+{
+    VSOut vso;
+    float4x4 transformation = transpose(float4x4(
+        vIn.transformX, vIn.transformY, vIn.transformZ, vIn.transformW));
+    float4x4 worldViewProj = mul(projection, transformation);
+    vso.position = mul(worldViewProj, float4(vIn.pos.x, vIn.pos.y, 0.0f, 1.0f));
+    vso.texCoord = vIn.tex;
+    return vso;
+}
+";
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub const TEXTURE_INSTANCED_VERTEX_SHADER: &str = "texture_instanced";
+
 #[cfg(target_os = "macos")]
 pub const TEXTURE_FRAGMENT_SHADER: &str = "
 using namespace metal;
@@ -110,3 +193,9 @@ float4 PSMain(float2 tc : TEXCOORD) : SV_Target
     return tex.Sample(splr, tc);
 }
 ";
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub const TEXTURE_VERTEX_SHADER: &str = "texture";
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub const TEXTURE_FRAGMENT_SHADER: &str = "texture";
@@ -16,6 +16,7 @@ pub enum UniformType {
     Float,
 }
 
+#[allow(dead_code)]
 pub struct UniformSpec {
     name: &'static str,
     uniform_type: UniformType,
@@ -25,6 +26,7 @@ pub struct ShaderUniformSpec {
     pub uniforms: Vec<UniformSpec>,
 }
 
+#[allow(dead_code)]
 pub struct VertexShader {
     platform_vertex_shader: PlatformVertexShader,
     layout: BufferLayout,
@@ -67,6 +69,7 @@ impl VertexShader {
             platform_vertex_shader: PlatformVertexShader::new(
                 &renderer.platform_renderer,
                 &layout,
+                None,
                 vertex_src,
             ),
             layout,
@@ -83,6 +86,7 @@ impl VertexShader {
     }
 }
 
+#[allow(dead_code)]
 pub struct FragmentShader {
     platform_fragment_shader: PlatformFragmentShader,
     uniform_spec: ShaderUniformSpec,
@@ -153,13 +157,36 @@ impl Shader {
         layout: BufferLayout,
         vertex_src: &str,
         fragment_src: &str,
-        vertex_uniform_spec: ShaderUniformSpec,
-        fragment_uniform_spec: ShaderUniformSpec,
+        _vertex_uniform_spec: ShaderUniformSpec,
+        _fragment_uniform_spec: ShaderUniformSpec,
     ) -> Shader {
         return Shader {
             platform_shader: PlatformShader::new(
                 &renderer.platform_renderer,
                 layout,
+                None,
+                vertex_src,
+                fragment_src,
+            ),
+        };
+    }
+
+    /// A shader that also reads `instance_layout` from the buffer bound to the instance slot,
+    /// for drawing with `Scene::draw_indexed_tris_instanced`.
+    pub fn new_instanced(
+        renderer: &Renderer,
+        layout: BufferLayout,
+        instance_layout: BufferLayout,
+        vertex_src: &str,
+        fragment_src: &str,
+        _vertex_uniform_spec: ShaderUniformSpec,
+        _fragment_uniform_spec: ShaderUniformSpec,
+    ) -> Shader {
+        return Shader {
+            platform_shader: PlatformShader::new(
+                &renderer.platform_renderer,
+                layout,
+                Some(instance_layout),
                 vertex_src,
                 fragment_src,
             ),
@@ -173,16 +200,16 @@ impl Shader {
     pub fn upload_vertex_uniform(&self, scene: &Scene, offset: u32, uniform: ShaderUniform) {
         match uniform {
             ShaderUniform::Mat4(m) => self.upload_vertex_uniform_mat4(scene, offset, m),
-            ShaderUniform::Float4(f) => {}
-            ShaderUniform::Float3(f) => {}
-            ShaderUniform::Float2(f) => {}
+            ShaderUniform::Float4(_f) => {}
+            ShaderUniform::Float3(_f) => {}
+            ShaderUniform::Float2(_f) => {}
             ShaderUniform::Float(f) => self.upload_vertex_uniform_float(scene, offset, f),
         }
     }
 
     pub fn upload_fragment_uniform(&self, scene: &Scene, offset: u32, uniform: ShaderUniform) {
         match uniform {
-            ShaderUniform::Mat4(m) => {}
+            ShaderUniform::Mat4(_m) => {}
             ShaderUniform::Float4(f) => self.upload_fragment_uniform_float4(scene, offset, f),
             ShaderUniform::Float3(f) => self.upload_fragment_uniform_float3(scene, offset, f),
             ShaderUniform::Float2(f) => self.upload_fragment_uniform_float2(scene, offset, f),
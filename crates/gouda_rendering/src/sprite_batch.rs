@@ -0,0 +1,306 @@
+use std::cmp::Ordering;
+
+use cgmath::{Deg, Matrix4, Vector4};
+
+use crate::buffers::{IndexBuffer, VertexBuffer};
+use crate::shader_lib::sprite_shader::sprite_shader_layout;
+use crate::{Scene, Texture};
+
+/// Indices are 16 bit, so a single draw can address at most this many four-vertex quads.
+const MAX_SPRITES_PER_DRAW: usize = u16::MAX as usize / 4;
+
+/// A region of a texture in normalized coordinates, with (0, 0) at the top left.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UvRect {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+}
+
+impl UvRect {
+    pub const FULL: UvRect = UvRect {
+        x: 0.,
+        y: 0.,
+        w: 1.,
+        h: 1.,
+    };
+
+    pub fn new(x: f32, y: f32, w: f32, h: f32) -> UvRect {
+        return UvRect { x, y, w, h };
+    }
+}
+
+/// One textured quad. The quad is the unit square centred on the origin, rotated by `rotation`
+/// degrees counter-clockwise and then placed by `transform`.
+#[derive(Debug, Clone, Copy)]
+pub struct Sprite {
+    pub transform: Matrix4<f32>,
+    pub rotation: f32,
+    pub uv: UvRect,
+    pub tint: [f32; 4],
+    pub depth: f32,
+}
+
+impl Sprite {
+    pub fn new(transform: Matrix4<f32>) -> Sprite {
+        return Sprite {
+            transform,
+            rotation: 0.,
+            uv: UvRect::FULL,
+            tint: [1., 1., 1., 1.],
+            depth: 0.,
+        };
+    }
+
+    pub fn rotation(mut self, degrees: f32) -> Sprite {
+        self.rotation = degrees;
+        return self;
+    }
+
+    pub fn uv(mut self, uv: UvRect) -> Sprite {
+        self.uv = uv;
+        return self;
+    }
+
+    pub fn tint(mut self, tint: [f32; 4]) -> Sprite {
+        self.tint = tint;
+        return self;
+    }
+
+    /// Sprites with a greater depth are drawn first, so they end up behind the others.
+    pub fn depth(mut self, depth: f32) -> Sprite {
+        self.depth = depth;
+        return self;
+    }
+
+    /// Bottom left, bottom right, top right, top left, each as position, uv and tint.
+    fn vertices(&self) -> [[f32; 8]; 4] {
+        let transform = self.transform * Matrix4::from_angle_z(Deg(self.rotation));
+        let uv = self.uv;
+        let corners = [
+            (-0.5, -0.5, uv.x, uv.y + uv.h),
+            (0.5, -0.5, uv.x + uv.w, uv.y + uv.h),
+            (0.5, 0.5, uv.x + uv.w, uv.y),
+            (-0.5, 0.5, uv.x, uv.y),
+        ];
+        let t = self.tint;
+        return corners.map(|(x, y, u, v)| {
+            let p = transform * Vector4::new(x, y, 0., 1.);
+            [p.x, p.y, u, v, t[0], t[1], t[2], t[3]]
+        });
+    }
+}
+
+/// Collects sprites over a frame and draws them with as few draw calls as possible. Sprites are
+/// drawn back to front by depth, and sprites at the same depth are grouped by texture, so each
+/// texture at each depth costs one draw. Sprites that share a texture and depth are drawn in the
+/// order they were pushed; overlapping sprites with different textures need different depths to
+/// be layered reliably.
+#[derive(Default)]
+pub struct SpriteBatch<'t> {
+    textures: Vec<&'t Texture>,
+    sprites: Vec<(usize, Sprite)>,
+}
+
+impl<'t> SpriteBatch<'t> {
+    pub fn new() -> SpriteBatch<'t> {
+        return SpriteBatch {
+            textures: vec![],
+            sprites: vec![],
+        };
+    }
+
+    pub fn push(&mut self, texture: &'t Texture, sprite: Sprite) {
+        let index = match self.textures.iter().position(|t| std::ptr::eq(*t, texture)) {
+            Some(index) => index,
+            None => {
+                self.textures.push(texture);
+                self.textures.len() - 1
+            }
+        };
+        self.sprites.push((index, sprite));
+    }
+
+    pub fn len(&self) -> usize {
+        return self.sprites.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.sprites.is_empty();
+    }
+
+    /// Draws every queued sprite with the scene's camera and empties the batch. Returns the number
+    /// of draw calls issued.
+    pub fn flush(&mut self, scene: &Scene) -> usize {
+        if self.sprites.is_empty() {
+            return 0;
+        }
+        scene.flush();
+
+        self.sprites.sort_by(|(ta, a), (tb, b)| {
+            return b
+                .depth
+                .partial_cmp(&a.depth)
+                .unwrap_or(Ordering::Equal)
+                .then(ta.cmp(tb));
+        });
+
+        let runs: Vec<&[(usize, Sprite)]> = self
+            .sprites
+            .chunk_by(|(ta, a), (tb, b)| ta == tb && a.depth == b.depth)
+            .flat_map(|run| run.chunks(MAX_SPRITES_PER_DRAW))
+            .collect();
+
+        let largest = runs.iter().map(|run| run.len()).max().unwrap_or(0);
+        let indices = (0..largest as u16)
+            .flat_map(|i| {
+                let base = i * 4;
+                [base, base + 1, base + 2, base, base + 2, base + 3]
+            })
+            .collect();
+        let index_buffer = IndexBuffer::new(scene.renderer, indices);
+
+        let shader = scene.renderer.get_shader("sprite");
+        shader.bind(scene);
+        shader.upload_vertex_uniform_mat4(scene, 0, scene.camera_view_projection_matrix);
+
+        for run in &runs {
+            let vertices: Vec<[f32; 8]> = run
+                .iter()
+                .flat_map(|(_, sprite)| sprite.vertices())
+                .collect();
+            let vertex_buffer =
+                VertexBuffer::new(scene.renderer, sprite_shader_layout(), 0, vertices);
+
+            self.textures[run[0].0].bind(scene);
+            vertex_buffer.bind(scene);
+            index_buffer.bind(scene);
+            scene.draw_indexed_tris(run.len() as u64 * 6, &index_buffer);
+        }
+
+        let draws = runs.len();
+        self.textures.clear();
+        self.sprites.clear();
+        return draws;
+    }
+}
+
+#[cfg(all(test, not(any(target_os = "macos", target_os = "windows"))))]
+mod tests {
+    use super::*;
+    use crate::Renderer;
+    use cgmath::{SquareMatrix, Vector3};
+    use gouda_images::Image;
+
+    fn solid(renderer: &Renderer, color: [u8; 4]) -> Texture {
+        let image = Image {
+            width: 1,
+            height: 1,
+            data: Image::data_from_raw_pixels(1, 1, color.to_vec()),
+        };
+        return Texture::new(renderer, &image);
+    }
+
+    fn at(x: f32, y: f32) -> Matrix4<f32> {
+        return Matrix4::from_translation(Vector3::new(x, y, 0.)) * Matrix4::from_scale(0.5);
+    }
+
+    #[test]
+    fn test_groups_sprites_by_texture() {
+        let renderer = Renderer::new_headless(16, 16);
+        let red = solid(&renderer, [255, 0, 0, 255]);
+        let blue = solid(&renderer, [0, 0, 255, 255]);
+
+        let mut batch = SpriteBatch::new();
+        for i in 0..4 {
+            let texture = if i % 2 == 0 { &red } else { &blue };
+            batch.push(texture, Sprite::new(at(-0.75 + i as f32 * 0.5, 0.)));
+        }
+
+        let scene = renderer.begin_scene().unwrap();
+        assert_eq!(batch.flush(&scene), 2);
+        renderer.end_scene(scene);
+        assert!(batch.is_empty());
+
+        let framebuffer = renderer.platform_renderer.framebuffer();
+        assert_eq!(framebuffer.pixel(2, 8), [255, 0, 0, 255]);
+        assert_eq!(framebuffer.pixel(6, 8), [0, 0, 255, 255]);
+        assert_eq!(framebuffer.pixel(10, 8), [255, 0, 0, 255]);
+        assert_eq!(framebuffer.pixel(14, 8), [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn test_draws_back_to_front() {
+        let renderer = Renderer::new_headless(16, 16);
+        let red = solid(&renderer, [255, 0, 0, 255]);
+        let blue = solid(&renderer, [0, 0, 255, 255]);
+
+        let mut batch = SpriteBatch::new();
+        batch.push(&red, Sprite::new(Matrix4::identity()).depth(0.));
+        batch.push(&blue, Sprite::new(Matrix4::identity()).depth(1.));
+
+        let scene = renderer.begin_scene().unwrap();
+        assert_eq!(batch.flush(&scene), 2);
+        renderer.end_scene(scene);
+
+        assert_eq!(
+            renderer.platform_renderer.framebuffer().pixel(8, 8),
+            [255, 0, 0, 255]
+        );
+    }
+
+    #[test]
+    fn test_uv_rect_and_rotation() {
+        let renderer = Renderer::new_headless(16, 16);
+        let raw = vec![
+            0, 255, 0, 255, 255, 0, 0, 255, 255, 0, 0, 255, 0, 0, 255, 255, 0, 0, 255, 255, 0, 255,
+            0, 255,
+        ];
+        let image = Image {
+            width: 6,
+            height: 1,
+            data: Image::data_from_raw_pixels(6, 1, raw),
+        };
+        let texture = Texture::new(&renderer, &image);
+
+        // Only the red and blue middle of the texture is used, turned a half turn so blue is on
+        // the left
+        let mut batch = SpriteBatch::new();
+        batch.push(
+            &texture,
+            Sprite::new(Matrix4::from_scale(2.))
+                .uv(UvRect::new(1. / 6., 0., 4. / 6., 1.))
+                .rotation(180.),
+        );
+
+        let scene = renderer.begin_scene().unwrap();
+        batch.flush(&scene);
+        renderer.end_scene(scene);
+
+        let framebuffer = renderer.platform_renderer.framebuffer();
+        assert_eq!(framebuffer.pixel(2, 8), [0, 0, 255, 255]);
+        assert_eq!(framebuffer.pixel(13, 8), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_tint_multiplies_texture() {
+        let renderer = Renderer::new_headless(16, 16);
+        let white = solid(&renderer, [255, 255, 255, 255]);
+
+        let mut batch = SpriteBatch::new();
+        batch.push(
+            &white,
+            Sprite::new(Matrix4::identity()).tint([0., 1., 1., 1.]),
+        );
+
+        let scene = renderer.begin_scene().unwrap();
+        batch.flush(&scene);
+        renderer.end_scene(scene);
+
+        assert_eq!(
+            renderer.platform_renderer.framebuffer().pixel(8, 8),
+            [0, 255, 255, 255]
+        );
+    }
+}
@@ -1,34 +1,50 @@
 use std::rc::Rc;
 
 use gouda_ecs::ECS;
-use gouda_images::png::PNG;
-use gouda_images::spritesheet::Spritesheet;
 use gouda_transform::TransformComponent;
 
-use crate::{Scene, Texture};
+use crate::sprite_batch::{Sprite, SpriteBatch, UvRect};
+use crate::texture_library::TextureHandle;
+use crate::Scene;
 
 use super::Renderer;
 
 #[derive(Debug)]
 pub struct SpriteComponent {
-    texture: Texture,
+    texture: TextureHandle,
+    pub depth: f32,
 }
 
 impl SpriteComponent {
     pub fn new(ecs: &mut ECS, sprite_name: String) -> SpriteComponent {
         let renderer = ecs.read_res::<Rc<Renderer>>();
-        let texture = Texture::new(renderer, &PNG::from_file(&sprite_name).unwrap().image());
-        return SpriteComponent { texture };
+        let texture = renderer.load_texture(&sprite_name);
+        return SpriteComponent { texture, depth: 0. };
+    }
+
+    /// Sprites with a greater depth are drawn behind the others when batched.
+    pub fn with_depth(mut self, depth: f32) -> SpriteComponent {
+        self.depth = depth;
+        return self;
     }
 
     pub fn draw(&self, scene: &Scene, location: &TransformComponent) {
         scene.submit_texture(&self.texture, location.transform_matrix())
     }
+
+    pub fn batch<'a>(&'a self, batch: &mut SpriteBatch<'a>, location: &TransformComponent) {
+        batch.push(
+            &self.texture,
+            Sprite::new(location.transform_matrix()).depth(self.depth),
+        );
+    }
 }
 
+/// Frames are cut out of a single texture, in rows from the top left.
 #[derive(Debug)]
 pub struct SpriteSheetComponent {
-    textures: Vec<Texture>,
+    texture: TextureHandle,
+    frames: Vec<UvRect>,
     pub active: usize,
 }
 
@@ -40,30 +56,38 @@ impl SpriteSheetComponent {
         columns: usize,
     ) -> SpriteSheetComponent {
         let renderer = ecs.read_res::<Rc<Renderer>>();
+        let texture = renderer.load_texture(&spritesheet_name);
 
-        let png = PNG::from_file(&spritesheet_name);
-        let sheet = Spritesheet::new(rows, columns, png.unwrap().image());
-
-        let mut all_textures = vec![];
+        let (w, h) = (1. / columns as f32, 1. / rows as f32);
+        let mut frames = vec![];
         for i in 0..rows {
             for j in 0..columns {
-                let texture = Texture::new(renderer, &sheet.sprite(j, i));
-                all_textures.push(texture);
+                frames.push(UvRect::new(j as f32 * w, i as f32 * h, w, h));
             }
         }
         return SpriteSheetComponent {
-            textures: all_textures,
+            texture,
+            frames,
             active: 0,
         };
     }
 
     pub fn draw(&self, scene: &Scene, location: &TransformComponent) {
-        let texture = self.textures.get(self.active).unwrap();
-        scene.submit_texture(&texture, location.transform_matrix())
+        let mut batch = SpriteBatch::new();
+        self.batch(&mut batch, location);
+        batch.flush(scene);
+    }
+
+    pub fn batch<'a>(&'a self, batch: &mut SpriteBatch<'a>, location: &TransformComponent) {
+        let frame = *self.frames.get(self.active).unwrap();
+        batch.push(
+            &self.texture,
+            Sprite::new(location.transform_matrix()).uv(frame),
+        );
     }
 
     pub fn next(&mut self) {
-        if self.active == self.textures.len() - 1 {
+        if self.active == self.frames.len() - 1 {
             self.active = 0;
         } else {
             self.active += 1;
@@ -73,27 +97,31 @@ impl SpriteSheetComponent {
 
 #[derive(Debug)]
 pub struct SpriteListComponent {
-    textures: Vec<Texture>,
+    textures: Vec<TextureHandle>,
     active: usize,
 }
 
 impl SpriteListComponent {
     pub fn new(ecs: &mut ECS, sprite_names: Vec<String>) -> SpriteListComponent {
-        let mut all_textures = vec![];
-        for sprite_name in sprite_names {
-            let renderer = ecs.read_res::<Rc<Renderer>>();
-            let texture = Texture::new(renderer, &PNG::from_file(&sprite_name).unwrap().image());
-            all_textures.push(texture);
-        }
+        let renderer = ecs.read_res::<Rc<Renderer>>();
+        let textures = sprite_names
+            .iter()
+            .map(|sprite_name| renderer.load_texture(sprite_name))
+            .collect();
         return SpriteListComponent {
-            textures: all_textures,
+            textures,
             active: 0,
         };
     }
 
     pub fn draw(&self, scene: &Scene, location: &TransformComponent) {
         let texture = self.textures.get(self.active).unwrap();
-        scene.submit_texture(&texture, location.transform_matrix())
+        scene.submit_texture(texture, location.transform_matrix())
+    }
+
+    pub fn batch<'a>(&'a self, batch: &mut SpriteBatch<'a>, location: &TransformComponent) {
+        let texture = self.textures.get(self.active).unwrap();
+        batch.push(texture, Sprite::new(location.transform_matrix()));
     }
 }
 
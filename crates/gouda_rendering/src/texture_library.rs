@@ -1,3 +1,240 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::ops::Deref;
+use std::rc::Rc;
+
+use gouda_images::atlas::Atlas;
+use gouda_images::png::PNG;
+use gouda_images::Image;
+
+use crate::sprite_batch::UvRect;
+use crate::{Renderer, Texture};
+
 pub type TextureKey = String;
 
-pub struct TextureLibrary {}
+/// A shared reference to a texture owned by the library. Cloning one is cheap and every clone
+/// draws from the same GPU texture.
+#[derive(Debug, Clone)]
+pub struct TextureHandle {
+    texture: Rc<Texture>,
+}
+
+impl TextureHandle {
+    pub fn new(texture: Texture) -> TextureHandle {
+        return TextureHandle {
+            texture: Rc::new(texture),
+        };
+    }
+}
+
+impl Deref for TextureHandle {
+    type Target = Texture;
+
+    fn deref(&self) -> &Texture {
+        return &self.texture;
+    }
+}
+
+impl PartialEq for TextureHandle {
+    fn eq(&self, other: &Self) -> bool {
+        return Rc::ptr_eq(&self.texture, &other.texture);
+    }
+}
+
+/// Returned when the file at `path` can't be opened or decoded as a PNG.
+#[derive(Debug)]
+pub struct TextureLoadError {
+    pub path: String,
+}
+
+impl fmt::Display for TextureLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Failed to load texture {}", self.path)
+    }
+}
+
+impl Error for TextureLoadError {}
+
+/// An atlas uploaded as a single texture, with the UV rect of each packed image by name.
+#[derive(Debug, Clone)]
+pub struct TextureAtlas {
+    pub texture: TextureHandle,
+    uvs: Rc<HashMap<String, UvRect>>,
+}
+
+impl TextureAtlas {
+    pub fn uv(&self, name: &str) -> Option<UvRect> {
+        return self.uvs.get(name).copied();
+    }
+}
+
+/// Textures keyed by the path they were loaded from, or by a name for images built at runtime, so
+/// each is decoded and uploaded once no matter how many sprites use it.
+#[derive(Debug, Default)]
+pub struct TextureLibrary {
+    textures: HashMap<TextureKey, TextureHandle>,
+    atlases: HashMap<TextureKey, TextureAtlas>,
+}
+
+impl TextureLibrary {
+    pub fn new() -> TextureLibrary {
+        return TextureLibrary {
+            textures: HashMap::new(),
+            atlases: HashMap::new(),
+        };
+    }
+
+    /// Returns the texture for the PNG at `path`, loading it the first time it is asked for.
+    pub fn load(
+        &mut self,
+        renderer: &Renderer,
+        path: &str,
+    ) -> Result<TextureHandle, TextureLoadError> {
+        if let Some(handle) = self.textures.get(path) {
+            return Ok(handle.clone());
+        }
+        let png = PNG::from_file(path).ok_or_else(|| TextureLoadError {
+            path: path.to_string(),
+        })?;
+        return Ok(self.add(renderer, path, &png.image()));
+    }
+
+    /// Uploads `image` under `key`, replacing anything previously stored there.
+    pub fn add(&mut self, renderer: &Renderer, key: &str, image: &Image) -> TextureHandle {
+        let handle = TextureHandle::new(Texture::new(renderer, image));
+        self.textures.insert(key.to_string(), handle.clone());
+        return handle;
+    }
+
+    pub fn get(&self, key: &str) -> Option<TextureHandle> {
+        return self.textures.get(key).cloned();
+    }
+
+    /// Uploads a packed atlas under `key`. Its texture is also available through `get`.
+    pub fn add_atlas(&mut self, renderer: &Renderer, key: &str, atlas: &Atlas) -> TextureAtlas {
+        let texture = self.add(renderer, key, &atlas.image);
+        let uvs = atlas
+            .names()
+            .map(|name| {
+                let [x, y, w, h] = atlas.uv(name).unwrap();
+                (name.clone(), UvRect::new(x, y, w, h))
+            })
+            .collect();
+        let atlas = TextureAtlas {
+            texture,
+            uvs: Rc::new(uvs),
+        };
+        self.atlases.insert(key.to_string(), atlas.clone());
+        return atlas;
+    }
+
+    pub fn get_atlas(&self, key: &str) -> Option<TextureAtlas> {
+        return self.atlases.get(key).cloned();
+    }
+
+    pub fn len(&self) -> usize {
+        return self.textures.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.textures.is_empty();
+    }
+}
+
+#[cfg(all(test, not(any(target_os = "macos", target_os = "windows"))))]
+mod tests {
+    use super::*;
+    use crate::sprite_batch::{Sprite, SpriteBatch};
+    use cgmath::Matrix4;
+    use gouda_images::atlas::AtlasBuilder;
+
+    fn asset(name: &str) -> String {
+        return format!(
+            "{}/../../assets/bitmap/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            name
+        );
+    }
+
+    fn solid(width: usize, height: usize, color: [u8; 4]) -> Image {
+        return Image {
+            width,
+            height,
+            data: Image::data_from_raw_pixels(width, height, color.repeat(width * height)),
+        };
+    }
+
+    #[test]
+    fn test_load_deduplicates_by_path() {
+        let renderer = Renderer::new_headless(16, 16);
+        let grass = renderer.load_texture(&asset("grass.png"));
+        let again = renderer.load_texture(&asset("grass.png"));
+        let tree = renderer.load_texture(&asset("tree.png"));
+
+        assert!(grass == again);
+        assert!(grass != tree);
+        assert_eq!(renderer.texture_lib.borrow().len(), 2);
+    }
+
+    #[test]
+    fn test_missing_file_is_an_error() {
+        let renderer = Renderer::new_headless(16, 16);
+        let missing = asset("missing.png");
+        let err = renderer.try_load_texture(&missing).unwrap_err();
+        assert_eq!(err.path, missing);
+        assert!(renderer.texture_lib.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_shared_handles_batch_together() {
+        let renderer = Renderer::new_headless(16, 16);
+        let handles: Vec<TextureHandle> = (0..10)
+            .map(|_| renderer.load_texture(&asset("grass.png")))
+            .collect();
+
+        let mut batch = SpriteBatch::new();
+        for handle in &handles {
+            batch.push(handle, Sprite::new(Matrix4::from_scale(0.1)));
+        }
+        let scene = renderer.begin_scene().unwrap();
+        assert_eq!(batch.flush(&scene), 1);
+        renderer.end_scene(scene);
+    }
+
+    #[test]
+    fn test_atlas_uvs_select_images() {
+        let renderer = Renderer::new_headless(16, 16);
+        let atlas = AtlasBuilder::new(1)
+            .add("red", solid(4, 4, [255, 0, 0, 255]))
+            .add("blue", solid(4, 4, [0, 0, 255, 255]))
+            .build()
+            .unwrap();
+        let atlas = renderer
+            .texture_lib
+            .borrow_mut()
+            .add_atlas(&renderer, "colors", &atlas);
+        assert!(renderer.texture_lib.borrow().get_atlas("colors").is_some());
+        assert!(atlas.uv("green").is_none());
+
+        let mut batch = SpriteBatch::new();
+        let left = Matrix4::from_translation([-0.5, 0., 0.].into());
+        let right = Matrix4::from_translation([0.5, 0., 0.].into());
+        batch.push(
+            &atlas.texture,
+            Sprite::new(left).uv(atlas.uv("red").unwrap()),
+        );
+        batch.push(
+            &atlas.texture,
+            Sprite::new(right).uv(atlas.uv("blue").unwrap()),
+        );
+
+        let scene = renderer.begin_scene().unwrap();
+        assert_eq!(batch.flush(&scene), 1);
+        renderer.end_scene(scene);
+
+        let framebuffer = renderer.platform_renderer.framebuffer();
+        assert_eq!(framebuffer.pixel(4, 8), [255, 0, 0, 255]);
+        assert_eq!(framebuffer.pixel(11, 8), [0, 0, 255, 255]);
+    }
+}
@@ -0,0 +1,125 @@
+use crate::TransformComponent;
+use cgmath::{Matrix4, SquareMatrix, Vector3};
+use gouda_ecs::commands::Commands;
+use gouda_ecs::query::Without;
+use gouda_ecs::{ChildOfComponent, Entity, HasChildrenComponent, Mutations, ECS};
+
+/// An entity's transform in world space: its own `TransformComponent` combined with those of all
+/// of its ancestors. Kept up to date by `transform_propagation_system`.
+#[derive(Debug, Clone, Copy)]
+pub struct GlobalTransform {
+    pub matrix: Matrix4<f32>,
+}
+
+impl GlobalTransform {
+    pub fn position(&self) -> Vector3<f32> {
+        return self.matrix.w.truncate();
+    }
+}
+
+/// Computes a `GlobalTransform` for every entity with a `TransformComponent`. Entities in the
+/// hierarchy without a transform pass their parent's through unchanged.
+pub fn transform_propagation_system(ecs: &ECS, _dt: f32) -> Mutations {
+    let mut stack: Vec<(Entity, Matrix4<f32>)> = vec![];
+    for (entity, transform, _) in
+        ecs.query::<(Entity, &TransformComponent, Without<ChildOfComponent>)>()
+    {
+        stack.push((entity, transform.transform_matrix()));
+    }
+    for (entity, _, _, _) in ecs.query::<(
+        Entity,
+        &HasChildrenComponent,
+        Without<TransformComponent>,
+        Without<ChildOfComponent>,
+    )>() {
+        stack.push((entity, Matrix4::identity()));
+    }
+
+    let mut globals = vec![];
+    while let Some((entity, matrix)) = stack.pop() {
+        if ecs.read::<TransformComponent>(&entity).is_some() {
+            globals.push((entity, matrix));
+        }
+        for child in ecs.children(&entity) {
+            let child_matrix = match ecs.read::<TransformComponent>(child) {
+                Some(local) => matrix * local.transform_matrix(),
+                None => matrix,
+            };
+            stack.push((*child, child_matrix));
+        }
+    }
+
+    let mut commands = Commands::new();
+    commands.add(move |ecs, _| {
+        for (entity, matrix) in globals {
+            match ecs.write::<GlobalTransform>(&entity) {
+                Some(global) => global.matrix = matrix,
+                None => ecs.add_component(&entity, GlobalTransform { matrix }),
+            }
+        }
+    });
+    return commands.into();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(ecs: &mut ECS) {
+        for mutation in transform_propagation_system(ecs, 0.) {
+            mutation.apply(ecs);
+        }
+    }
+
+    #[test]
+    fn test_children_inherit_parent_transform() {
+        let mut ecs = ECS::new();
+        let parent = ecs
+            .build_entity()
+            .add_component(
+                TransformComponent::builder()
+                    .position(10., 0.)
+                    .scale(2., 2.)
+                    .build(),
+            )
+            .entity();
+        let child = ecs
+            .build_entity()
+            .add_component(TransformComponent::builder().position(1., 1.).build())
+            .entity();
+        let grandchild = ecs
+            .build_entity()
+            .add_component(TransformComponent::builder().position(0., 1.).build())
+            .entity();
+        ecs.set_parent(&child, &parent);
+        ecs.set_parent(&grandchild, &child);
+
+        run(&mut ecs);
+
+        let position = |e| ecs.read::<GlobalTransform>(&e).unwrap().position();
+        assert_eq!(position(parent), Vector3::new(10., 0., 0.));
+        assert_eq!(position(child), Vector3::new(12., 2., 0.));
+        assert_eq!(position(grandchild), Vector3::new(12., 4., 0.));
+    }
+
+    #[test]
+    fn test_reparenting_updates_global_transform() {
+        let mut ecs = ECS::new();
+        let parent = ecs
+            .build_entity()
+            .add_component(TransformComponent::builder().position(5., 5.).build())
+            .entity();
+        let child = ecs
+            .build_entity()
+            .add_component(TransformComponent::builder().position(1., 0.).build())
+            .entity();
+        ecs.set_parent(&child, &parent);
+        run(&mut ecs);
+
+        ecs.remove_parent(&child);
+        run(&mut ecs);
+
+        let global = ecs.read::<GlobalTransform>(&child).unwrap();
+        assert_eq!(global.position(), Vector3::new(1., 0., 0.));
+    }
+}
@@ -1,6 +1,10 @@
-use cgmath::{Deg, Matrix4, Vector2, Vector3};
+use cgmath::{Deg, Matrix4, Vector3};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy)]
+mod global;
+pub use global::{transform_propagation_system, GlobalTransform};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct TransformComponent {
     pub position: Vector3<f32>,
     pub rotation: Vector3<f32>,
@@ -9,7 +13,7 @@ pub struct TransformComponent {
 
 impl TransformComponent {
     pub fn change_pos(&mut self, dx: f32, dy: f32) {
-        self.position = self.position + Vector3::new(dx, dy, 0.);
+        self.position += Vector3::new(dx, dy, 0.);
     }
 
     pub fn builder() -> TransformComponentBuilder {
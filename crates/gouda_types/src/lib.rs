@@ -1,4 +1,3 @@
-use gouda_ecs::Entity;
 
 #[derive(Debug, Eq, PartialEq)]
 pub enum Direction {
@@ -0,0 +1,136 @@
+use crate::{GameWindowImpl, WindowEvent, WindowProps};
+use gouda_input::GameInput;
+use std::collections::VecDeque;
+
+/// The input and window events a headless window hands out for a single frame.
+#[derive(Default, Clone)]
+pub struct ScriptedFrame {
+    pub input: GameInput,
+    pub events: Vec<WindowEvent>,
+    /// Seconds of real time the frame takes, or a single fixed step if not set. Only read by
+    /// the headless game loop, the window itself ignores it.
+    pub frame_time: Option<f32>,
+}
+
+impl ScriptedFrame {
+    pub fn new(input: GameInput, events: Vec<WindowEvent>) -> Self {
+        return ScriptedFrame {
+            input,
+            events,
+            frame_time: None,
+        };
+    }
+
+    pub fn with_frame_time(mut self, seconds: f32) -> Self {
+        self.frame_time = Some(seconds);
+        return self;
+    }
+}
+
+/// A window with no backing surface. It reports the size it was created with and plays back any
+/// scripted frames it was given, one per call to `capture_input`. Once the script runs out the
+/// last input is carried over and no more events are produced.
+pub struct HeadlessWindow {
+    props: WindowProps,
+    input: GameInput,
+    events: Vec<WindowEvent>,
+    script: VecDeque<ScriptedFrame>,
+}
+
+impl HeadlessWindow {
+    pub fn new(props: WindowProps) -> Self {
+        return HeadlessWindow::scripted(props, vec![]);
+    }
+
+    pub fn scripted(props: WindowProps, frames: Vec<ScriptedFrame>) -> Self {
+        let mut input = GameInput::new();
+        input.seconds_to_advance_over_update = props.target_ms_per_frame / 1000.;
+        return HeadlessWindow {
+            props,
+            input,
+            events: vec![],
+            script: frames.into(),
+        };
+    }
+}
+
+impl GameWindowImpl for HeadlessWindow {
+    fn capture_events(&mut self) -> Vec<WindowEvent> {
+        return self.events.clone();
+    }
+
+    fn capture_input(&mut self) -> GameInput {
+        match self.script.pop_front() {
+            Some(frame) => {
+                self.input = frame.input;
+                self.events = frame.events;
+            }
+            None => {
+                self.input = GameInput::from(&self.input);
+                self.events = vec![];
+            }
+        }
+        self.input.seconds_to_advance_over_update = self.props.target_ms_per_frame / 1000.;
+        for event in &self.events {
+            if let WindowEvent::ResizeEvent { width, height } = event {
+                self.props.width = *width as f64;
+                self.props.height = *height as f64;
+            }
+        }
+        return self.input.clone();
+    }
+
+    fn get_width(&self) -> usize {
+        return self.props.width as usize;
+    }
+
+    fn get_height(&self) -> usize {
+        return self.props.height as usize;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gouda_input::LetterKeys;
+
+    #[test]
+    fn test_plays_back_script_then_carries_over() {
+        let mut pressed = GameInput::new();
+        pressed.keyboard.letter_keys[LetterKeys::A].ended_down = true;
+        pressed.keyboard.letter_keys[LetterKeys::A].half_transition_count = 1;
+        let mut window = HeadlessWindow::scripted(
+            WindowProps::default(),
+            vec![ScriptedFrame::new(pressed, vec![WindowEvent::CloseEvent])],
+        );
+
+        let input = window.capture_input();
+        assert!(input.keyboard.letter_down(LetterKeys::A));
+        assert_eq!(window.capture_events().len(), 1);
+
+        let input = window.capture_input();
+        assert!(input.keyboard.letter_keys[LetterKeys::A].ended_down);
+        assert_eq!(
+            input.keyboard.letter_keys[LetterKeys::A].half_transition_count,
+            0
+        );
+        assert!(window.capture_events().is_empty());
+    }
+
+    #[test]
+    fn test_scripted_resize_updates_size() {
+        let mut window = HeadlessWindow::scripted(
+            WindowProps::default(),
+            vec![ScriptedFrame::new(
+                GameInput::new(),
+                vec![WindowEvent::ResizeEvent {
+                    width: 64.,
+                    height: 32.,
+                }],
+            )],
+        );
+        window.capture_input();
+        assert_eq!(window.get_width(), 64);
+        assert_eq!(window.get_height(), 32);
+    }
+}
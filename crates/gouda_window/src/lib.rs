@@ -12,6 +12,17 @@ pub mod win32;
 #[cfg(target_os = "windows")]
 pub use win32::PlatformWindow;
 
+#[cfg(target_os = "linux")]
+pub mod linux;
+
+#[cfg(target_os = "linux")]
+pub use linux::PlatformWindow;
+
+pub mod headless;
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+pub use headless::HeadlessWindow as PlatformWindow;
+
 #[cfg(target_os = "macos")]
 #[macro_use]
 extern crate objc;
@@ -49,6 +60,12 @@ pub trait GameWindowImpl {
     fn get_height(&self) -> usize;
 }
 
+/// Displays frames produced by a renderer that draws into CPU memory. Pixels are tightly packed
+/// RGBA8 rows, starting from the top left.
+pub trait FramePresenter {
+    fn present(&self, width: usize, height: usize, pixels: &[u8]);
+}
+
 pub struct WindowProps {
     pub width: f64,
     pub height: f64,
@@ -0,0 +1,289 @@
+use crate::{FramePresenter, GameWindowImpl, WindowEvent, WindowProps};
+use gouda_input::linux::{linux_process_key, linux_process_keyboard_message};
+use gouda_input::GameInput;
+use std::ffi::CString;
+use std::mem;
+use std::os::raw::{c_char, c_uint};
+use std::ptr::null_mut;
+use std::rc::Rc;
+use x11_dl::xlib;
+
+/// The display connection and the window created on it, shared between the window and any
+/// presenters so neither outlives the other.
+struct X11Surface {
+    xlib: xlib::Xlib,
+    display: *mut xlib::Display,
+    window: xlib::Window,
+    gc: xlib::GC,
+}
+
+impl Drop for X11Surface {
+    fn drop(&mut self) {
+        unsafe {
+            (self.xlib.XFreeGC)(self.display, self.gc);
+            (self.xlib.XDestroyWindow)(self.display, self.window);
+            (self.xlib.XCloseDisplay)(self.display);
+        }
+    }
+}
+
+pub struct PlatformWindow {
+    surface: Rc<X11Surface>,
+    wm_delete_window: xlib::Atom,
+    props: WindowProps,
+    input: GameInput,
+    events: Vec<WindowEvent>,
+}
+
+impl PlatformWindow {
+    pub fn new(props: WindowProps) -> Self {
+        let xlib = xlib::Xlib::open().expect("Failed to load libX11");
+        unsafe {
+            let display_ptr = (xlib.XOpenDisplay)(null_mut());
+            if display_ptr.is_null() {
+                panic!("Failed to open X display, is DISPLAY set?");
+            }
+
+            let screen = (xlib.XDefaultScreen)(display_ptr);
+            let root = (xlib.XRootWindow)(display_ptr, screen);
+            let window = (xlib.XCreateSimpleWindow)(
+                display_ptr,
+                root,
+                0,
+                0,
+                props.width as c_uint,
+                props.height as c_uint,
+                0,
+                (xlib.XBlackPixel)(display_ptr, screen),
+                (xlib.XBlackPixel)(display_ptr, screen),
+            );
+
+            let title = CString::new(props.title.clone()).unwrap();
+            (xlib.XStoreName)(display_ptr, window, title.as_ptr() as *mut c_char);
+
+            (xlib.XSelectInput)(
+                display_ptr,
+                window,
+                xlib::KeyPressMask
+                    | xlib::KeyReleaseMask
+                    | xlib::ButtonPressMask
+                    | xlib::ButtonReleaseMask
+                    | xlib::PointerMotionMask
+                    | xlib::StructureNotifyMask,
+            );
+
+            let protocol = CString::new("WM_DELETE_WINDOW").unwrap();
+            let mut wm_delete_window =
+                (xlib.XInternAtom)(display_ptr, protocol.as_ptr(), xlib::False);
+            (xlib.XSetWMProtocols)(display_ptr, window, &mut wm_delete_window, 1);
+
+            // Without this X sends a release before every repeated press while a key is held
+            (xlib.XkbSetDetectableAutoRepeat)(display_ptr, xlib::True, null_mut());
+
+            let gc = (xlib.XCreateGC)(display_ptr, window, 0, null_mut());
+
+            (xlib.XMapWindow)(display_ptr, window);
+            (xlib.XFlush)(display_ptr);
+
+            let mut input = GameInput::new();
+            input.seconds_to_advance_over_update = props.target_ms_per_frame / 1000.;
+
+            return PlatformWindow {
+                surface: Rc::new(X11Surface {
+                    xlib,
+                    display: display_ptr,
+                    window,
+                    gc,
+                }),
+                wm_delete_window,
+                props,
+                input,
+                events: vec![],
+            };
+        }
+    }
+
+    /// A handle that copies rendered frames into this window.
+    pub fn presenter(&self) -> X11Presenter {
+        return X11Presenter {
+            surface: self.surface.clone(),
+        };
+    }
+
+    fn process_event(&mut self, event: &mut xlib::XEvent) {
+        match event.get_type() {
+            xlib::KeyPress | xlib::KeyRelease => {
+                let is_down = event.get_type() == xlib::KeyPress;
+                let keysym = unsafe { (self.surface.xlib.XLookupKeysym)(&mut event.key, 0) };
+                linux_process_key(&mut self.input.keyboard, keysym as u32, is_down);
+            }
+            xlib::ButtonPress | xlib::ButtonRelease => {
+                let is_down = event.get_type() == xlib::ButtonPress;
+                let button = unsafe { event.button };
+                self.input.mouse.x = button.x;
+                self.input.mouse.y = button.y;
+                let index = match button.button {
+                    xlib::Button1 => Some(0),
+                    xlib::Button2 => Some(1),
+                    xlib::Button3 => Some(2),
+                    8 => Some(3),
+                    9 => Some(4),
+                    _ => None,
+                };
+                if let Some(index) = index {
+                    linux_process_keyboard_message(&mut self.input.mouse.buttons[index], is_down);
+                }
+            }
+            xlib::MotionNotify => {
+                let motion = unsafe { event.motion };
+                self.input.mouse.x = motion.x;
+                self.input.mouse.y = motion.y;
+            }
+            xlib::ConfigureNotify => {
+                let configure = unsafe { event.configure };
+                let (width, height) = (configure.width as f64, configure.height as f64);
+                if width != self.props.width || height != self.props.height {
+                    self.props.width = width;
+                    self.props.height = height;
+                    self.events.push(WindowEvent::ResizeEvent {
+                        width: width as f32,
+                        height: height as f32,
+                    });
+                }
+            }
+            xlib::ClientMessage => {
+                let client = unsafe { event.client_message };
+                if client.data.get_long(0) as xlib::Atom == self.wm_delete_window {
+                    self.events.push(WindowEvent::CloseEvent);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl GameWindowImpl for PlatformWindow {
+    fn capture_events(&mut self) -> Vec<WindowEvent> {
+        return self.events.clone();
+    }
+
+    fn capture_input(&mut self) -> GameInput {
+        self.input = GameInput::from(&self.input);
+        self.input.seconds_to_advance_over_update = self.props.target_ms_per_frame / 1000.;
+        self.events = Vec::new();
+        let surface = self.surface.clone();
+        unsafe {
+            while (surface.xlib.XPending)(surface.display) > 0 {
+                let mut event: xlib::XEvent = mem::zeroed();
+                (surface.xlib.XNextEvent)(surface.display, &mut event);
+                self.process_event(&mut event);
+            }
+        }
+        return self.input.clone();
+    }
+
+    fn get_width(&self) -> usize {
+        return self.props.width as usize;
+    }
+
+    fn get_height(&self) -> usize {
+        return self.props.height as usize;
+    }
+}
+
+pub struct X11Presenter {
+    surface: Rc<X11Surface>,
+}
+
+impl FramePresenter for X11Presenter {
+    fn present(&self, width: usize, height: usize, pixels: &[u8]) {
+        let xlib = &self.surface.xlib;
+        let display = self.surface.display;
+        unsafe {
+            let screen = (xlib.XDefaultScreen)(display);
+            let visual = (xlib.XDefaultVisual)(display, screen);
+            let depth = (xlib.XDefaultDepth)(display, screen);
+
+            // TrueColor visuals are almost always 0xRRGGBB, which is BGRA in memory
+            let red_first = (*visual).red_mask == 0xff;
+            let mut data: Vec<u8> = pixels
+                .chunks_exact(4)
+                .flat_map(|p| {
+                    if red_first {
+                        [p[0], p[1], p[2], 255]
+                    } else {
+                        [p[2], p[1], p[0], 255]
+                    }
+                })
+                .collect();
+
+            let image = (xlib.XCreateImage)(
+                display,
+                visual,
+                depth as c_uint,
+                xlib::ZPixmap,
+                0,
+                data.as_mut_ptr() as *mut c_char,
+                width as c_uint,
+                height as c_uint,
+                32,
+                0,
+            );
+            if image.is_null() {
+                return;
+            }
+            (xlib.XPutImage)(
+                display,
+                self.surface.window,
+                self.surface.gc,
+                image,
+                0,
+                0,
+                0,
+                0,
+                width as c_uint,
+                height as c_uint,
+            );
+            // The pixel data belongs to us, so detach it before Xlib frees the image
+            (*image).data = null_mut();
+            (xlib.XDestroyImage)(image);
+            (xlib.XFlush)(display);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // These need an X server, e.g. `xvfb-run cargo test -p gouda_window -- --ignored`
+    #[test]
+    #[ignore = "needs an X server"]
+    fn test_window_reports_props_size() {
+        let mut window = PlatformWindow::new(WindowProps {
+            width: 320.,
+            height: 240.,
+            ..WindowProps::default()
+        });
+        window.capture_input();
+        assert_eq!(window.get_width(), 320);
+        assert_eq!(window.get_height(), 240);
+        window
+            .presenter()
+            .present(320, 240, &vec![255; 320 * 240 * 4]);
+    }
+
+    #[test]
+    #[ignore = "needs an X server"]
+    fn test_input_carries_over_between_frames() {
+        let mut window = PlatformWindow::new(WindowProps::default());
+        window.input.mouse.buttons[0].ended_down = true;
+        let input = window.capture_input();
+        assert!(input.mouse.buttons[0].ended_down);
+        assert_eq!(input.mouse.buttons[0].half_transition_count, 0);
+        assert_eq!(
+            input.seconds_to_advance_over_update,
+            WindowProps::default().target_ms_per_frame / 1000.
+        );
+    }
+}
@@ -1,9 +1,8 @@
 
 use std::{
-    collections::HashMap, default, env, rc::Rc
+    collections::HashMap, env, rc::Rc
 };
 
-use cgmath::{Matrix4, SquareMatrix};
 use gouda::{
     camera::{Camera, PerspectiveCamera},
     ecs::{Entity, GameSceneId, Mutation, Mutations, ECS},
@@ -69,8 +68,8 @@ impl GameScene for MainGameScene {
 
     fn render_scene(&self, ecs: &ECS, scene: &Scene) {
 
-        for (green, ent) in ecs.read1::<GreenLevel>() {
-            let transform1 = TransformComponent::builder()
+        for (_green, _ent) in ecs.read1::<GreenLevel>() {
+            let _transform1 = TransformComponent::builder()
                 .position(-0.2, 0.)
                 .scale(0.1, 0.8)
                 .rotation(0.3, 0.)
@@ -80,7 +79,7 @@ impl GameScene for MainGameScene {
                 .scale(0.3, 0.3)
                 .rotation3d(30., 30., 30.)
                 .build();
-            let transform3 = TransformComponent::builder()
+            let _transform3 = TransformComponent::builder()
                 .position(0., 0.35)
                 .scale(0.4, 0.1)
                 .rotation(0.3, 0.3)
@@ -124,8 +123,6 @@ impl GameLogic for Game {
 
     fn register_events(&self, _ecs: &mut ECS) {}
 
-    fn migrate_events(&self, _ecs: &mut ECS) {}
-
     fn game_scenes(&self) -> HashMap<GameSceneId, Box<dyn GameScene>> {
         let mut res: HashMap<GameSceneId, Box<dyn GameScene>> = HashMap::new();
         res.insert(START_MENU_SCENE, Box::new(MainGameScene {}));
@@ -177,8 +177,6 @@ impl GameLogic for Game {
 
     fn register_events(&self, _ecs: &mut ECS) {}
 
-    fn migrate_events(&self, _ecs: &mut ECS) {}
-
     fn game_scenes(&self) -> HashMap<GameSceneId, Box<dyn GameScene>> {
         let mut res: HashMap<GameSceneId, Box<dyn GameScene>> = HashMap::new();
         res.insert(START_MENU_SCENE, Box::new(MainGameScene {}));
@@ -1,18 +1,16 @@
-use core::time;
 use std::{
-    collections::{HashMap, VecDeque},
+    collections::HashMap,
     env,
-    time::SystemTime,
 };
 
 use gouda::{
-    camera::{Camera, OrthographicCamera},
-    ecs::{Entity, EntityBuilder, GameSceneId, Mutation, Mutations, ECS},
+    camera::OrthographicCamera,
+    ecs::{commands::Commands, Entity, GameSceneId, Mutation, Mutations, ECS},
     input::{AnyKey, GameInput, LetterKeys, SpecialKeys},
     rendering::{sprites::{ColorBoxComponent, SpriteSheetComponent}, Scene},
-    transform::{self, TransformComponent},
+    transform::TransformComponent,
     window::WindowProps,
-    GameLogic, GameScene, Gouda, QuitEvent, RenderLayer,
+    GameLogic, GameScene, Gouda, RenderLayer,
 };
 
 pub const MAIN_GAME_SCENE: GameSceneId = 0;
@@ -42,7 +40,7 @@ struct Paddle {}
 
 impl Paddle {
     pub fn createp1(ecs: &mut ECS) {
-        let color = ColorBoxComponent::new(ecs, [0.3, 0.3, 0.3]);
+        let color = ColorBoxComponent::new([0.3, 0.3, 0.3]);
         let transform = TransformComponent::builder()
             .position(-7., 0.)
             .scale(0.3, 0.8)
@@ -58,16 +56,11 @@ impl Paddle {
         };
 
         ecs.build_entity()
-            .add_component(color)
-            .add_component(collider)
-            .add_component(velocity)
-            .add_component(transform)
-            .add_component(controls)
-            .add_component(Paddle {});
+            .add_bundle((color, collider, velocity, transform, controls, Paddle {}));
     }
 
     pub fn createp2(ecs: &mut ECS) {
-        let color = ColorBoxComponent::new(ecs, [0.3, 0.3, 0.3]);
+        let color = ColorBoxComponent::new([0.3, 0.3, 0.3]);
         let transform = TransformComponent::builder()
             .position(7., 0.)
             .scale(0.3, 0.8)
@@ -82,12 +75,7 @@ impl Paddle {
             down: AnyKey::Special(SpecialKeys::DownArrow),
         };
         ecs.build_entity()
-            .add_component(color)
-            .add_component(collider)
-            .add_component(velocity)
-            .add_component(transform)
-            .add_component(controls)
-            .add_component(Paddle {});
+            .add_bundle((color, collider, velocity, transform, controls, Paddle {}));
     }
 
 }
@@ -97,7 +85,7 @@ struct Ball {}
 
 impl Ball {
     pub fn create(ecs: &mut ECS) {
-        let color = ColorBoxComponent::new(ecs, [0.3, 0.3, 0.3]);
+        let color = ColorBoxComponent::new([0.3, 0.3, 0.3]);
         let transform = TransformComponent::builder()
             .position(0., 0.)
             .scale(0.3, 0.3)
@@ -108,38 +96,20 @@ impl Ball {
             height: 0.3,
         };
         ecs.build_entity()
-            .add_component(color)
-            .add_component(collider)
-            .add_component(velocity)
-            .add_component(transform)
-            .add_component(Ball {});
+            .add_bundle((color, collider, velocity, transform, Ball {}));
     }
 }
 
 
-struct ApplyMovementMutation {
-    entity: Entity,
-    dy: f32,
-    dx: f32,
-}
-
-impl Mutation for ApplyMovementMutation {
-    fn apply(&self, ecs: &mut ECS) {
-        let transform = ecs.write::<TransformComponent>(&self.entity).unwrap();
-        transform.change_pos(self.dx, self.dy);
-    }
-}
-
 pub fn apply_velocity_system(ecs: &ECS, dt: f32) -> Mutations {
-    let mut mutations: Mutations = Vec::new();
+    let mut commands = Commands::new();
     for (vel, ent) in ecs.read1::<Velocity>() {
-        mutations.push(Box::new(ApplyMovementMutation {
-            entity: ent,
-            dy: vel.dy * dt,
-            dx: vel.dx * dt,
-        }));
+        let (dx, dy) = (vel.dx * dt, vel.dy * dt);
+        commands.update(ent, move |transform: &mut TransformComponent| {
+            transform.change_pos(dx, dy)
+        });
     }
-    return mutations;
+    return commands.into();
 }
 
 fn collides(
@@ -175,15 +145,16 @@ pub fn ball_collision_handling_system(ecs: &ECS, _dt: f32) -> Mutations {
             }
         }
 
-        if ball_transform.position[1] < (-8. + ball_transform.scale[1] * 2.) {
-            mutations.push(Box::new(ChangeBallVelocityMutation { entity: ball_ent, new_velocity: [ball_velocity.dx, -ball_velocity.dy]}));
-        } else if ball_transform.position[1] > (8. - ball_transform.scale[1] / 2.) {
+        if ball_transform.position[1] < (-8. + ball_transform.scale[1] * 2.)
+            || ball_transform.position[1] > (8. - ball_transform.scale[1] / 2.)
+        {
             mutations.push(Box::new(ChangeBallVelocityMutation { entity: ball_ent, new_velocity: [ball_velocity.dx, -ball_velocity.dy]}));
         }
     }
     return mutations;
 }
 
+#[allow(dead_code)]
 struct JumpMutation {
     entity: Entity,
 }
@@ -197,16 +168,16 @@ impl Mutation for JumpMutation {
 
 pub fn jump_system(ecs: &ECS, _dt: f32) -> Mutations {
     let input = ecs.read_res::<GameInput>();
-    let mut mutations: Mutations = Vec::new();
+    let mut commands = Commands::new();
     for (_, controls, ent) in ecs.read2::<Paddle, Controls>() {
         if input.keyboard.key_down(controls.up) {
-            mutations.push(Box::new(ApplyMovementMutation { entity: ent, dy: 0.1, dx: 0.}))
+            commands.update(ent, |transform: &mut TransformComponent| transform.change_pos(0., 0.1));
         }
         if input.keyboard.key_down(controls.down) {
-            mutations.push(Box::new(ApplyMovementMutation { entity: ent, dy: -0.1, dx: 0.}))
+            commands.update(ent, |transform: &mut TransformComponent| transform.change_pos(0., -0.1));
         }
     }
-    return mutations;
+    return commands.into();
 }
 
 struct ChangeBallVelocityMutation {
@@ -226,23 +197,23 @@ impl Mutation for ChangeBallVelocityMutation {
 pub struct GameOverScene {}
 
 impl GameScene for GameOverScene {
-    fn on_scene_start(&self, ecs: &mut ECS) {
+    fn on_scene_start(&self, _ecs: &mut ECS) {
         todo!()
     }
 
-    fn on_scene_stop(&self, ecs: &mut ECS) {
+    fn on_scene_stop(&self, _ecs: &mut ECS) {
         todo!()
     }
 
-    fn render_scene(&self, ecs: &ECS, scene: &Scene) {
+    fn render_scene(&self, _ecs: &ECS, _scene: &Scene) {
         todo!()
     }
 
-    fn next_scene(&self, ecs: &ECS) -> Option<GameSceneId> {
+    fn next_scene(&self, _ecs: &ECS) -> Option<GameSceneId> {
         todo!()
     }
 
-    fn active_layers(&self, ecs: &ECS) -> Vec<RenderLayer> {
+    fn active_layers(&self, _ecs: &ECS) -> Vec<RenderLayer> {
         todo!()
     }
 }
@@ -267,7 +238,7 @@ impl GameScene for MainGameScene {
         //
     }
 
-    fn on_scene_stop(&self, ecs: &mut ECS) {}
+    fn on_scene_stop(&self, _ecs: &mut ECS) {}
 
     fn render_scene(&self, ecs: &ECS, scene: &Scene) {
         // Draw background
@@ -275,15 +246,15 @@ impl GameScene for MainGameScene {
         // Draw bird
         // Draw pipes
         for (location, color_box, _) in ecs.read2::<TransformComponent, ColorBoxComponent>() {
-            color_box.draw(&scene, location);
+            color_box.draw(scene, location);
         }
 
         for (location, spritesheet, _) in ecs.read2::<TransformComponent, SpriteSheetComponent>() {
-            spritesheet.draw(&scene, location);
+            spritesheet.draw(scene, location);
         }
     }
 
-    fn next_scene(&self, ecs: &ECS) -> Option<u32> {
+    fn next_scene(&self, _ecs: &ECS) -> Option<u32> {
         return None;
     }
 
@@ -309,9 +280,7 @@ impl GameLogic for Game {
         }
     }
 
-    fn register_events(&self, ecs: &mut ECS) {}
-
-    fn migrate_events(&self, ecs: &mut ECS) {}
+    fn register_events(&self, _ecs: &mut ECS) {}
 
     fn game_scenes(&self) -> HashMap<GameSceneId, Box<dyn GameScene>> {
         let mut res: HashMap<GameSceneId, Box<dyn GameScene>> = HashMap::new();
@@ -323,7 +292,7 @@ impl GameLogic for Game {
         return START_MENU_SCENE;
     }
 
-    fn setup(&mut self, ecs: &mut ECS) {}
+    fn setup(&mut self, _ecs: &mut ECS) {}
 }
 
 fn main() {
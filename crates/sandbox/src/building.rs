@@ -1,6 +1,8 @@
 use crate::monster::Monster;
 use crate::tilemap::Tile;
-use gouda::ecs::{Entity, Mutation, Mutations, ECS};
+use gouda::ecs::commands::Commands;
+use gouda::ecs::parallel::SendMutations;
+use gouda::ecs::{Entity, Mutation, ECS};
 use gouda::input::GameInput;
 use gouda::rendering::sprites::SpriteComponent;
 use gouda::transform::TransformComponent;
@@ -69,46 +71,8 @@ pub struct DamageDealt {
     pub damage: u32,
 }
 
-struct ArrowCollisionMutation {
-    arrow: Entity,
-}
-
-impl Mutation for ArrowCollisionMutation {
-    fn apply(&self, ecs: &mut ECS) {
-        let arrow = ecs.read::<Arrow>(&self.arrow).unwrap();
-        let target = arrow.target.clone();
-        let damage = arrow.damage;
-        ecs.delete_entity(&self.arrow);
-
-        ecs.add_component(&target, DamageDealt { damage });
-    }
-}
-
-struct ArrowDestroyMutation {
-    arrow: Entity,
-}
-
-impl Mutation for ArrowDestroyMutation {
-    fn apply(&self, ecs: &mut ECS) {
-        ecs.delete_entity(&self.arrow);
-    }
-}
-
-struct MoveArrowTowardsMutation {
-    arrow: Entity,
-    dx: f32,
-    dy: f32,
-}
-
-impl Mutation for MoveArrowTowardsMutation {
-    fn apply(&self, ecs: &mut ECS) {
-        let arrow = ecs.write::<TransformComponent>(&self.arrow).unwrap();
-        arrow.change_pos(self.dx, self.dy);
-    }
-}
-
-pub fn arrow_move_system(ecs: &ECS, _dt: f32) -> Mutations {
-    let mut mutations: Mutations = vec![];
+pub fn arrow_move_system(ecs: &ECS, _dt: f32) -> SendMutations {
+    let mut commands = Commands::new();
     let dt = ecs.read_res::<GameInput>().seconds_to_advance_over_update;
     for (arrow, arrow_location, entity) in ecs.read2::<Arrow, TransformComponent>() {
         let target = ecs.read::<TransformComponent>(&arrow.target);
@@ -119,20 +83,26 @@ pub fn arrow_move_system(ecs: &ECS, _dt: f32) -> Mutations {
             );
             let dist = (v.0 * v.0 + v.1 * v.1).sqrt();
             if dist < 0.5 {
-                mutations.push(Box::new(ArrowCollisionMutation { arrow: entity }));
+                commands.despawn(entity);
+                commands.insert(
+                    arrow.target,
+                    DamageDealt {
+                        damage: arrow.damage,
+                    },
+                );
             } else {
-                mutations.push(Box::new(MoveArrowTowardsMutation {
-                    arrow: entity,
-                    dx: v.0 * dt / dist * arrow.speed,
-                    dy: v.1 * dt / dist * arrow.speed,
-                }))
+                let dx = v.0 * dt / dist * arrow.speed;
+                let dy = v.1 * dt / dist * arrow.speed;
+                commands.update(entity, move |location: &mut TransformComponent| {
+                    location.change_pos(dx, dy)
+                });
             }
         } else {
-            mutations.push(Box::new(ArrowDestroyMutation { arrow: entity }));
+            commands.despawn(entity);
         }
     }
 
-    return mutations;
+    return commands.into();
 }
 
 pub struct FireArrowMutation {
@@ -162,8 +132,8 @@ impl Mutation for DecrTurretTimerMutation {
     }
 }
 
-pub fn turret_attack_system(ecs: &ECS, _dt: f32) -> Mutations {
-    let mut mutations: Mutations = vec![];
+pub fn turret_attack_system(ecs: &ECS, _dt: f32) -> SendMutations {
+    let mut mutations: SendMutations = vec![];
 
     let mut monster_positions: Vec<(Entity, f32, f32)> = vec![];
     for (_, transform, entity) in ecs.read2::<Monster, TransformComponent>() {
@@ -179,10 +149,10 @@ pub fn turret_attack_system(ecs: &ECS, _dt: f32) -> Mutations {
 
             if let Some((_, closest_dist)) = closest {
                 if dist < closest_dist {
-                    closest = Some((monster.clone(), dist));
+                    closest = Some((*monster, dist));
                 }
             } else {
-                closest = Some((monster.clone(), dist));
+                closest = Some((*monster, dist));
             }
         }
 
@@ -1,10 +1,11 @@
 use gouda::{
-    camera::{Camera, OrthographicCamera},
+    camera::OrthographicCamera,
     ecs::{Entity, Mutation, Mutations, ECS},
     input::{GameInput, LetterKeys},
     transform::TransformComponent,
 };
 
+#[allow(dead_code)]
 #[derive(Debug)]
 pub struct CameraComponent {
     pub move_speed: f32,
@@ -49,6 +50,7 @@ impl Mutation for OrthoScaleMutation {
     }
 }
 
+#[allow(dead_code)]
 pub fn camera_control_system(ecs: &ECS, dt: f32) -> Mutations {
     let input = ecs.read_res::<GameInput>();
     let cameras = ecs.read2::<CameraComponent, TransformComponent>();
@@ -10,7 +10,7 @@ use gouda::types::Color;
 use std::rc::Rc;
 
 pub fn change_stage_text(ecs: &mut ECS, text: &str) {
-    let e = ecs.get2::<StageText, GuiText>().first().unwrap().clone();
+    let e = *ecs.get2::<StageText, GuiText>().first().unwrap();
     let renderer = ecs.read_res::<Rc<Renderer>>().clone();
     ecs.write::<GuiText>(&e)
         .unwrap()
@@ -18,7 +18,7 @@ pub fn change_stage_text(ecs: &mut ECS, text: &str) {
 }
 
 pub fn change_gold_text(ecs: &mut ECS) {
-    let e = ecs.get2::<GoldText, GuiText>().first().unwrap().clone();
+    let e = *ecs.get2::<GoldText, GuiText>().first().unwrap();
     let renderer = ecs.read_res::<Rc<Renderer>>().clone();
     let gold = ecs.read_res::<Supplies>().gold;
     ecs.write::<GuiText>(&e)
@@ -27,7 +27,7 @@ pub fn change_gold_text(ecs: &mut ECS) {
 }
 
 pub fn change_wood_text(ecs: &mut ECS) {
-    let e = ecs.get2::<WoodText, GuiText>().first().unwrap().clone();
+    let e = *ecs.get2::<WoodText, GuiText>().first().unwrap();
     let renderer = ecs.read_res::<Rc<Renderer>>().clone();
     let gold = ecs.read_res::<Supplies>().wood;
     ecs.write::<GuiText>(&e)
@@ -36,7 +36,7 @@ pub fn change_wood_text(ecs: &mut ECS) {
 }
 
 pub fn change_stone_text(ecs: &mut ECS) {
-    let e = ecs.get2::<StoneText, GuiText>().first().unwrap().clone();
+    let e = *ecs.get2::<StoneText, GuiText>().first().unwrap();
     let renderer = ecs.read_res::<Rc<Renderer>>().clone();
     let gold = ecs.read_res::<Supplies>().stone;
     ecs.write::<GuiText>(&e)
@@ -248,7 +248,7 @@ fn create_bottom_panel(ecs: &mut ECS, mouse_layer: Entity) -> Entity {
         Color::from_u8(0x88, 0x88, 0x88, 0xFF),
     );
     let buttons_box = ecs.read::<GuiComponent>(&buttons_box_entity).unwrap();
-    let buttons_box_bounds = buttons_box.calculated_bounds.clone();
+    let buttons_box_bounds = buttons_box.calculated_bounds;
     let child1_entity = GuiComponent::create_hoverable(
         ecs,
         Some(mouse_layer),
@@ -273,7 +273,7 @@ fn create_bottom_panel(ecs: &mut ECS, mouse_layer: Entity) -> Entity {
     bottom_panel.add_child(buttons_box_entity);
 
     let child1 = ecs.read::<GuiComponent>(&child1_entity).unwrap();
-    let child1_bounds = child1.calculated_bounds.clone();
+    let child1_bounds = child1.calculated_bounds;
     let image = PNG::from_file("./assets/bitmap/turret2.png")
         .unwrap()
         .image();
@@ -1,8 +1,9 @@
 use gouda::ecs::ECS;
 use gouda::rendering::sprites::SpriteComponent;
 use gouda::transform::TransformComponent;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Hearth {}
 
 impl Hearth {
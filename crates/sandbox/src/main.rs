@@ -1,9 +1,12 @@
-use camera::{camera_control_system, CameraComponent};
-use gouda::camera::{Camera, OrthographicCamera};
+use camera::CameraComponent;
+use gouda::camera::OrthographicCamera;
+use gouda::ecs::query::With;
+use gouda::ecs::schedule::Stage;
 use gouda::ecs::{Entity, GameSceneId, Mutation, Mutations, ECS};
 use gouda::input::{GameInput, LetterKeys};
 use gouda::rendering::drawable::ShapeDrawable;
 use gouda::rendering::obj::{load_mtl_file, load_obj_file, ObjMesh};
+use gouda::rendering::sprite_batch::SpriteBatch;
 use gouda::rendering::sprites::{ColorBoxComponent, SpriteComponent, SpriteSheetComponent};
 use gouda::rendering::{Renderer, Scene};
 use gouda::transform::TransformComponent;
@@ -12,7 +15,7 @@ use std::rc::Rc;
 use tree::create_tree;
 
 extern crate rand;
-use crate::building::{arrow_move_system, turret_attack_system, Turret};
+use crate::building::{arrow_move_system, turret_attack_system, Arrow, DamageDealt, Turret};
 use crate::cursor::Cursor;
 use crate::gui::{change_stage_text, game_gui_system, GameGui};
 use crate::main_menu::{menu_mouse_system, MenuScreen, ResumeEvent, SaveEvent, SettingsEvent};
@@ -26,7 +29,7 @@ use crate::tree::TreeComponent;
 use crate::turret::{CreateTurretMutation, TurretDeselectMutation, TurretSelectMutation};
 use gouda::gui::{ActiveGui, GuiComponent};
 use gouda::mouse_capture::{mouse_capture_system, ActiveCaptureLayer, MouseCaptureArea};
-use gouda::window::{WindowEvent, WindowProps};
+use gouda::window::WindowProps;
 use rand::{thread_rng, Rng};
 use std::collections::HashMap;
 
@@ -40,6 +43,7 @@ mod main_menu;
 mod monster;
 mod pathfinding;
 mod player;
+mod save;
 mod spawners;
 mod start_menu;
 mod supplies;
@@ -117,7 +121,7 @@ impl Mutation for CursorVisibilityMutation {
 fn mouse_cursor_system(ecs: &ECS, _dt: f32) -> Mutations {
     let mut mutations: Mutations = vec![];
     let mut any_hovered = false;
-    for (_, mouse_capture, e) in ecs.read2::<Tile, MouseCaptureArea>() {
+    for (e, mouse_capture, _) in ecs.query::<(Entity, &MouseCaptureArea, With<Tile>)>() {
         if mouse_capture.is_hovered {
             mutations.push(Box::new(CursorSetPositionMutation { tile: e }));
             any_hovered = true;
@@ -130,42 +134,88 @@ fn mouse_cursor_system(ecs: &ECS, _dt: f32) -> Mutations {
 }
 
 fn register_core_systems(ecs: &mut ECS) {
-    ecs.add_system(Box::new(player_move_system));
-    ecs.add_system(Box::new(mouse_capture_system));
-    ecs.add_system(Box::new(game_gui_system));
+    ecs.register_system("player_move", Box::new(player_move_system))
+        .engine();
+    ecs.register_system("mouse_capture", Box::new(mouse_capture_system))
+        .stage(Stage::PreUpdate)
+        .engine();
+    ecs.register_system("game_gui", Box::new(game_gui_system))
+        .after("player_move")
+        .engine();
+}
+
+fn register_combat_systems(ecs: &mut ECS) {
+    ecs.register_parallel_system("monster_move", Box::new(monster_move_system))
+        .reads::<Monster>()
+        .reads::<TransformComponent>()
+        .writes::<Monster>()
+        .writes::<TransformComponent>()
+        .writes::<ShapeDrawable>()
+        .writes::<DamageDealt>();
+    ecs.register_parallel_system("turret_attack", Box::new(turret_attack_system))
+        .reads::<Monster>()
+        .reads::<Turret>()
+        .reads::<TransformComponent>()
+        .reads::<GameInput>()
+        .writes::<Turret>()
+        .writes::<Arrow>()
+        .writes::<TransformComponent>()
+        .writes::<SpriteComponent>();
+    ecs.register_parallel_system("arrow_move", Box::new(arrow_move_system))
+        .reads::<Arrow>()
+        .reads::<TransformComponent>()
+        .reads::<GameInput>()
+        .writes::<Arrow>()
+        .writes::<TransformComponent>()
+        .writes::<SpriteComponent>()
+        .writes::<DamageDealt>();
+    ecs.register_parallel_system("monster_damage", Box::new(monster_damage_system))
+        .reads::<Monster>()
+        .reads::<DamageDealt>()
+        .writes::<Monster>()
+        .writes::<DamageDealt>()
+        .writes::<TransformComponent>()
+        .writes::<ShapeDrawable>();
 }
 
 fn draw_everything(ecs: &ECS, scene: &Scene) {
-    for (location, sprite, _) in ecs.read2::<TransformComponent, SpriteComponent>() {
-        sprite.draw(&scene, location);
+    let mut sprites = SpriteBatch::new();
+    for (location, sprite) in ecs.query::<(&TransformComponent, &SpriteComponent)>() {
+        sprite.batch(&mut sprites, location);
     }
+    sprites.flush(scene);
 
-    for (location, color_box, _) in ecs.read2::<TransformComponent, ColorBoxComponent>() {
-        color_box.draw(&scene, location);
+    for (location, color_box) in ecs.query::<(&TransformComponent, &ColorBoxComponent)>() {
+        color_box.draw(scene, location);
     }
 
-    for (location, spritesheet, _) in ecs.read2::<TransformComponent, SpriteSheetComponent>() {
-        spritesheet.draw(&scene, location);
+    for (location, spritesheet) in ecs.query::<(&TransformComponent, &SpriteSheetComponent)>() {
+        spritesheet.batch(&mut sprites, location);
     }
+    sprites.flush(scene);
 
-    for (shape, transform, _) in ecs.read2::<ShapeDrawable, TransformComponent>() {
+    for (shape, transform) in ecs.query::<(&ShapeDrawable, &TransformComponent)>() {
         scene.submit_shape_by_name(
-            &shape.shader_name,
-            &shape.shape_name,
+            shape.shader_name,
+            shape.shape_name,
             transform.transform_matrix(),
             shape.color,
         );
     }
 
-    ecs.read_res::<Cursor>().draw(&scene);
-    ecs.read_res::<ObjMesh>().draw(&scene);
+    ecs.read_res::<Cursor>().draw(scene);
+    let tree_transform = TransformComponent::builder()
+        .position(0., 0.)
+        .scale(0.2, 0.2)
+        .build();
+    scene.submit_obj(ecs.read_res::<ObjMesh>(), tree_transform.transform_matrix());
 
     for (player, _) in ecs.read1::<Player>() {
-        player.draw(&scene);
+        player.draw(scene);
     }
 
     for (gui, _active, _) in ecs.read2::<GuiComponent, ActiveGui>() {
-        gui.render(&ecs, &scene);
+        gui.render(ecs, scene);
     }
 }
 
@@ -175,14 +225,10 @@ pub struct MainGameScene {}
 
 impl GameScene for MainGameScene {
     fn on_scene_start(&self, ecs: &mut ECS) {
-        register_core_systems(ecs);
         ecs.add_system(Box::new(wave_spawner_system));
-        ecs.add_system(Box::new(monster_move_system));
         ecs.add_system(Box::new(mouse_click_system));
         ecs.add_system(Box::new(mouse_cursor_system));
-        ecs.add_system(Box::new(turret_attack_system));
-        ecs.add_system(Box::new(arrow_move_system));
-        ecs.add_system(Box::new(monster_damage_system));
+        register_combat_systems(ecs);
         ecs.build_entity()
             .add_component(OrthographicCamera::new(6.))
             .add_component(CameraComponent::new())
@@ -237,7 +283,7 @@ impl Mutation for StateCountdownMutation {
 }
 
 fn day_state_countdown(_ecs: &ECS, dt: f32) -> Mutations {
-    return vec![Box::new(StateCountdownMutation { dt: dt })];
+    return vec![Box::new(StateCountdownMutation { dt })];
 }
 
 fn next_day(ecs: &mut ECS) {
@@ -279,7 +325,6 @@ pub struct DayGameScene {}
 
 impl GameScene for DayGameScene {
     fn on_scene_start(&self, ecs: &mut ECS) {
-        register_core_systems(ecs);
         ecs.add_system(Box::new(mouse_click_system));
         ecs.add_system(Box::new(mouse_cursor_system));
         ecs.add_system(Box::new(day_state_countdown));
@@ -333,14 +378,10 @@ pub struct NightGameScene {}
 
 impl GameScene for NightGameScene {
     fn on_scene_start(&self, ecs: &mut ECS) {
-        register_core_systems(ecs);
         ecs.add_system(Box::new(wave_spawner_system));
-        ecs.add_system(Box::new(monster_move_system));
         ecs.add_system(Box::new(mouse_click_system));
         ecs.add_system(Box::new(mouse_cursor_system));
-        ecs.add_system(Box::new(turret_attack_system));
-        ecs.add_system(Box::new(arrow_move_system));
-        ecs.add_system(Box::new(monster_damage_system));
+        register_combat_systems(ecs);
         ecs.add_system(Box::new(day_state_countdown));
         ecs.build_entity()
             .add_component(OrthographicCamera::new(6.))
@@ -393,7 +434,6 @@ pub struct MainMenuGameScene {}
 
 impl GameScene for MainMenuGameScene {
     fn on_scene_start(&self, ecs: &mut ECS) {
-        register_core_systems(ecs);
         ecs.add_system(Box::new(menu_mouse_system));
         let capture_layer = ecs.read_res::<MenuScreen>().capture_layer;
         ecs.add_component(&capture_layer, ActiveCaptureLayer {});
@@ -417,7 +457,7 @@ impl GameScene for MainMenuGameScene {
         draw_everything(ecs, scene);
         let menu = ecs.read_res::<MenuScreen>();
         let menugui = ecs.read::<GuiComponent>(&menu.entity);
-        menugui.unwrap().render(&ecs, &scene);
+        menugui.unwrap().render(ecs, scene);
     }
 
     fn next_scene(&self, ecs: &ECS) -> Option<u32> {
@@ -425,7 +465,7 @@ impl GameScene for MainMenuGameScene {
         if input.keyboard.letter_pressed(LetterKeys::B) {
             return Some(ecs.read_res::<LastScene>().0);
         }
-        if ecs.events::<ResumeEvent>().len() > 0 {
+        if !ecs.events::<ResumeEvent>().is_empty() {
             return Some(ecs.read_res::<LastScene>().0);
         }
         return None;
@@ -468,14 +508,6 @@ impl GameLogic for Game {
         ecs.register_event_type::<StartEvent>();
     }
 
-    fn migrate_events(&self, ecs: &mut ECS) {
-        ecs.migrate_events::<ResumeEvent>();
-        ecs.migrate_events::<SaveEvent>();
-        ecs.migrate_events::<SettingsEvent>();
-        ecs.migrate_events::<QuitEvent>();
-        ecs.migrate_events::<StartEvent>();
-    }
-
     fn game_scenes(&self) -> HashMap<GameSceneId, Box<dyn GameScene>> {
         let mut res: HashMap<GameSceneId, Box<dyn GameScene>> = HashMap::new();
         res.insert(START_MENU_SCENE, Box::new(StartMenuScene {}));
@@ -504,6 +536,8 @@ impl GameLogic for Game {
         ecs.add_res(generate_days());
         ecs.add_res(Supplies::new());
         StartMenu::create(ecs);
+        register_core_systems(ecs);
+        save::register_saved_types(ecs);
     }
 }
 
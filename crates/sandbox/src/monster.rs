@@ -1,5 +1,6 @@
 use crate::building::DamageDealt;
-use gouda::ecs::{Entity, Mutation, Mutations, ECS};
+use gouda::ecs::parallel::SendMutations;
+use gouda::ecs::{Entity, Mutation, ECS};
 use gouda::rendering::drawable::ShapeDrawable;
 use gouda::transform::TransformComponent;
 
@@ -50,8 +51,8 @@ impl Mutation for MonsterMoveMutation {
     }
 }
 
-pub fn monster_move_system(ecs: &ECS, dt: f32) -> Mutations {
-    let mut mutations: Mutations = Vec::new();
+pub fn monster_move_system(ecs: &ECS, dt: f32) -> SendMutations {
+    let mut mutations: SendMutations = Vec::new();
     for (_, transform, monster) in ecs.read2::<Monster, TransformComponent>() {
         let mut dx = 0.;
         let mut dy = 0.;
@@ -59,11 +60,11 @@ pub fn monster_move_system(ecs: &ECS, dt: f32) -> Mutations {
 
         let monster_speed = 2.;
         if transform.position.x > 0.03 {
-            dx = -1. * monster_speed * dt;
+            dx = -monster_speed * dt;
         } else if transform.position.x < -0.03 {
             dx = 1. * monster_speed * dt;
         } else if transform.position.y > 1.1 {
-            dy = -1. * monster_speed * dt;
+            dy = -monster_speed * dt;
         } else if transform.position.y < 0.9 {
             dy = 1. * monster_speed * dt;
         } else {
@@ -95,8 +96,8 @@ impl Mutation for MonsterDamageMutation {
     }
 }
 
-pub fn monster_damage_system(ecs: &ECS, _dt: f32) -> Mutations {
-    let mut mutations: Mutations = vec![];
+pub fn monster_damage_system(ecs: &ECS, _dt: f32) -> SendMutations {
+    let mut mutations: SendMutations = vec![];
     for (_, damage, entity) in ecs.read2::<Monster, DamageDealt>() {
         mutations.push(Box::new(MonsterDamageMutation {
             monster: entity,
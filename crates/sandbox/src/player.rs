@@ -7,6 +7,7 @@ use gouda::rendering::{Renderer, Scene};
 use gouda::transform::TransformComponent;
 use std::rc::Rc;
 
+#[allow(dead_code)]
 #[derive(Debug)]
 pub struct Player {
     selected_drawable: QuadDrawable,
@@ -0,0 +1,72 @@
+use crate::hearth::Hearth;
+use crate::tilemap::Tile;
+use gouda::ecs::ECS;
+use gouda::transform::TransformComponent;
+
+pub fn register_saved_types(ecs: &mut ECS) {
+    ecs.register_saved_component_with_entities::<Tile>("tile");
+    ecs.register_saved_component::<Hearth>("hearth");
+    ecs.register_saved_component::<TransformComponent>("transform");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::register_saved_types;
+    use crate::hearth::Hearth;
+    use crate::tilemap::{link_neighbors, Tile};
+    use gouda::ecs::{Entity, ECS};
+    use gouda::transform::TransformComponent;
+    use gouda::types::Direction;
+
+    #[test]
+    fn test_tiles_and_hearth_round_trip() {
+        let mut ecs = ECS::new();
+        register_saved_types(&mut ecs);
+        let tiles: Vec<Vec<Entity>> = (0..3)
+            .map(|x| {
+                (0..3)
+                    .map(|y| {
+                        let transform = TransformComponent::builder()
+                            .position(x as f32, y as f32)
+                            .build();
+                        ecs.build_entity()
+                            .add_component(Tile::new(x, y))
+                            .add_component(transform)
+                            .entity()
+                    })
+                    .collect()
+            })
+            .collect();
+        link_neighbors(&mut ecs, &tiles);
+        let hearth = ecs
+            .build_entity()
+            .add_component(Hearth {})
+            .add_component(TransformComponent::builder().position(0., 1.).build())
+            .entity();
+        let mut buffer = vec![];
+        ecs.save_to(&mut buffer).unwrap();
+
+        let mut loaded = ECS::new();
+        register_saved_types(&mut loaded);
+        loaded.new_entity();
+        let mapper = loaded.load_from(buffer.as_slice()).unwrap();
+
+        let center = mapper.get(tiles[1][1]).unwrap();
+        let tile = loaded.read::<Tile>(&center).unwrap();
+        assert_eq!((tile.x, tile.y), (1, 1));
+        assert_eq!(tile._neighbor(Direction::Top), mapper.get(tiles[1][0]));
+        assert_eq!(tile._neighbor(Direction::Right), mapper.get(tiles[2][1]));
+        assert_eq!(tile._neighbor(Direction::Down), mapper.get(tiles[1][2]));
+        assert_eq!(tile._neighbor(Direction::Left), mapper.get(tiles[0][1]));
+        let corner = loaded
+            .read::<Tile>(&mapper.get(tiles[0][0]).unwrap())
+            .unwrap();
+        assert_eq!(corner._neighbor(Direction::Left), None);
+
+        let hearth = mapper.get(hearth).unwrap();
+        assert!(loaded.read::<Hearth>(&hearth).is_some());
+        let transform = loaded.read::<TransformComponent>(&hearth).unwrap();
+        assert_eq!(transform.position, (0., 1., 0.).into());
+        assert_eq!(loaded.get1::<Tile>().len(), 9);
+    }
+}
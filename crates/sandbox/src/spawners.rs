@@ -15,7 +15,7 @@ pub struct ProcessSpawnerMutation {
 impl Mutation for ProcessSpawnerMutation {
     fn apply(&self, ecs: &mut ECS) {
         let spawner = ecs.write::<WaveSpawner>(&self.entity).unwrap();
-        if let Some(_) = spawner.progress(self.dt) {
+        if spawner.progress(self.dt).is_some() {
             Monster::create(ecs, self.x, self.y);
         }
 
@@ -60,7 +60,7 @@ impl WaveSpawner {
             .build();
         let spawner = WaveSpawner {
             wave_spec: spec,
-            num_monsters: num_monsters,
+            num_monsters,
             current_monster_index: 0,
             spawn_max_cd: spawn_cd,
             spawn_current_cd: spawn_cd,
@@ -78,7 +78,7 @@ impl WaveSpawner {
 
         self.spawn_current_cd -= dt;
         if self.spawn_current_cd <= 0. {
-            self.spawn_current_cd = self.spawn_max_cd + self.spawn_current_cd;
+            self.spawn_current_cd += self.spawn_max_cd;
             let res = Some(self.wave_spec.monsters[self.current_monster_index]);
             self.current_monster_index += 1;
             return res;
@@ -96,6 +96,7 @@ pub struct WaveSpec {
     pub monsters: Vec<MonsterSpec>,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone, Copy)]
 pub struct MonsterSpec {
     pub monster_type: MonsterType,
@@ -3,8 +3,8 @@ use crate::main_menu::MainMenu;
 use crate::player::Player;
 use crate::start_menu::StartMenuButtonId::Start;
 use crate::tilemap::Tilemap;
-use crate::{register_core_systems, LastScene, MAIN_GAME_SCENE};
-use gouda::camera::{Camera, OrthographicCamera};
+use crate::{LastScene, MAIN_GAME_SCENE};
+use gouda::camera::OrthographicCamera;
 use gouda::ecs::{Entity, GameSceneId, Mutation, Mutations, ECS};
 use gouda::gui::constraints::Constraint::{CenterConstraint, RelativeConstraint};
 use gouda::gui::constraints::{Constraint, GuiConstraints};
@@ -34,7 +34,6 @@ pub struct StartMenuScene {}
 
 impl GameScene for StartMenuScene {
     fn on_scene_start(&self, ecs: &mut ECS) {
-        register_core_systems(ecs);
         ecs.add_system(Box::new(start_menu_mouse_system));
         let capture_layer = ecs.read_res::<StartMenuScreen>().capture_layer;
         ecs.add_component(&capture_layer, ActiveCaptureLayer {});
@@ -52,18 +51,18 @@ impl GameScene for StartMenuScene {
         let button_layer = ecs.read_res::<StartMenuScreen>().button_layer;
         ecs.remove_component::<ActiveCaptureLayer>(&button_layer);
         let camera = ecs.read1::<OrthographicCamera>();
-        let cam = camera.get(0).unwrap().1;
+        let cam = camera.first().unwrap().1;
         ecs.delete_entity(&cam);
     }
 
     fn render_scene(&self, ecs: &ECS, scene: &Scene) {
         let menu = ecs.read_res::<StartMenuScreen>();
         let menugui = ecs.read::<GuiComponent>(&menu.entity);
-        menugui.unwrap().render(&ecs, &scene);
+        menugui.unwrap().render(ecs, scene);
     }
 
     fn next_scene(&self, ecs: &ECS) -> Option<u32> {
-        if ecs.events::<StartEvent>().len() > 0 {
+        if !ecs.events::<StartEvent>().is_empty() {
             return Some(MAIN_GAME_SCENE);
         }
         return None;
@@ -1,11 +1,13 @@
 use crate::hearth::Hearth;
+use gouda::ecs::save::{EntityMapper, MapEntities};
 use gouda::ecs::{Entity, ECS};
 use gouda::mouse_capture::{ActiveCaptureLayer, MouseCaptureArea, MouseCaptureLayer};
 use gouda::rendering::sprites::SpriteComponent;
 use gouda::transform::TransformComponent;
 use gouda::types::{Bounds, Direction};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Tile {
     pub x: i32,
     pub y: i32,
@@ -13,7 +15,22 @@ pub struct Tile {
     neighbors: [Option<Entity>; 4],
 }
 
+impl MapEntities for Tile {
+    fn map_entities(&mut self, mapper: &EntityMapper) {
+        self.neighbors.map_entities(mapper);
+    }
+}
+
 impl Tile {
+    pub fn new(x: i32, y: i32) -> Tile {
+        Tile {
+            occupied: false,
+            x,
+            y,
+            neighbors: [None; 4],
+        }
+    }
+
     pub fn create_image_tile(image_name: String, ecs: &mut ECS, x: usize, y: usize) -> Entity {
         Self::create_texture_tile(ecs, image_name, x, y)
     }
@@ -23,32 +40,26 @@ impl Tile {
     }
 
     fn create_texture_tile(ecs: &mut ECS, image_name: String, x: usize, y: usize) -> Entity {
-        let sprite = SpriteComponent::new(ecs, image_name);
+        // Tiles sit behind everything else on the map
+        let sprite = SpriteComponent::new(ecs, image_name).with_depth(1.);
         let x = x as i32 - 5;
         let y = y as i32 - 3;
-        let tile = Tile {
-            occupied: false,
-            x: x,
-            y: y,
-            neighbors: [None; 4],
-        };
+        let tile = Tile::new(x, y);
         let transform = TransformComponent::builder()
             .position((x) as f32, (y) as f32)
             .scale(1.0, 1.0)
             .build();
+        let capture_area = MouseCaptureArea::new(
+            false,
+            Bounds {
+                x: x as f32 - 0.5,
+                y: y as f32 - 0.5,
+                w: 1.,
+                h: 1.,
+            },
+        );
         ecs.build_entity()
-            .add_component(tile)
-            .add_component(sprite)
-            .add_component(transform)
-            .add_component(MouseCaptureArea::new(
-                false,
-                Bounds {
-                    x: x as f32 - 0.5,
-                    y: y as f32 - 0.5,
-                    w: 1.,
-                    h: 1.,
-                },
-            ))
+            .add_bundle((tile, sprite, transform, capture_area))
             .entity()
     }
 }
@@ -58,7 +69,7 @@ pub struct Tilemap {
     borders: Vec<Entity>,
 }
 
-fn set_neighbors(tile: &mut Tile, x: usize, y: usize, tiles: &Vec<Vec<Entity>>) {
+fn set_neighbors(tile: &mut Tile, x: usize, y: usize, tiles: &[Vec<Entity>]) {
     tile.neighbors = [
         if y > 0 { Some(tiles[x][y - 1]) } else { None },
         if x < (tiles.len() - 1) {
@@ -75,13 +86,25 @@ fn set_neighbors(tile: &mut Tile, x: usize, y: usize, tiles: &Vec<Vec<Entity>>)
     ]
 }
 
+/// Points each tile in the grid, indexed by column then row, at the tiles around it.
+#[allow(clippy::needless_range_loop)]
+pub fn link_neighbors(ecs: &mut ECS, tiles: &[Vec<Entity>]) {
+    for x in 0..tiles.len() {
+        for y in 0..tiles[x].len() {
+            let t = ecs.write::<Tile>(&tiles[x][y]).unwrap();
+            set_neighbors(t, x, y, tiles);
+        }
+    }
+}
+
 impl Tilemap {
     pub fn borders(&self) -> &Vec<Entity> {
         return &self.borders;
     }
 
+    #[allow(clippy::needless_range_loop)]
     pub fn create(ecs: &mut ECS) {
-        let mut tiles: Vec<Vec<Entity>> = vec![Vec::with_capacity(9); 11];
+        let mut tiles: Vec<Vec<Entity>> = (0..11).map(|_| Vec::with_capacity(9)).collect();
         let mut center_tile = None;
         let mut borders = vec![];
         for x in 0..11 {
@@ -99,7 +122,7 @@ impl Tilemap {
                     Tile::create_image_tile("./assets/bitmap/grass.png".to_string(), ecs, x, y)
                 };
                 if x == 5 && y == 4 {
-                    center_tile = Some(tile.clone());
+                    center_tile = Some(tile);
                 }
                 tiles[x].push(tile);
             }
@@ -107,16 +130,11 @@ impl Tilemap {
         let mut all_tiles = vec![];
         for tiles in &tiles {
             for tile in tiles {
-                all_tiles.push(tile.clone());
+                all_tiles.push(*tile);
             }
         }
 
-        for x in 0..11 {
-            for y in 0..9 {
-                let t = ecs.write::<Tile>(&tiles[x][y]).unwrap();
-                set_neighbors(t, x, y, &tiles);
-            }
-        }
+        link_neighbors(ecs, &tiles);
         let capture_area = MouseCaptureLayer {
             sort_index: 0,
             capture_areas: all_tiles,
@@ -132,7 +150,7 @@ impl Tilemap {
     }
 
     pub fn tile_at_pos(&self, x: usize, y: usize) -> Entity {
-        self.tiles[x][y].clone()
+        self.tiles[x][y]
     }
 
     pub fn pos_of_tile(&self, tile: Entity) -> (f32, f32) {
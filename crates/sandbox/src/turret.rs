@@ -1,4 +1,4 @@
-use cgmath::{Vector2, Vector3};
+use cgmath::Vector3;
 use gouda::ecs::{Entity, Mutation, ECS};
 use gouda::rendering::sprites::SpriteComponent;
 use gouda::transform::TransformComponent;
@@ -17,7 +17,6 @@ impl Mutation for CreateTurretMutation {
             Turret::create(ecs, self.tile_e);
 
             ecs.write::<Tile>(&self.tile_e).unwrap().occupied = true;
-        } else {
         }
     }
 }
@@ -45,7 +44,7 @@ impl Mutation for TurretDeselectMutation {
     fn apply(&self, ecs: &mut ECS) {
         let turrets = ecs.get1::<Turret>();
         for turret in &turrets {
-            let turret = ecs.write::<Turret>(&turret).unwrap();
+            let turret = ecs.write::<Turret>(turret).unwrap();
             turret.selected = false;
             let indicator = turret.range_indicator;
             if let Some(e) = indicator {